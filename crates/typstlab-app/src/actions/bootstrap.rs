@@ -1,11 +1,14 @@
 use crate::actions::load::{LoadAction, LoadEvent};
 use crate::actions::toolchain_resolve::{
     ToolChain, ToolchainResolveAction, ToolchainResolveError, ToolchainResolveEvent,
-    ToolchainResolveInput,
+    ToolchainResolveInput, ToolchainResolveWarning,
+};
+use crate::models::{
+    DocsStore, Project, ProjectConfig, ProjectError, ProjectHandle, ToolchainMirrors, TypstStore,
 };
-use crate::models::{DocsStore, Project, ProjectConfig, ProjectError, ProjectHandle, TypstStore};
 use std::path::PathBuf;
 use thiserror::Error;
+use typstlab_base::link_resolver::{LinkResolveError, validate_url_template};
 use typstlab_proto::Loaded;
 use typstlab_proto::{Action, AppEvent, EventScope};
 
@@ -13,8 +16,71 @@ use typstlab_proto::{Action, AppEvent, EventScope};
 pub enum BootstrapError {
     #[error("Failed to load project: {0}")]
     ProjectLoadError(#[from] ProjectError),
-    #[error("Toolchain resolution failed: {0:?}")]
+    #[error("Toolchain resolution failed: {}", join_errors(.0))]
     ToolchainResolve(Vec<ToolchainResolveError>),
+    #[error("Invalid {var}: {source}")]
+    InvalidMirror {
+        var: &'static str,
+        #[source]
+        source: LinkResolveError,
+    },
+}
+
+fn join_errors(errors: &[ToolchainResolveError]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// オフラインモードを有効にする環境変数
+pub const OFFLINE_ENV: &str = "TYPSTLAB_OFFLINE";
+
+/// `TYPSTLAB_OFFLINE` が `1` / `true` / `yes` / `on` ならオフライン
+pub fn offline_from_env() -> bool {
+    std::env::var(OFFLINE_ENV).is_ok_and(|value| is_truthy(&value))
+}
+
+/// Typst のダウンロード URL テンプレートを上書きする環境変数
+pub const TYPST_MIRROR_ENV: &str = "TYPSTLAB_TYPST_MIRROR";
+/// docs のダウンロード URL テンプレートを上書きする環境変数
+pub const DOCS_MIRROR_ENV: &str = "TYPSTLAB_DOCS_MIRROR";
+
+/// 環境変数で指定されたミラー。空文字列は指定なし扱いで、
+/// `{version}` や `{asset}` を欠くテンプレートはエラーにする
+pub fn mirrors_from_env() -> Result<ToolchainMirrors, BootstrapError> {
+    let mut mirrors = ToolchainMirrors::default();
+    for (var, slot) in [
+        (TYPST_MIRROR_ENV, &mut mirrors.typst),
+        (DOCS_MIRROR_ENV, &mut mirrors.docs),
+    ] {
+        if let Some(template) = non_empty_env(var) {
+            validate_url_template(&template)
+                .map_err(|source| BootstrapError::InvalidMirror { var, source })?;
+            *slot = Some(template);
+        }
+    }
+    Ok(mirrors)
+}
+
+fn non_empty_env(key: &str) -> Option<String> {
+    std::env::var(key)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn is_truthy(value: &str) -> bool {
+    matches!(
+        value.trim().to_ascii_lowercase().as_str(),
+        "1" | "true" | "yes" | "on"
+    )
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BootstrapWarning {
+    Toolchain(ToolchainResolveWarning),
 }
 
 /// 起動プロセス中に発生するイベント
@@ -38,18 +104,22 @@ pub struct AppContext {
 pub struct BootstrapAction {
     pub project_root: PathBuf,
     pub cache_root: PathBuf,
+    /// true ならツールチェーンをダウンロードしない
+    pub offline: bool,
+    /// `[toolchain.mirrors]` より優先するミラー（環境変数など）
+    pub mirrors: ToolchainMirrors,
 }
 
 impl Action for BootstrapAction {
     type Output = AppContext;
     type Event = BootstrapEvent;
-    type Warning = ();
+    type Warning = BootstrapWarning;
     type Error = BootstrapError;
 
     fn run(
         self,
         monitor: &mut dyn FnMut(AppEvent<BootstrapEvent>),
-        _warning: &mut dyn FnMut(()),
+        warning: &mut dyn FnMut(BootstrapWarning),
     ) -> Result<Self::Output, Vec<Self::Error>> {
         let scope = EventScope::new("bootstrap");
         // 1. プロジェクトのロード
@@ -96,12 +166,15 @@ impl Action for BootstrapAction {
         let docs_store = DocsStore::new(self.cache_root.join("docs"));
 
         // 3. Toolchain 解決
+        let mut toolchain = loaded_project.toolchain().clone();
+        toolchain.mirrors = self.mirrors.or(toolchain.mirrors);
         let toolchain_action = ToolchainResolveAction {
             input: ToolchainResolveInput {
                 project_root: project_root.clone(),
-                toolchain: loaded_project.toolchain().clone(),
+                toolchain,
                 typst_store: typst_store.clone(),
                 docs_store: docs_store.clone(),
+                offline: self.offline,
             },
         };
         let toolchain = toolchain_action
@@ -109,7 +182,7 @@ impl Action for BootstrapAction {
                 &mut |e| {
                     monitor(e.map_payload(BootstrapEvent::ResolvingToolchain));
                 },
-                &mut |_| {},
+                &mut |w| warning(BootstrapWarning::Toolchain(w)),
             )
             .map_err(|errs| vec![BootstrapError::ToolchainResolve(errs)])?;
 
@@ -123,3 +196,18 @@ impl Action for BootstrapAction {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::is_truthy;
+
+    #[test]
+    fn test_offline_env_accepts_common_truthy_values() {
+        for value in ["1", "true", "YES", " on "] {
+            assert!(is_truthy(value), "{value}");
+        }
+        for value in ["", "0", "false", "off", "no"] {
+            assert!(!is_truthy(value), "{value}");
+        }
+    }
+}
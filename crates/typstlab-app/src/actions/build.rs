@@ -1,9 +1,24 @@
 use crate::actions::discovery::{DiscoveryAction, DiscoveryError};
+use crate::models::build_artifact_scope::OutputNaming;
 use crate::models::{
-    CollectionError, PaperError, PaperHandle, Project, ProjectConfig, ProjectHandle,
+    BuildArtifact, BuildArtifactScope, BuildCacheEntry, BuildProfile, CollectionError,
+    FormatManifest, FormatStatus, HookStage, HooksConfig, ManifestFile, OutputFormat, Paper,
+    PaperConfig, PaperError, PaperHandle, PaperManifest, Project, ProjectConfig, ProjectHandle,
 };
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
 use thiserror::Error;
-use typstlab_base::driver::{TypstCommand, TypstDriver};
+use typstlab_base::diagnostics::{BuildDiagnostic, DiagnosticSeverity, parse_diagnostics};
+use typstlab_base::digest::ContentDigest;
+use typstlab_base::driver::{
+    CompileOptions, DiagnosticFormat, ExecutionResult, TypstCommand, TypstDriver,
+};
+use typstlab_base::lock::FileLock;
+use typstlab_base::pdf::pdf_page_count;
+use typstlab_base::shell::run_shell;
+use typstlab_base::timings::{TimingPhase, summarize_trace};
 use typstlab_proto::{Action, AppEvent, Collection, Entity, EventScope, Loadable, Loaded};
 
 #[derive(Error, Debug)]
@@ -20,6 +35,23 @@ pub enum BuildError {
         #[source]
         source: PaperError,
     },
+    #[error("Unknown build profile '{0}' (define it under [profiles] in typstlab.toml)")]
+    UnknownProfile(String),
+    #[error("Failed to lock the project for building: {0}")]
+    LockFailed(#[source] std::io::Error),
+    #[error(
+        "{stage} hook `{command}`{} failed: {message}",
+        paper_id.as_ref().map(|id| format!(" for '{}'", id)).unwrap_or_default()
+    )]
+    HookFailed {
+        /// None ならプロジェクト全体のフック
+        paper_id: Option<String>,
+        stage: HookStage,
+        command: String,
+        /// 起動できなかった場合は None
+        exit_code: Option<i32>,
+        message: String,
+    },
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 }
@@ -36,6 +68,24 @@ pub struct DistObject {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum BuildWarning {
     NoTargetsFound,
+    /// ビルドキャッシュの書き込みに失敗した（ビルド自体は成功している）
+    CacheWriteFailed(String),
+    /// dist/manifest.json の作成に失敗した
+    ManifestWriteFailed(String),
+    /// `--timings` を指定したが Typst のバージョンが対応していない
+    TimingsUnsupported {
+        version: String,
+    },
+    /// タイミングトレースを読めなかった（成果物自体は出力済み）
+    TimingsUnreadable {
+        path: PathBuf,
+        reason: String,
+    },
+    /// 成功したコンパイルでコンパイラが報告した警告
+    CompilerWarning {
+        paper_id: String,
+        diagnostic: BuildDiagnostic,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -52,10 +102,31 @@ pub enum BuildEvent {
     Starting {
         paper_id: String,
     },
+    /// 同じプロジェクトの別のビルドが終わるのを待っている
+    WaitingForLock {
+        path: PathBuf,
+    },
+    /// `[hooks]` のコマンドを実行する（paper_id が None ならプロジェクト全体のフック）
+    RunningHook {
+        paper_id: Option<String>,
+        stage: HookStage,
+        command: String,
+    },
+    /// 入力が前回のビルドから変わっていないためスキップした
+    UpToDate {
+        paper_id: String,
+    },
     Finished {
         artifact: crate::models::BuildArtifact,
         duration_ms: u64,
     },
+    /// `--timings` で得たトレースの集計（時間のかかった順）
+    Timings {
+        paper_id: String,
+        format: &'static str,
+        trace: PathBuf,
+        phases: Vec<TimingPhase>,
+    },
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -94,13 +165,87 @@ impl BuildFormat {
         }
         formats
     }
+
+    /// paper.toml の `formats` から組み立てる（空なら既定の PDF のみ）
+    pub fn from_output_formats(formats: &[OutputFormat]) -> Self {
+        if formats.is_empty() {
+            return Self::default();
+        }
+        Self {
+            pdf: formats.contains(&OutputFormat::Pdf),
+            png: formats.contains(&OutputFormat::Png),
+            svg: formats.contains(&OutputFormat::Svg),
+            html: formats.contains(&OutputFormat::Html),
+        }
+    }
+}
+
+/// paper.toml の `[build]` より優先されるコンパイル設定（主に CLI フラグ由来）
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BuildOverrides {
+    pub ppi: Option<f32>,
+    pub pages: Option<String>,
+    /// 論文側の同じキーを上書きする
+    pub inputs: BTreeMap<String, String>,
+    /// 論文側のフォントパスより先に検索される
+    pub font_paths: Vec<PathBuf>,
+    pub pdf_standard: Option<String>,
+}
+
+/// 同時に走らせる `typst compile` の既定数（CPU 数）
+pub fn default_jobs() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+}
+
+/// 1 回の `typst compile` 呼び出しに相当する作業単位
+struct CompileJob {
+    paper_index: usize,
+    format: &'static str,
+    artifact: BuildArtifact,
+    command: TypstCommand,
+    output_path: PathBuf,
+    /// 成果物の名前付け（プロファイルの接尾辞込み）
+    naming: OutputNaming,
+    /// `--timings` のトレースの出力先
+    timings_path: Option<PathBuf>,
+}
+
+impl CompileJob {
+    /// 成果物の隣にタイミングトレースを出力させる
+    fn with_timings(mut self) -> Self {
+        let path = self
+            .artifact
+            .path()
+            .join(format!("{}.timings.json", self.naming.name));
+        if let TypstCommand::Compile { options, .. } = &mut self.command {
+            options.timings = Some(path.clone());
+        }
+        self.timings_path = Some(path);
+        self
+    }
+}
+
+enum JobMessage {
+    Started(usize),
+    Done(usize, Result<ExecutionResult, String>),
 }
 
 pub struct BuildAction {
     pub loaded_project: Loaded<Project, ProjectConfig>,
     pub typst_driver: TypstDriver,
     pub inputs: Option<Vec<String>>,
-    pub format: BuildFormat,
+    /// None なら論文ごとの `[build].formats` に従う
+    pub format: Option<BuildFormat>,
+    pub overrides: BuildOverrides,
+    /// typstlab.toml の `[profiles.<name>]` から選ぶビルド設定
+    pub profile: Option<String>,
+    pub jobs: usize,
+    /// true ならビルドキャッシュを無視して全て再ビルドする
+    pub force: bool,
+    /// true なら Typst にタイミングトレースを出力させる
+    pub timings: bool,
 }
 
 impl BuildAction {
@@ -108,15 +253,130 @@ impl BuildAction {
         loaded_project: Loaded<Project, ProjectConfig>,
         typst_driver: TypstDriver,
         inputs: Option<Vec<String>>,
-        format: BuildFormat,
+        format: Option<BuildFormat>,
     ) -> Self {
         Self {
             loaded_project,
             typst_driver,
             inputs,
             format,
+            overrides: BuildOverrides::default(),
+            profile: None,
+            jobs: default_jobs(),
+            force: false,
+            timings: false,
         }
     }
+
+    pub fn with_overrides(mut self, overrides: BuildOverrides) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
+    pub fn with_profile(mut self, profile: Option<String>) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    pub fn with_force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    pub fn with_timings(mut self, timings: bool) -> Self {
+        self.timings = timings;
+        self
+    }
+
+    /// 並列ワーカー数を指定する（0 は 1 として扱う）
+    pub fn with_jobs(mut self, jobs: usize) -> Self {
+        self.jobs = jobs.max(1);
+        self
+    }
+
+    /// `.typstlab/build.lock` を取る。他のビルドが保持していれば通知してから待つ。
+    fn acquire_lock(
+        &self,
+        monitor: &mut dyn FnMut(AppEvent<BuildEvent>),
+    ) -> std::io::Result<FileLock> {
+        let path = self.loaded_project.build_lock_path();
+        if let Some(lock) = FileLock::try_acquire(&path)? {
+            return Ok(lock);
+        }
+        monitor(AppEvent::line(
+            EventScope::new("build"),
+            BuildEvent::WaitingForLock { path: path.clone() },
+        ));
+        FileLock::acquire(&path)
+    }
+
+    /// `stage` のフックのコマンドを順に実行し、最初に失敗したところで止める
+    fn run_hooks(
+        &self,
+        paper_id: Option<&str>,
+        stage: HookStage,
+        hooks: &HooksConfig,
+        cwd: &Path,
+        env: &[(String, String)],
+        monitor: &mut dyn FnMut(AppEvent<BuildEvent>),
+    ) -> Result<(), BuildError> {
+        let scope = match paper_id {
+            Some(id) => EventScope::labeled("build", id.to_string()),
+            None => EventScope::new("build"),
+        };
+        for command in hooks.commands(stage) {
+            monitor(AppEvent::line(
+                scope.clone(),
+                BuildEvent::RunningHook {
+                    paper_id: paper_id.map(str::to_string),
+                    stage,
+                    command: command.clone(),
+                },
+            ));
+            let failed = |exit_code, message: String| BuildError::HookFailed {
+                paper_id: paper_id.map(str::to_string),
+                stage,
+                command: command.clone(),
+                exit_code,
+                message,
+            };
+            let result = run_shell(command, cwd, env).map_err(|e| failed(None, e.to_string()))?;
+            if result.exit_code != 0 {
+                let output = if result.stderr.trim().is_empty() {
+                    result.stdout
+                } else {
+                    result.stderr
+                };
+                return Err(failed(
+                    Some(result.exit_code),
+                    format!("exited with code {}: {}", result.exit_code, output.trim()),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// 全てのフックに渡す環境変数
+    fn hook_env(&self, artifact_scope: &BuildArtifactScope) -> Vec<(String, String)> {
+        let mut env = vec![
+            (
+                "TYPSTLAB_PROJECT_ROOT".to_string(),
+                self.loaded_project
+                    .actual
+                    .root
+                    .to_string_lossy()
+                    .to_string(),
+            ),
+            (
+                "TYPSTLAB_DIST".to_string(),
+                artifact_scope.path().to_string_lossy().to_string(),
+            ),
+        ];
+        if let Some(profile) = &self.profile {
+            env.push(("TYPSTLAB_PROFILE".to_string(), profile.clone()));
+        }
+        env
+    }
 }
 
 impl Action for BuildAction {
@@ -140,6 +400,15 @@ impl Action for BuildAction {
             },
         ));
 
+        // 1. プロファイルの解決
+        let profile = match &self.profile {
+            Some(name) => match self.loaded_project.profile(name) {
+                Some(profile) => Some(profile.clone()),
+                None => return Err(vec![BuildError::UnknownProfile(name.clone())]),
+            },
+            None => None,
+        };
+
         // 2. ターゲットの特定
         let targets = if let Some(inputs) = &self.inputs {
             monitor(AppEvent::verbose(
@@ -173,11 +442,73 @@ impl Action for BuildAction {
             },
         ));
 
-        // 4. 成果物領土の準備
-        let artifact_scope = self.loaded_project.build_artifact_scope();
+        // 3. 同じプロジェクトを同時にビルドしないようロックを取る（drop まで保持）
+        let _lock = match self.acquire_lock(monitor) {
+            Ok(lock) => lock,
+            Err(e) => return Err(vec![BuildError::LockFailed(e)]),
+        };
+
+        // 4. 成果物領土とビルドキャッシュの準備
+        let artifact_scope = match profile.as_ref().and_then(|p| p.dist_subdir.as_deref()) {
+            Some(subdir) => self.loaded_project.build_artifact_scope().subdir(subdir),
+            None => self.loaded_project.build_artifact_scope(),
+        };
+        let shared_dirs = self.loaded_project.shared_source_dirs();
+        // `--root` でプロジェクトルートを渡す場合、Typst はその下の任意のファイルを読めるため
+        // 成果物とキャッシュ以外の全体を指紋に含める
+        let compile_root = self.loaded_project.compile_root();
+        let fingerprint_excluded = vec![
+            self.loaded_project.build_artifact_scope().path(),
+            self.loaded_project.cache_dir(),
+            self.loaded_project.actual.root.join(".git"),
+        ];
+        let mut cache = self.loaded_project.build_cache();
+        // バージョンが取れない場合はキャッシュを使わない（コンパイル側で失敗として報告される）
+        let typst_version = self.typst_driver.get_version().ok();
+        // 対応バージョンならヒントも読める human 形式を明示して診断を受け取る
+        let diagnostic_format = typst_version
+            .as_ref()
+            .and_then(DiagnosticFormat::preferred_for);
+        // 非対応のバージョンではトレース無しでビルドする
+        let timings = match &typst_version {
+            Some(version) if self.timings => {
+                let supported = CompileOptions::timings_required_version()
+                    .is_ok_and(|requirement| requirement.matches(version));
+                if !supported {
+                    warning(BuildWarning::TimingsUnsupported {
+                        version: version.to_string(),
+                    });
+                }
+                supported
+            }
+            _ => false,
+        };
+        let typst_version = typst_version.map(|version| version.to_string());
         let mut results = Vec::new();
+        let mut states = Vec::new();
+        let mut jobs = Vec::new();
+        // ジョブごとの作業場所。成功したものだけが dist へ反映され、残りは drop で消える
+        let mut stagings = Vec::new();
+        // 論文ごとの dist/manifest.json 用の記録
+        let mut manifests: Vec<Vec<FormatManifest>> = Vec::new();
+        let dist_root = artifact_scope.path();
+        // 論文ごとの post_build フック用の作業ディレクトリと設定
+        let mut paper_hooks = Vec::new();
 
-        // 5. 各ターゲットのビルド実行
+        // プロジェクトの pre_build フック。失敗すればビルド全体を中止する
+        let hook_env = self.hook_env(&artifact_scope);
+        if let Err(e) = self.run_hooks(
+            None,
+            HookStage::PreBuild,
+            self.loaded_project.hooks(),
+            &self.loaded_project.actual.root,
+            &hook_env,
+            monitor,
+        ) {
+            return Err(vec![e]);
+        }
+
+        // 5. 各ターゲットのコンパイル単位と作業場所を組み立てる
         for paper in targets {
             let paper_id = paper.id.clone();
             let loaded_paper = match paper.load() {
@@ -188,108 +519,367 @@ impl Action for BuildAction {
                 }
             };
 
-            monitor(AppEvent::line(
-                scope.clone(),
-                BuildEvent::Starting {
-                    paper_id: loaded_paper.paper_id().to_string(),
-                },
-            ));
+            // 生成物が指紋に含まれるよう、論文の pre_build フックは計画より先に実行する
+            let paper_id = loaded_paper.paper_id().to_string();
+            let paper_dir = loaded_paper.actual.path();
+            let mut env = hook_env.clone();
+            env.extend(paper_hook_env(&artifact_scope, &paper_id, &paper_dir));
+            if let Err(e) = self.run_hooks(
+                Some(&paper_id),
+                HookStage::PreBuild,
+                loaded_paper.hooks(),
+                &paper_dir,
+                &env,
+                monitor,
+            ) {
+                errors.push(e);
+                continue;
+            }
+            paper_hooks.push((paper_dir, loaded_paper.hooks().clone()));
 
-            let mut dist_obj = DistObject {
-                paper_id: loaded_paper.paper_id().to_string(),
+            let paper_index = results.len();
+            results.push(DistObject {
+                paper_id: paper_id.clone(),
                 pdf: None,
                 png: None,
                 svg: None,
                 html: None,
+            });
+            manifests.push(Vec::new());
+
+            // フォーマットは CLI > プロファイル > paper.toml の順に決まる
+            let format = self.format.unwrap_or_else(|| {
+                profile
+                    .as_ref()
+                    .and_then(|p| p.formats.as_deref())
+                    .or(loaded_paper.build_config().formats.as_deref())
+                    .map(BuildFormat::from_output_formats)
+                    .unwrap_or_default()
+            });
+            let options = compile_options(
+                &self.loaded_project,
+                &loaded_paper,
+                profile.as_ref(),
+                &self.overrides,
+            );
+            let naming = OutputNaming::new(
+                loaded_paper.output_base_name(),
+                profile.as_ref().and_then(|p| p.output_suffix.as_deref()),
+            );
+            let cache_key = match &self.profile {
+                Some(name) => format!("{}@{}", paper_id, name),
+                None => paper_id.clone(),
             };
+            let planned: Vec<CompileJob> = format
+                .active_formats()
+                .into_iter()
+                .map(|fmt| {
+                    let job = plan_job(
+                        paper_index,
+                        &loaded_paper,
+                        &artifact_scope,
+                        &naming,
+                        fmt,
+                        diagnostic_format,
+                        &options,
+                    );
+                    if timings { job.with_timings() } else { job }
+                })
+                .collect();
 
-            for fmt in self.format.active_formats() {
-                // 領土階層から成果物実体（Artifact）を生成
-                let mut artifact = artifact_scope
-                    .paper_scope(loaded_paper.paper_id())
-                    .format_artifact(fmt);
-
-                // 以前の出力（特に複数ページの画像）をクリーンアップ
-                if artifact.path().exists()
-                    && let Err(e) = std::fs::remove_dir_all(artifact.path())
-                {
-                    errors.push(BuildError::IoError(e));
-                    continue;
-                }
+            let fingerprint = typst_version.as_deref().and_then(|version| {
+                let mut sources = match &compile_root {
+                    Some(root) => vec![root.clone()],
+                    None => vec![loaded_paper.actual.path()],
+                };
+                sources.extend(
+                    shared_dirs
+                        .iter()
+                        .filter(|dir| {
+                            !compile_root
+                                .as_ref()
+                                .is_some_and(|root| dir.starts_with(root))
+                        })
+                        .cloned(),
+                );
+                fingerprint(&sources, &fingerprint_excluded, &planned, version).ok()
+            });
 
-                if let Err(e) = std::fs::create_dir_all(artifact.path()) {
-                    errors.push(BuildError::IoError(e));
-                    continue;
+            // 入力・バージョン・フォーマットが前回と同じで成果物も残っていればスキップ。
+            // トレースはコンパイルしないと取れないので --timings の時もスキップしない
+            if !self.force
+                && !timings
+                && let Some(entry) = &fingerprint
+                && cache.is_fresh(&cache_key, entry)
+                && planned.iter().all(|job| !output_files(job).is_empty())
+            {
+                for job in &planned {
+                    apply_outputs(&mut results[paper_index], job.format, output_files(job));
+                    match format_manifest(&dist_root, job, FormatStatus::UpToDate, None) {
+                        Ok(entry) => manifests[paper_index].push(entry),
+                        Err(e) => warning(BuildWarning::ManifestWriteFailed(e.to_string())),
+                    }
                 }
+                monitor(AppEvent::line(
+                    EventScope::labeled("build", paper_id.clone()),
+                    BuildEvent::UpToDate { paper_id },
+                ));
+                states.push(PaperState {
+                    cache_key,
+                    fingerprint: None,
+                    up_to_date: true,
+                    failed: false,
+                });
+                continue;
+            }
 
-                let output_filename = match fmt {
-                    "pdf" => format!("{}.pdf", loaded_paper.output_base_name()),
-                    "png" => "{0p}.png".to_string(),
-                    "svg" => "{0p}.svg".to_string(),
-                    "html" => format!("{}.html", loaded_paper.output_base_name()),
-                    _ => unreachable!(),
-                };
+            let mut state = PaperState {
+                cache_key,
+                fingerprint,
+                up_to_date: false,
+                failed: false,
+            };
+            for job in planned {
+                let staging_id = format!("{}-{}", paper_id, job.format);
+                match artifact_scope.create_staging_area(&staging_id) {
+                    Ok(staging) => {
+                        jobs.push(job);
+                        stagings.push(Some(staging));
+                    }
+                    Err(e) => {
+                        errors.push(BuildError::IoError(e));
+                        state.failed = true;
+                    }
+                }
+            }
+            states.push(state);
+        }
 
-                let output_path = artifact.path().join(output_filename);
-                let features = if fmt == "html" {
-                    vec!["html".to_string()]
-                } else {
-                    vec![]
-                };
+        // 6. ワーカープールでコンパイルを並列実行する
+        //    イベントはメインスレッドから発行し、結果はジョブ順に並べ直す
+        let mut outcomes: Vec<Option<Result<ExecutionResult, String>>> =
+            (0..jobs.len()).map(|_| None).collect();
+        let staging_dirs: Vec<PathBuf> = stagings
+            .iter()
+            .flatten()
+            .map(|staging| staging.path().to_path_buf())
+            .collect();
+        let mut started = vec![false; results.len()];
+        let workers = self.jobs.clamp(1, jobs.len().max(1));
+        let next_job = AtomicUsize::new(0);
+        let (tx, rx) = mpsc::channel();
 
-                let command = TypstCommand::Compile {
-                    source: loaded_paper.main_typ_path(),
-                    output: Some(output_path.clone()),
-                    features,
-                };
+        std::thread::scope(|s| {
+            for _ in 0..workers {
+                let tx = tx.clone();
+                let next_job = &next_job;
+                let jobs = &jobs;
+                let staging_dirs = &staging_dirs;
+                let driver = &self.typst_driver;
+                s.spawn(move || {
+                    loop {
+                        let index = next_job.fetch_add(1, Ordering::SeqCst);
+                        let Some(job) = jobs.get(index) else {
+                            break;
+                        };
+                        if tx.send(JobMessage::Started(index)).is_err() {
+                            break;
+                        }
+                        let result = driver
+                            .execute(staged_command(job, &staging_dirs[index]))
+                            .map_err(|e| e.to_string());
+                        if tx.send(JobMessage::Done(index, result)).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            drop(tx);
 
-                match self.typst_driver.execute(command) {
-                    Ok(res) if res.exit_code == 0 => {
-                        artifact.success = true;
-                        monitor(AppEvent::line(
-                            scope.clone(),
-                            BuildEvent::Finished {
-                                artifact: artifact.clone(),
-                                duration_ms: res.duration_ms,
-                            },
-                        ));
-                        match fmt {
-                            "pdf" => dist_obj.pdf = Some(output_path),
-                            "html" => dist_obj.html = Some(output_path),
-                            "png" | "svg" => {
-                                let mut files = Vec::new();
-                                if let Ok(entries) = std::fs::read_dir(artifact.path()) {
-                                    for entry in entries.flatten() {
-                                        if entry.path().extension().and_then(|e| e.to_str())
-                                            == Some(fmt)
-                                        {
-                                            files.push(entry.path());
-                                        }
-                                    }
-                                }
-                                files.sort();
-                                if fmt == "png" {
-                                    dist_obj.png = Some(files);
-                                } else {
-                                    dist_obj.svg = Some(files);
+            for message in rx {
+                match message {
+                    JobMessage::Started(index) => {
+                        let job = &jobs[index];
+                        if !started[job.paper_index] {
+                            started[job.paper_index] = true;
+                            let paper_id = results[job.paper_index].paper_id.clone();
+                            monitor(AppEvent::line(
+                                EventScope::labeled("build", paper_id.clone()),
+                                BuildEvent::Starting { paper_id },
+                            ));
+                        }
+                    }
+                    JobMessage::Done(index, mut result) => {
+                        let job = &jobs[index];
+                        // 成功した出力だけを作業場所から dist へ反映する
+                        if let Ok(res) = &result
+                            && res.exit_code == 0
+                            && let Some(staging) = stagings[index].take()
+                            && let Err(e) =
+                                artifact_scope.commit_staged(&job.artifact, staging, &job.naming)
+                        {
+                            result = Err(format!("Failed to publish build output: {}", e));
+                        }
+                        if let Ok(res) = &result
+                            && res.exit_code == 0
+                        {
+                            let mut artifact = job.artifact.clone();
+                            artifact.success = true;
+                            monitor(AppEvent::line(
+                                EventScope::labeled(
+                                    "build",
+                                    results[job.paper_index].paper_id.clone(),
+                                ),
+                                BuildEvent::Finished {
+                                    artifact,
+                                    duration_ms: res.duration_ms,
+                                },
+                            ));
+                            if let Some(trace) = &job.timings_path {
+                                let paper_id = results[job.paper_index].paper_id.clone();
+                                match read_timings(trace) {
+                                    Ok(phases) => monitor(AppEvent::line(
+                                        EventScope::labeled("build", paper_id.clone()),
+                                        BuildEvent::Timings {
+                                            paper_id,
+                                            format: job.format,
+                                            trace: trace.clone(),
+                                            phases,
+                                        },
+                                    )),
+                                    Err(reason) => warning(BuildWarning::TimingsUnreadable {
+                                        path: trace.clone(),
+                                        reason,
+                                    }),
                                 }
                             }
-                            _ => {}
                         }
+                        outcomes[index] = Some(result);
                     }
-                    Ok(res) => {
-                        artifact.success = false;
-                        artifact.error_message = Some(res.stderr);
-                        errors.push(BuildError::PaperBuildError(artifact));
-                    }
-                    Err(e) => {
-                        artifact.success = false;
-                        artifact.error_message = Some(e.to_string());
-                        errors.push(BuildError::PaperBuildError(artifact));
+                }
+            }
+        });
+
+        // 7. 結果を論文・フォーマット順に DistObject へ反映する
+        //    同じ論文の警告はフォーマットごとに重複するため 1 度だけ通知する
+        let mut reported_warnings = HashSet::new();
+        for (job, outcome) in jobs.into_iter().zip(outcomes) {
+            let paper_index = job.paper_index;
+            let mut artifact = job.artifact.clone();
+
+            let (status, duration_ms) = match &outcome {
+                Some(Ok(res)) if res.exit_code == 0 => (FormatStatus::Built, Some(res.duration_ms)),
+                Some(Ok(res)) => (FormatStatus::Failed, Some(res.duration_ms)),
+                _ => (FormatStatus::Failed, None),
+            };
+            match format_manifest(&dist_root, &job, status, duration_ms) {
+                Ok(entry) => manifests[paper_index].push(entry),
+                Err(e) => warning(BuildWarning::ManifestWriteFailed(e.to_string())),
+            }
+
+            match outcome {
+                Some(Ok(res)) if res.exit_code == 0 => {
+                    artifact.success = true;
+                    apply_outputs(&mut results[paper_index], job.format, output_files(&job));
+                    for diagnostic in parse_diagnostics(&res.stderr) {
+                        if diagnostic.severity == DiagnosticSeverity::Warning
+                            && reported_warnings.insert((paper_index, diagnostic.clone()))
+                        {
+                            warning(BuildWarning::CompilerWarning {
+                                paper_id: results[paper_index].paper_id.clone(),
+                                diagnostic,
+                            });
+                        }
                     }
+                    continue;
+                }
+                Some(Ok(res)) => {
+                    artifact.diagnostics = parse_diagnostics(&res.stderr);
+                    artifact.error_message = Some(res.stderr);
+                }
+                Some(Err(message)) => {
+                    artifact.error_message = Some(message);
+                }
+                None => {
+                    artifact.error_message = Some("compile job did not run".to_string());
                 }
             }
-            results.push(dist_obj);
+            artifact.success = false;
+            states[paper_index].failed = true;
+            errors.push(BuildError::PaperBuildError(artifact));
+        }
+
+        // 反映されなかった作業場所を捨て、dist に空の置き場を残さない
+        drop(stagings);
+        artifact_scope.remove_staging_root();
+
+        // 8. 再ビルドした論文の指紋をキャッシュへ記録する
+        if typst_version.is_some() {
+            for state in &states {
+                if state.up_to_date {
+                    continue;
+                }
+                match (&state.fingerprint, state.failed) {
+                    (Some(entry), false) => cache.record(&state.cache_key, entry.clone()),
+                    _ => cache.forget(&state.cache_key),
+                }
+            }
+            if let Err(e) = cache.save() {
+                warning(BuildWarning::CacheWriteFailed(e.to_string()));
+            }
+        }
+
+        // 9. 今回の結果を dist/manifest.json に反映する（他の論文の記録は残す）
+        let built_at = chrono::Utc::now().to_rfc3339();
+        let mut manifest = artifact_scope.manifest();
+        for ((dist, state), formats) in results.iter().zip(&states).zip(manifests) {
+            manifest.record(
+                &dist.paper_id,
+                PaperManifest {
+                    success: !state.failed,
+                    typst_version: typst_version.clone(),
+                    built_at: built_at.clone(),
+                    formats,
+                },
+            );
+        }
+        if let Err(e) = manifest.save() {
+            warning(BuildWarning::ManifestWriteFailed(e.to_string()));
+        }
+
+        // 10. 成功した論文の post_build フック、全体が成功したらプロジェクトの post_build フック
+        for ((dist, state), (paper_dir, hooks)) in results.iter().zip(&states).zip(&paper_hooks) {
+            if state.failed {
+                continue;
+            }
+            let mut env = hook_env.clone();
+            env.extend(paper_hook_env(&artifact_scope, &dist.paper_id, paper_dir));
+            env.extend(dist_hook_env(dist));
+            if let Err(e) = self.run_hooks(
+                Some(&dist.paper_id),
+                HookStage::PostBuild,
+                hooks,
+                paper_dir,
+                &env,
+                monitor,
+            ) {
+                errors.push(e);
+            }
+        }
+        if errors.is_empty() {
+            let mut env = hook_env;
+            let paper_ids: Vec<&str> = results.iter().map(|dist| dist.paper_id.as_str()).collect();
+            env.push(("TYPSTLAB_PAPER_IDS".to_string(), paper_ids.join(" ")));
+            if let Err(e) = self.run_hooks(
+                None,
+                HookStage::PostBuild,
+                self.loaded_project.hooks(),
+                &self.loaded_project.actual.root,
+                &env,
+                monitor,
+            ) {
+                errors.push(e);
+            }
         }
 
         if errors.is_empty() {
@@ -300,6 +890,286 @@ impl Action for BuildAction {
     }
 }
 
+/// 論文 1 本分のキャッシュ判定とビルド結果
+struct PaperState {
+    /// プロファイルごとに別エントリとして記録するためのキー
+    cache_key: String,
+    fingerprint: Option<BuildCacheEntry>,
+    up_to_date: bool,
+    failed: bool,
+}
+
+/// 論文ごとのフックに渡す環境変数
+fn paper_hook_env(
+    artifact_scope: &BuildArtifactScope,
+    paper_id: &str,
+    paper_dir: &Path,
+) -> Vec<(String, String)> {
+    vec![
+        ("TYPSTLAB_PAPER_ID".to_string(), paper_id.to_string()),
+        (
+            "TYPSTLAB_PAPER_DIR".to_string(),
+            paper_dir.to_string_lossy().to_string(),
+        ),
+        (
+            "TYPSTLAB_PAPER_DIST".to_string(),
+            artifact_scope
+                .paper_scope(paper_id)
+                .path()
+                .to_string_lossy()
+                .to_string(),
+        ),
+    ]
+}
+
+/// post_build フックに渡す成果物のパス（PNG/SVG はパス区切り文字で連結する）
+fn dist_hook_env(dist: &DistObject) -> Vec<(String, String)> {
+    let mut env = Vec::new();
+    let single = [("TYPSTLAB_PDF", &dist.pdf), ("TYPSTLAB_HTML", &dist.html)];
+    for (key, path) in single {
+        if let Some(path) = path {
+            env.push((key.to_string(), path.to_string_lossy().to_string()));
+        }
+    }
+    let pages = [("TYPSTLAB_PNG", &dist.png), ("TYPSTLAB_SVG", &dist.svg)];
+    for (key, paths) in pages {
+        if let Some(paths) = paths
+            && let Ok(joined) = std::env::join_paths(paths)
+        {
+            env.push((key.to_string(), joined.to_string_lossy().to_string()));
+        }
+    }
+    env
+}
+
+/// 論文の `[build]` 設定にプロファイルと上書き設定を重ねたコンパイルオプション
+/// (フォントは CLI > 論文 > プロジェクトの順に検索される)
+pub(crate) fn compile_options(
+    loaded_project: &Loaded<Project, ProjectConfig>,
+    loaded_paper: &Loaded<Paper, PaperConfig>,
+    profile: Option<&BuildProfile>,
+    overrides: &BuildOverrides,
+) -> CompileOptions {
+    let build = loaded_paper.build_config();
+    let mut inputs = build.inputs.clone();
+    if let Some(profile) = profile {
+        inputs.extend(profile.inputs.clone());
+    }
+    inputs.extend(overrides.inputs.clone());
+    let mut font_paths = overrides.font_paths.clone();
+    font_paths.extend(loaded_paper.font_paths());
+    font_paths.extend(
+        loaded_project
+            .font_dirs()
+            .into_iter()
+            .filter(|dir| dir.is_dir()),
+    );
+
+    CompileOptions {
+        root: loaded_project.compile_root(),
+        ppi: overrides.ppi.or(build.ppi),
+        pages: overrides.pages.clone().or_else(|| build.pages.clone()),
+        inputs,
+        font_paths,
+        ignore_system_fonts: loaded_project.fonts().ignore_system_fonts,
+        pdf_standard: overrides
+            .pdf_standard
+            .clone()
+            .or_else(|| build.pdf_standard.clone()),
+        ..Default::default()
+    }
+}
+
+/// ビルドと同じコンパイルコマンドを、出力先だけ `dir` の中に差し替えて組み立てる
+pub(crate) fn throwaway_command(
+    loaded_paper: &Loaded<Paper, PaperConfig>,
+    artifact_scope: &BuildArtifactScope,
+    fmt: &'static str,
+    diagnostic_format: Option<DiagnosticFormat>,
+    options: &CompileOptions,
+    dir: &Path,
+) -> TypstCommand {
+    let job = plan_job(
+        0,
+        loaded_paper,
+        artifact_scope,
+        &OutputNaming::new(loaded_paper.output_base_name(), None),
+        fmt,
+        diagnostic_format,
+        options,
+    );
+    staged_command(&job, dir)
+}
+
+/// フォーマットごとの出力先とコンパイルコマンドを組み立てる（ファイルシステムには触れない）
+fn plan_job(
+    paper_index: usize,
+    loaded_paper: &Loaded<Paper, PaperConfig>,
+    artifact_scope: &BuildArtifactScope,
+    naming: &OutputNaming,
+    fmt: &'static str,
+    diagnostic_format: Option<DiagnosticFormat>,
+    options: &CompileOptions,
+) -> CompileJob {
+    // 領土階層から成果物実体（Artifact）を生成
+    let artifact = artifact_scope
+        .paper_scope(loaded_paper.paper_id())
+        .format_artifact(fmt);
+
+    let output_filename = match fmt {
+        "pdf" => format!("{}.pdf", naming.name),
+        "png" | "svg" => naming.page_template(fmt),
+        "html" => format!("{}.html", naming.name),
+        _ => unreachable!(),
+    };
+
+    let output_path = artifact.path().join(output_filename);
+    let features = if fmt == "html" {
+        vec!["html".to_string()]
+    } else {
+        vec![]
+    };
+
+    // フォーマットに関係しないオプションは渡さない (Typst が拒否するため)
+    let options = CompileOptions {
+        ppi: options.ppi.filter(|_| fmt == "png"),
+        pages: options.pages.clone().filter(|_| fmt != "html"),
+        pdf_standard: options.pdf_standard.clone().filter(|_| fmt == "pdf"),
+        ..options.clone()
+    };
+
+    let command = TypstCommand::Compile {
+        source: loaded_paper.main_typ_path(),
+        output: Some(output_path.clone()),
+        features,
+        diagnostic_format,
+        options: Box::new(options),
+    };
+
+    CompileJob {
+        paper_index,
+        format: fmt,
+        artifact,
+        command,
+        output_path,
+        naming: naming.clone(),
+        timings_path: None,
+    }
+}
+
+/// 出力先（とトレース）を作業場所に差し替えたコンパイルコマンド
+fn staged_command(job: &CompileJob, staging_dir: &Path) -> TypstCommand {
+    let mut command = job.command.clone();
+    if let TypstCommand::Compile {
+        output, options, ..
+    } = &mut command
+    {
+        if let Some(output) = output
+            && let Some(file_name) = job.output_path.file_name()
+        {
+            *output = staging_dir.join(file_name);
+        }
+        if let Some(timings) = &mut options.timings
+            && let Some(file_name) = timings.file_name()
+        {
+            *timings = staging_dir.join(file_name);
+        }
+    }
+    command
+}
+
+fn read_timings(path: &Path) -> Result<Vec<TimingPhase>, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    summarize_trace(&content).map_err(|e| e.to_string())
+}
+
+/// 入力ディレクトリ（`excluded` 以下を除く）とコンパイル引数から論文の指紋を計算する
+fn fingerprint(
+    sources: &[PathBuf],
+    excluded: &[PathBuf],
+    jobs: &[CompileJob],
+    typst_version: &str,
+) -> std::io::Result<BuildCacheEntry> {
+    let mut digest = ContentDigest::new();
+    for dir in sources {
+        digest.update_str(&dir.to_string_lossy());
+        digest.update_path_excluding(dir, excluded)?;
+    }
+    for job in jobs {
+        for arg in job.command.to_args() {
+            digest.update_str(&arg);
+        }
+    }
+
+    Ok(BuildCacheEntry {
+        inputs_hash: digest.finish(),
+        typst_version: typst_version.to_string(),
+        formats: jobs.iter().map(|job| job.format.to_string()).collect(),
+    })
+}
+
+/// ジョブの出力として現在ディスク上にあるファイル
+fn output_files(job: &CompileJob) -> Vec<PathBuf> {
+    match job.format {
+        "png" | "svg" => {
+            let mut files = Vec::new();
+            if let Ok(entries) = std::fs::read_dir(job.artifact.path()) {
+                for entry in entries.flatten() {
+                    if entry.path().extension().and_then(|e| e.to_str()) == Some(job.format)
+                        && job.naming.is_page(&entry.file_name().to_string_lossy())
+                    {
+                        files.push(entry.path());
+                    }
+                }
+            }
+            files.sort();
+            files
+        }
+        _ if job.output_path.is_file() => vec![job.output_path.clone()],
+        _ => Vec::new(),
+    }
+}
+
+/// ジョブの出力を dist/manifest.json 用に記述する
+fn format_manifest(
+    dist_root: &Path,
+    job: &CompileJob,
+    status: FormatStatus,
+    duration_ms: Option<u64>,
+) -> std::io::Result<FormatManifest> {
+    let files = output_files(job);
+    let page_count = match job.format {
+        _ if files.is_empty() => None,
+        "pdf" => std::fs::read(&files[0])
+            .ok()
+            .and_then(|bytes| pdf_page_count(&bytes)),
+        "png" | "svg" => Some(files.len()),
+        _ => None,
+    };
+    let files = files
+        .iter()
+        .map(|file| ManifestFile::read(dist_root, file))
+        .collect::<std::io::Result<Vec<_>>>()?;
+
+    Ok(FormatManifest {
+        format: job.format.to_string(),
+        status,
+        duration_ms,
+        page_count,
+        files,
+    })
+}
+
+fn apply_outputs(dist_obj: &mut DistObject, fmt: &str, files: Vec<PathBuf>) {
+    match fmt {
+        "pdf" => dist_obj.pdf = files.into_iter().next(),
+        "html" => dist_obj.html = files.into_iter().next(),
+        "png" => dist_obj.png = Some(files),
+        "svg" => dist_obj.svg = Some(files),
+        _ => {}
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{BuildAction, BuildWarning};
@@ -321,6 +1191,7 @@ mod tests {
                 },
                 toolchain: ProjectToolChain::default(),
                 structure: StructureConfig::default(),
+                ..Default::default()
             },
         }
     }
@@ -334,7 +1205,7 @@ mod tests {
         let project = loaded_project(temp.path());
         let driver = TypstDriver::new(PathBuf::from("typst"));
 
-        let action = BuildAction::new(project, driver, None, Default::default());
+        let action = BuildAction::new(project, driver, None, None);
         let mut warnings = Vec::new();
 
         let result = action.run(&mut |_| {}, &mut |warning| warnings.push(warning));
@@ -343,6 +1214,688 @@ mod tests {
         assert_eq!(warnings, vec![BuildWarning::NoTargetsFound]);
     }
 
+    /// `--version` と `compile` だけを真似る偽の typst バイナリを用意する
+    /// (`<binary>.stderr` があればその内容を stderr に出し、`<binary>.fail` があれば失敗する)
+    #[cfg(unix)]
+    fn fake_typst(dir: &std::path::Path) -> PathBuf {
+        crate::actions::test_support::fake_typst(
+            dir,
+            "[ -f \"$0.stderr\" ] && cat \"$0.stderr\" >&2\n\
+             [ -f \"$0.fail\" ] && exit 1\n\
+             for arg; do case \"$arg\" in --timings=*) printf '[{\"name\":\"typeset\",\"ph\":\"X\",\"ts\":0,\"dur\":2000}]' > \"${arg#--timings=}\";; esac; done\n\
+             for last; do :; done\n\
+             out=$(printf '%s' \"$last\" | sed 's/{0p}/1/')\n\
+             printf 'ok' > \"$out\"\n",
+        )
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_parallel_build_keeps_dist_order_and_labels_events() {
+        use super::{BuildEvent, BuildFormat};
+
+        let temp = TempDir::new().unwrap();
+        for id in ["p03", "p01", "p02"] {
+            let paper = temp.path().join("papers").join(id);
+            fs::create_dir_all(&paper).unwrap();
+            fs::write(paper.join("paper.toml"), "[paper]\ntitle = \"Demo\"\n").unwrap();
+            fs::write(paper.join("main.typ"), "= Demo").unwrap();
+        }
+        let driver = TypstDriver::new(fake_typst(temp.path()));
+        let format = BuildFormat {
+            pdf: true,
+            png: true,
+            svg: false,
+            html: false,
+        };
+
+        let action =
+            BuildAction::new(loaded_project(temp.path()), driver, None, Some(format)).with_jobs(4);
+        let mut started = Vec::new();
+        let result = action.run(
+            &mut |event| {
+                if let BuildEvent::Starting { paper_id } = &event.payload {
+                    assert_eq!(event.scope.label.as_deref(), Some(paper_id.as_str()));
+                    started.push(paper_id.clone());
+                }
+            },
+            &mut |_| {},
+        );
+
+        let dist = result.unwrap();
+        let ids: Vec<_> = dist.iter().map(|d| d.paper_id.as_str()).collect();
+        assert_eq!(ids, vec!["p01", "p02", "p03"]);
+        started.sort();
+        assert_eq!(started, vec!["p01", "p02", "p03"]);
+        for obj in &dist {
+            assert!(obj.pdf.as_ref().unwrap().exists());
+            assert_eq!(obj.png.as_ref().unwrap().len(), 1);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unchanged_papers_are_reported_up_to_date() {
+        use super::BuildEvent;
+
+        let temp = TempDir::new().unwrap();
+        let paper = temp.path().join("papers").join("p01");
+        fs::create_dir_all(&paper).unwrap();
+        fs::write(paper.join("paper.toml"), "[paper]\ntitle = \"Demo\"\n").unwrap();
+        fs::write(paper.join("main.typ"), "= Demo").unwrap();
+        let typst = fake_typst(temp.path());
+
+        let build = |force: bool| {
+            let action = BuildAction::new(
+                loaded_project(temp.path()),
+                TypstDriver::new(typst.clone()),
+                None,
+                None,
+            )
+            .with_force(force);
+            let mut up_to_date = Vec::new();
+            let dist = action
+                .run(
+                    &mut |event| {
+                        if let BuildEvent::UpToDate { paper_id } = event.payload {
+                            up_to_date.push(paper_id);
+                        }
+                    },
+                    &mut |_| {},
+                )
+                .unwrap();
+            assert!(dist[0].pdf.as_ref().unwrap().exists());
+            up_to_date
+        };
+
+        assert!(build(false).is_empty());
+        assert_eq!(build(false), vec!["p01"]);
+        assert!(build(true).is_empty());
+
+        fs::write(paper.join("main.typ"), "= Changed").unwrap();
+        assert!(build(false).is_empty());
+
+        fs::remove_dir_all(temp.path().join("dist")).unwrap();
+        assert!(build(false).is_empty());
+        assert!(temp.path().join(".typstlab/build-manifest.json").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_files_under_compile_root_invalidate_the_cache() {
+        use super::BuildEvent;
+
+        let temp = TempDir::new().unwrap();
+        let paper = temp.path().join("papers").join("p01");
+        fs::create_dir_all(&paper).unwrap();
+        fs::write(paper.join("paper.toml"), "[paper]\ntitle = \"Demo\"\n").unwrap();
+        fs::write(paper.join("main.typ"), "#csv(\"/data/x.csv\")").unwrap();
+        fs::create_dir_all(temp.path().join("data")).unwrap();
+        fs::write(temp.path().join("data").join("x.csv"), "1,2").unwrap();
+        let typst = fake_typst(temp.path());
+
+        let build = || {
+            let mut project = loaded_project(temp.path());
+            project.config.structure.shared_dir = Some(PathBuf::from("shared"));
+            let mut up_to_date = false;
+            BuildAction::new(project, TypstDriver::new(typst.clone()), None, None)
+                .run(
+                    &mut |event| {
+                        up_to_date |= matches!(event.payload, BuildEvent::UpToDate { .. });
+                    },
+                    &mut |_| {},
+                )
+                .unwrap();
+            up_to_date
+        };
+
+        assert!(!build());
+        assert!(build());
+        fs::write(temp.path().join("data").join("x.csv"), "3,4").unwrap();
+        assert!(!build());
+        assert!(build());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_compiler_warnings_are_reported_once_per_paper() {
+        use super::BuildFormat;
+        use crate::models::DiagnosticSeverity;
+
+        let temp = TempDir::new().unwrap();
+        let paper = temp.path().join("papers").join("p01");
+        fs::create_dir_all(&paper).unwrap();
+        fs::write(paper.join("paper.toml"), "[paper]\ntitle = \"Demo\"\n").unwrap();
+        fs::write(paper.join("main.typ"), "= Demo").unwrap();
+        let typst = fake_typst(temp.path());
+        fs::write(
+            typst.with_extension("stderr"),
+            "main.typ:1:1: warning: unknown font family: nope\n",
+        )
+        .unwrap();
+
+        let format = BuildFormat {
+            pdf: true,
+            png: true,
+            svg: false,
+            html: false,
+        };
+        let action = BuildAction::new(
+            loaded_project(temp.path()),
+            TypstDriver::new(typst),
+            None,
+            Some(format),
+        );
+        let mut warnings = Vec::new();
+        action
+            .run(&mut |_| {}, &mut |warning| warnings.push(warning))
+            .unwrap();
+
+        let compiler_warnings: Vec<_> = warnings
+            .into_iter()
+            .filter_map(|warning| match warning {
+                BuildWarning::CompilerWarning {
+                    paper_id,
+                    diagnostic,
+                } => Some((paper_id, diagnostic)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(compiler_warnings.len(), 1);
+        assert_eq!(compiler_warnings[0].0, "p01");
+        assert_eq!(compiler_warnings[0].1.severity, DiagnosticSeverity::Warning);
+        assert_eq!(compiler_warnings[0].1.line, Some(1));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_paper_formats_apply_unless_cli_formats_are_given() {
+        use super::BuildFormat;
+
+        let temp = TempDir::new().unwrap();
+        let paper = temp.path().join("papers").join("p01");
+        fs::create_dir_all(&paper).unwrap();
+        fs::write(
+            paper.join("paper.toml"),
+            "[paper]\ntitle = \"Demo\"\n[build]\nformats = [\"png\"]\n",
+        )
+        .unwrap();
+        fs::write(paper.join("main.typ"), "= Demo").unwrap();
+        let typst = fake_typst(temp.path());
+
+        let build = |format: Option<BuildFormat>| {
+            BuildAction::new(
+                loaded_project(temp.path()),
+                TypstDriver::new(typst.clone()),
+                None,
+                format,
+            )
+            .run(&mut |_| {}, &mut |_| {})
+            .unwrap()
+            .remove(0)
+        };
+
+        let dist = build(None);
+        assert!(dist.pdf.is_none());
+        assert_eq!(dist.png.unwrap().len(), 1);
+
+        let dist = build(Some(BuildFormat::default()));
+        assert!(dist.pdf.unwrap().exists());
+        assert!(dist.png.is_none());
+    }
+
+    #[test]
+    fn test_overrides_take_precedence_over_profile_and_paper_settings() {
+        use super::{BuildOverrides, compile_options};
+        use crate::models::{BuildProfile, Paper, PaperConfig};
+
+        let temp = TempDir::new().unwrap();
+        let mut config: PaperConfig = toml::from_str(
+            r#"
+                [paper]
+                title = "Demo"
+
+                [build]
+                ppi = 144.0
+                pages = "1"
+                font_paths = ["fonts"]
+
+                [build.inputs]
+                lang = "ja"
+                draft = "true"
+            "#,
+        )
+        .unwrap();
+        config.build.pdf_standard = Some("a-2b".to_string());
+        let loaded_paper = Loaded {
+            actual: Paper::new("p01".to_string(), temp.path().to_path_buf()),
+            config,
+        };
+        let overrides = BuildOverrides {
+            ppi: Some(300.0),
+            inputs: [("draft".to_string(), "false".to_string())].into(),
+            font_paths: vec![PathBuf::from("/cli/fonts")],
+            ..Default::default()
+        };
+
+        let profile = BuildProfile {
+            inputs: [
+                ("lang".to_string(), "en".to_string()),
+                ("draft".to_string(), "maybe".to_string()),
+            ]
+            .into(),
+            ..Default::default()
+        };
+
+        let mut project = loaded_project(temp.path());
+        project.config.fonts.ignore_system_fonts = true;
+        fs::create_dir_all(temp.path().join("fonts")).unwrap();
+
+        let options = compile_options(&project, &loaded_paper, Some(&profile), &overrides);
+
+        assert_eq!(options.ppi, Some(300.0));
+        assert_eq!(options.pages.as_deref(), Some("1"));
+        assert_eq!(options.inputs["lang"], "en");
+        assert_eq!(options.inputs["draft"], "false");
+        assert_eq!(
+            options.font_paths,
+            vec![
+                PathBuf::from("/cli/fonts"),
+                temp.path().join("p01").join("fonts"),
+                temp.path().join("fonts"),
+            ]
+        );
+        assert!(options.ignore_system_fonts);
+        assert_eq!(options.pdf_standard.as_deref(), Some("a-2b"));
+        assert_eq!(options.root, None);
+
+        project.config.structure.shared_dir = Some(PathBuf::from("lib"));
+        let options = compile_options(&project, &loaded_paper, Some(&profile), &overrides);
+        assert_eq!(options.root.as_deref(), Some(temp.path()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_profile_redirects_outputs_and_rejects_unknown_names() {
+        use super::BuildError;
+        use crate::models::BuildProfile;
+
+        let temp = TempDir::new().unwrap();
+        let paper = temp.path().join("papers").join("p01");
+        fs::create_dir_all(&paper).unwrap();
+        fs::write(paper.join("paper.toml"), "[paper]\ntitle = \"Demo\"\n").unwrap();
+        fs::write(paper.join("main.typ"), "= Demo").unwrap();
+        let typst = fake_typst(temp.path());
+
+        let mut project = loaded_project(temp.path());
+        project.config.profiles.insert(
+            "anonymous".to_string(),
+            BuildProfile {
+                output_suffix: Some("-anon".to_string()),
+                dist_subdir: Some(PathBuf::from("submission")),
+                ..Default::default()
+            },
+        );
+        let dist = BuildAction::new(project, TypstDriver::new(typst.clone()), None, None)
+            .with_profile(Some("anonymous".to_string()))
+            .run(&mut |_| {}, &mut |_| {})
+            .unwrap();
+
+        assert_eq!(
+            dist[0].pdf.as_deref(),
+            Some(
+                temp.path()
+                    .join("dist/submission/p01/main-anon.pdf")
+                    .as_path()
+            )
+        );
+
+        let errors = match BuildAction::new(
+            loaded_project(temp.path()),
+            TypstDriver::new(typst),
+            None,
+            None,
+        )
+        .with_profile(Some("missing".to_string()))
+        .run(&mut |_| {}, &mut |_| {})
+        {
+            Ok(_) => panic!("expected unknown profile to fail"),
+            Err(errors) => errors,
+        };
+        assert!(
+            matches!(errors.as_slice(), [BuildError::UnknownProfile(name)] if name == "missing")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_suffix_only_profiles_keep_each_others_outputs() {
+        use super::BuildFormat;
+        use crate::models::{BuildProfile, OutputFormat};
+
+        let temp = TempDir::new().unwrap();
+        let paper = temp.path().join("papers").join("p01");
+        fs::create_dir_all(&paper).unwrap();
+        fs::write(paper.join("paper.toml"), "[paper]\ntitle = \"Demo\"\n").unwrap();
+        fs::write(paper.join("main.typ"), "= Demo").unwrap();
+        let typst = fake_typst(temp.path());
+
+        let project = || {
+            let mut project = loaded_project(temp.path());
+            for (name, suffix) in [("a", "-a"), ("b", "-b")] {
+                project.config.profiles.insert(
+                    name.to_string(),
+                    BuildProfile {
+                        formats: Some(vec![OutputFormat::Pdf, OutputFormat::Png]),
+                        output_suffix: Some(suffix.to_string()),
+                        ..Default::default()
+                    },
+                );
+            }
+            project
+        };
+        // 接尾辞の無いビルドのページは従来どおり `1.png`
+        for name in [None, Some("a"), Some("b")] {
+            BuildAction::new(
+                project(),
+                TypstDriver::new(typst.clone()),
+                None,
+                Some(BuildFormat::from_output_formats(&[
+                    OutputFormat::Pdf,
+                    OutputFormat::Png,
+                ])),
+            )
+            .with_profile(name.map(str::to_string))
+            .run(&mut |_| {}, &mut |_| {})
+            .unwrap();
+        }
+
+        let dist = temp.path().join("dist").join("p01");
+        for file in [
+            "main.pdf",
+            "main-a.pdf",
+            "main-b.pdf",
+            "png/1.png",
+            "png/main-a-1.png",
+            "png/main-b-1.png",
+        ] {
+            assert!(dist.join(file).is_file(), "{file}");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_failed_compile_keeps_previous_outputs() {
+        use super::BuildFormat;
+
+        let temp = TempDir::new().unwrap();
+        let paper = temp.path().join("papers").join("p01");
+        fs::create_dir_all(&paper).unwrap();
+        fs::write(paper.join("paper.toml"), "[paper]\ntitle = \"Demo\"\n").unwrap();
+        fs::write(paper.join("main.typ"), "= Demo").unwrap();
+        let typst = fake_typst(temp.path());
+        let format = BuildFormat {
+            pdf: true,
+            png: true,
+            svg: false,
+            html: false,
+        };
+        let build = || {
+            BuildAction::new(
+                loaded_project(temp.path()),
+                TypstDriver::new(typst.clone()),
+                None,
+                Some(format),
+            )
+            .with_force(true)
+            .run(&mut |_| {}, &mut |_| {})
+        };
+
+        let dist = build().unwrap();
+        let pdf = dist[0].pdf.clone().unwrap();
+        let png = dist[0].png.clone().unwrap();
+
+        fs::write(typst.with_extension("fail"), "").unwrap();
+        assert!(build().is_err());
+
+        assert_eq!(fs::read_to_string(&pdf).unwrap(), "ok");
+        assert!(png[0].exists());
+        assert!(!temp.path().join("dist").join(".tmp").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_timings_trace_is_written_next_to_artifact_and_summarized() {
+        use super::{BuildEvent, BuildFormat};
+
+        let temp = TempDir::new().unwrap();
+        let paper = temp.path().join("papers").join("p01");
+        fs::create_dir_all(&paper).unwrap();
+        fs::write(paper.join("paper.toml"), "[paper]\ntitle = \"Demo\"\n").unwrap();
+        fs::write(paper.join("main.typ"), "= Demo").unwrap();
+        let typst = fake_typst(temp.path());
+        let build = |with_timings: bool| {
+            let mut timings = Vec::new();
+            BuildAction::new(
+                loaded_project(temp.path()),
+                TypstDriver::new(typst.clone()),
+                None,
+                Some(BuildFormat {
+                    pdf: true,
+                    png: true,
+                    svg: false,
+                    html: false,
+                }),
+            )
+            .with_timings(with_timings)
+            .run(
+                &mut |event| {
+                    if let BuildEvent::Timings {
+                        format,
+                        trace,
+                        phases,
+                        ..
+                    } = event.payload
+                    {
+                        timings.push((format, trace, phases));
+                    }
+                },
+                &mut |_| {},
+            )
+            .unwrap();
+            timings
+        };
+
+        // 最新の成果物があっても --timings ならキャッシュを使わずにコンパイルする
+        assert!(build(false).is_empty());
+        assert_eq!(build(true).len(), 2);
+        let mut timings = build(true);
+
+        timings.sort_by_key(|(format, _, _)| *format);
+        let dist = temp.path().join("dist").join("p01");
+        assert_eq!(timings.len(), 2);
+        assert_eq!(timings[0].1, dist.join("main.timings.json"));
+        assert_eq!(timings[1].1, dist.join("png").join("main.timings.json"));
+        assert!(timings[0].1.exists() && timings[1].1.exists());
+        assert_eq!(timings[0].2[0].name, "typeset");
+        assert_eq!(timings[0].2[0].total_ms, 2.0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hooks_run_around_builds_with_artifact_env() {
+        use super::{BuildError, BuildEvent};
+        use crate::models::HookStage;
+
+        let temp = TempDir::new().unwrap();
+        for id in ["p01", "p02"] {
+            let paper = temp.path().join("papers").join(id);
+            fs::create_dir_all(&paper).unwrap();
+            fs::write(paper.join("main.typ"), "= Demo").unwrap();
+        }
+        fs::write(
+            temp.path().join("papers/p01/paper.toml"),
+            "[paper]\ntitle = \"Demo\"\n\n[hooks]\n\
+             pre_build = \"echo generated > data.csv\"\n\
+             post_build = \"echo \\\"$TYPSTLAB_PAPER_ID $TYPSTLAB_PDF\\\" >> ../../post.log\"\n",
+        )
+        .unwrap();
+        fs::write(
+            temp.path().join("papers/p02/paper.toml"),
+            "[paper]\ntitle = \"Demo\"\n\n[hooks]\npre_build = \"echo no plots >&2; exit 4\"\n",
+        )
+        .unwrap();
+        let typst = fake_typst(temp.path());
+        let mut project = loaded_project(temp.path());
+        project.config.hooks.post_build =
+            vec!["echo \"$TYPSTLAB_PAPER_IDS\" > project.log".to_string()];
+        let mut hooks = Vec::new();
+
+        let errors = BuildAction::new(project, TypstDriver::new(typst), None, None)
+            .run(
+                &mut |event| {
+                    if let BuildEvent::RunningHook {
+                        paper_id, stage, ..
+                    } = event.payload
+                    {
+                        hooks.push((paper_id, stage));
+                    }
+                },
+                &mut |_| {},
+            )
+            .unwrap_err();
+
+        assert_eq!(
+            fs::read_to_string(temp.path().join("papers/p01/data.csv")).unwrap(),
+            "generated\n"
+        );
+        let pdf = temp.path().join("dist/p01/main.pdf");
+        assert_eq!(
+            fs::read_to_string(temp.path().join("post.log")).unwrap(),
+            format!("p01 {}\n", pdf.display())
+        );
+        assert!(!temp.path().join("dist/p02").exists());
+        match errors.as_slice() {
+            [
+                BuildError::HookFailed {
+                    paper_id: Some(paper_id),
+                    stage: HookStage::PreBuild,
+                    exit_code: Some(4),
+                    message,
+                    ..
+                },
+            ] => {
+                assert_eq!(paper_id, "p02");
+                assert!(message.contains("no plots"));
+            }
+            other => panic!("unexpected errors: {:?}", other),
+        }
+        // 失敗した論文があるのでプロジェクトの post_build は走らない
+        assert!(!temp.path().join("project.log").exists());
+        assert_eq!(
+            hooks,
+            vec![
+                (Some("p01".to_string()), HookStage::PreBuild),
+                (Some("p02".to_string()), HookStage::PreBuild),
+                (Some("p01".to_string()), HookStage::PostBuild),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_concurrent_build_waits_for_project_lock() {
+        use super::BuildEvent;
+        use crate::models::ProjectHandle;
+        use typstlab_base::lock::FileLock;
+
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("papers").join("p01")).unwrap();
+        fs::write(
+            temp.path().join("papers/p01/paper.toml"),
+            "[paper]\ntitle = \"Demo\"\n",
+        )
+        .unwrap();
+        fs::write(temp.path().join("papers/p01/main.typ"), "= Demo").unwrap();
+        let typst = fake_typst(temp.path());
+        let project = loaded_project(temp.path());
+        let held = FileLock::acquire(project.build_lock_path()).unwrap();
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let handle = std::thread::spawn(move || {
+            BuildAction::new(project, TypstDriver::new(typst), None, None).run(
+                &mut |event| {
+                    if let BuildEvent::WaitingForLock { .. } = event.payload {
+                        let _ = tx.send(());
+                    }
+                },
+                &mut |_| {},
+            )
+        });
+
+        rx.recv_timeout(std::time::Duration::from_secs(10))
+            .expect("build should report that it is waiting for the lock");
+        assert!(!temp.path().join("dist/p01/main.pdf").exists());
+        drop(held);
+
+        handle.join().unwrap().unwrap();
+        assert!(temp.path().join("dist/p01/main.pdf").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dist_manifest_describes_every_build() {
+        use super::BuildFormat;
+        use crate::models::{DistManifest, FormatStatus};
+
+        let temp = TempDir::new().unwrap();
+        for id in ["p01", "p02"] {
+            let paper = temp.path().join("papers").join(id);
+            fs::create_dir_all(&paper).unwrap();
+            fs::write(paper.join("paper.toml"), "[paper]\ntitle = \"Demo\"\n").unwrap();
+            fs::write(paper.join("main.typ"), "= Demo").unwrap();
+        }
+        let typst = fake_typst(temp.path());
+        let format = BuildFormat {
+            pdf: true,
+            png: true,
+            svg: false,
+            html: false,
+        };
+        let build = |inputs: Option<Vec<String>>| {
+            BuildAction::new(
+                loaded_project(temp.path()),
+                TypstDriver::new(typst.clone()),
+                inputs,
+                Some(format),
+            )
+            .run(&mut |_| {}, &mut |_| {})
+            .unwrap();
+            let content = fs::read_to_string(temp.path().join("dist/manifest.json")).unwrap();
+            serde_json::from_str::<DistManifest>(&content).unwrap()
+        };
+
+        let manifest = build(None);
+        let p01 = &manifest.papers["p01"];
+        assert!(p01.success);
+        assert_eq!(p01.typst_version.as_deref(), Some("0.14.2"));
+        assert_eq!(p01.formats.len(), 2);
+        assert_eq!(p01.formats[0].format, "pdf");
+        assert_eq!(p01.formats[0].status, FormatStatus::Built);
+        assert!(p01.formats[0].duration_ms.is_some());
+        assert_eq!(p01.formats[0].files[0].path, "p01/main.pdf");
+        assert_eq!(p01.formats[0].files[0].size, 2);
+        assert_eq!(p01.formats[1].page_count, Some(1));
+
+        let manifest = build(Some(vec!["p01".to_string()]));
+        assert_eq!(
+            manifest.papers["p01"].formats[0].status,
+            FormatStatus::UpToDate
+        );
+        assert!(manifest.papers["p01"].formats[0].duration_ms.is_none());
+        assert!(manifest.papers.contains_key("p02"));
+    }
+
     #[test]
     fn test_build_format_active_formats() {
         let mut format = super::BuildFormat::default();
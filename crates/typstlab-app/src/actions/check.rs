@@ -0,0 +1,351 @@
+use crate::actions::build::{BuildFormat, BuildOverrides, compile_options, throwaway_command};
+use crate::actions::discovery::{DiscoveryAction, DiscoveryError};
+use crate::models::{CollectionError, PaperHandle, Project, ProjectConfig, ProjectHandle};
+use serde::Serialize;
+use thiserror::Error;
+use typstlab_base::diagnostics::{BuildDiagnostic, DiagnosticSeverity, parse_diagnostics};
+use typstlab_base::driver::{DiagnosticFormat, TypstDriver};
+use typstlab_proto::{Action, AppEvent, Collection, EventScope, Loadable, Loaded};
+
+/// 論文 1 本分のコンパイル検査の結果
+#[derive(Debug, Clone, Serialize)]
+pub struct PaperCheck {
+    pub paper_id: String,
+    /// 検査したフォーマット
+    pub formats: Vec<&'static str>,
+    /// 全フォーマットがコンパイルできたか（警告の有無は問わない）
+    pub compiled: bool,
+    /// エラーと警告。フォーマット間で重複するものは 1 度だけ
+    pub diagnostics: Vec<BuildDiagnostic>,
+}
+
+impl PaperCheck {
+    pub fn count(&self, severity: DiagnosticSeverity) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == severity)
+            .count()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CheckOutput {
+    pub papers: Vec<PaperCheck>,
+    /// `--deny-warnings` で検査したか
+    pub deny_warnings: bool,
+}
+
+impl CheckOutput {
+    pub fn count(&self, severity: DiagnosticSeverity) -> usize {
+        self.papers.iter().map(|paper| paper.count(severity)).sum()
+    }
+
+    /// CI で成功とみなせるか
+    pub fn passed(&self) -> bool {
+        self.papers.iter().all(|paper| paper.compiled)
+            && !(self.deny_warnings && self.count(DiagnosticSeverity::Warning) > 0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum CheckEvent {
+    Checking { paper_id: String },
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CheckWarning {
+    NoTargetsFound,
+}
+
+#[derive(Error, Debug)]
+pub enum CheckError {
+    #[error("Discovery failure: {0:?}")]
+    Discovery(Vec<DiscoveryError>),
+    #[error("Discovery failure: {0}")]
+    GeneralDiscovery(#[from] CollectionError),
+    #[error("Failed to prepare a scratch directory: {0}")]
+    Scratch(#[source] std::io::Error),
+}
+
+/// dist に触れずに論文をコンパイルし、全論文のエラーと警告を集めるアクション
+pub struct CheckAction {
+    pub loaded_project: Loaded<Project, ProjectConfig>,
+    pub typst_driver: TypstDriver,
+    /// None ならすべての論文
+    pub inputs: Option<Vec<String>>,
+    /// None なら論文ごとの `[build].formats` に従う
+    pub format: Option<BuildFormat>,
+    /// true なら警告も失敗として扱う
+    pub deny_warnings: bool,
+}
+
+impl CheckAction {
+    pub fn new(
+        loaded_project: Loaded<Project, ProjectConfig>,
+        typst_driver: TypstDriver,
+        inputs: Option<Vec<String>>,
+    ) -> Self {
+        Self {
+            loaded_project,
+            typst_driver,
+            inputs,
+            format: None,
+            deny_warnings: false,
+        }
+    }
+
+    pub fn with_format(mut self, format: Option<BuildFormat>) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn with_deny_warnings(mut self, deny_warnings: bool) -> Self {
+        self.deny_warnings = deny_warnings;
+        self
+    }
+}
+
+impl Action for CheckAction {
+    type Output = CheckOutput;
+    type Event = CheckEvent;
+    type Warning = CheckWarning;
+    type Error = CheckError;
+
+    fn run(
+        self,
+        monitor: &mut dyn FnMut(AppEvent<Self::Event>),
+        warning: &mut dyn FnMut(Self::Warning),
+    ) -> Result<Self::Output, Vec<Self::Error>> {
+        let targets = match &self.inputs {
+            Some(inputs) => {
+                DiscoveryAction::new(self.loaded_project.papers_scope(), inputs.clone())
+                    .run(&mut |_| {}, &mut |_| {})
+                    .map_err(|errors| vec![CheckError::Discovery(errors)])?
+            }
+            None => self
+                .loaded_project
+                .papers_scope()
+                .list()
+                .map_err(|error| vec![CheckError::GeneralDiscovery(error)])?,
+        };
+
+        let mut output = CheckOutput {
+            papers: Vec::new(),
+            deny_warnings: self.deny_warnings,
+        };
+        if targets.is_empty() {
+            warning(CheckWarning::NoTargetsFound);
+            return Ok(output);
+        }
+
+        // 出力は drop で消える作業場所へ捨てる。dist・ビルドキャッシュ・マニフェストには触れない
+        let tmp_dir = self.loaded_project.tmp_dir();
+        let scratch = std::fs::create_dir_all(&tmp_dir)
+            .and_then(|_| {
+                tempfile::Builder::new()
+                    .prefix("check-")
+                    .tempdir_in(&tmp_dir)
+            })
+            .map_err(|e| vec![CheckError::Scratch(e)])?;
+        let artifact_scope = self.loaded_project.build_artifact_scope();
+        let diagnostic_format = self
+            .typst_driver
+            .get_version()
+            .ok()
+            .as_ref()
+            .and_then(DiagnosticFormat::preferred_for);
+
+        // 途中で失敗しても止めず、全論文の結果を集める
+        for paper in targets {
+            let paper_id = paper.id.clone();
+            monitor(AppEvent::line(
+                EventScope::labeled("check", paper_id.clone()),
+                CheckEvent::Checking {
+                    paper_id: paper_id.clone(),
+                },
+            ));
+            let loaded_paper = match paper.load() {
+                Ok(loaded_paper) => loaded_paper,
+                Err(e) => {
+                    output.papers.push(PaperCheck {
+                        paper_id,
+                        formats: Vec::new(),
+                        compiled: false,
+                        diagnostics: vec![error_diagnostic(e.to_string())],
+                    });
+                    continue;
+                }
+            };
+
+            let format = self.format.unwrap_or_else(|| {
+                loaded_paper
+                    .build_config()
+                    .formats
+                    .as_deref()
+                    .map(BuildFormat::from_output_formats)
+                    .unwrap_or_default()
+            });
+            let options = compile_options(
+                &self.loaded_project,
+                &loaded_paper,
+                None,
+                &BuildOverrides::default(),
+            );
+            let mut check = PaperCheck {
+                paper_id: paper_id.clone(),
+                formats: format.active_formats(),
+                compiled: true,
+                diagnostics: Vec::new(),
+            };
+
+            for fmt in format.active_formats() {
+                let dir = scratch.path().join(format!("{}-{}", paper_id, fmt));
+                let result = std::fs::create_dir_all(&dir)
+                    .map_err(|e| e.to_string())
+                    .and_then(|_| {
+                        self.typst_driver
+                            .execute(throwaway_command(
+                                &loaded_paper,
+                                &artifact_scope,
+                                fmt,
+                                diagnostic_format,
+                                &options,
+                                &dir,
+                            ))
+                            .map_err(|e| e.to_string())
+                    });
+
+                let diagnostics = match result {
+                    Ok(res) if res.exit_code == 0 => parse_diagnostics(&res.stderr),
+                    Ok(res) => {
+                        check.compiled = false;
+                        let diagnostics = parse_diagnostics(&res.stderr);
+                        if diagnostics
+                            .iter()
+                            .any(|d| d.severity == DiagnosticSeverity::Error)
+                        {
+                            diagnostics
+                        } else {
+                            vec![error_diagnostic(res.stderr.trim().to_string())]
+                        }
+                    }
+                    Err(message) => {
+                        check.compiled = false;
+                        vec![error_diagnostic(message)]
+                    }
+                };
+                for diagnostic in diagnostics {
+                    if !check.diagnostics.contains(&diagnostic) {
+                        check.diagnostics.push(diagnostic);
+                    }
+                }
+            }
+            output.papers.push(check);
+        }
+
+        Ok(output)
+    }
+}
+
+/// 位置を持たないエラー（論文の読み込み失敗や Typst を起動できなかった場合など）
+pub(crate) fn error_diagnostic(message: String) -> BuildDiagnostic {
+    BuildDiagnostic {
+        severity: DiagnosticSeverity::Error,
+        file: None,
+        line: None,
+        column: None,
+        message,
+        hints: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+    use tempfile::TempDir;
+
+    /// `main.typ` に `fail` を含む論文ではエラー、`warn` を含む論文では警告を出す偽の typst
+    #[cfg(unix)]
+    fn fake_typst(dir: &Path) -> PathBuf {
+        crate::actions::test_support::fake_typst(
+            dir,
+            "for arg; do case \"$arg\" in *.typ) source=\"$arg\";; esac; done\n\
+             for arg; do last=\"$arg\"; done\n\
+             if grep -q fail \"$source\"; then echo \"main.typ:1:2: error: unknown variable: fail\" >&2; exit 1; fi\n\
+             if grep -q warn \"$source\"; then echo \"main.typ:3:1: warning: unknown font family: nope\" >&2; fi\n\
+             echo ok > \"$last\"\n",
+        )
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_check_collects_every_paper_without_touching_dist() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("project");
+        for (id, body) in [("p01", "= ok\n"), ("p02", "#fail\n"), ("p03", "#warn\n")] {
+            let paper = root.join("papers").join(id);
+            std::fs::create_dir_all(&paper).unwrap();
+            std::fs::write(paper.join("paper.toml"), "[paper]\ntitle = \"T\"\n").unwrap();
+            std::fs::write(paper.join("main.typ"), body).unwrap();
+        }
+        let project = Loaded {
+            actual: Project::new(root.clone()),
+            config: ProjectConfig::default(),
+        };
+        let cache_dir = project.cache_dir();
+        let tmp_dir = project.tmp_dir();
+
+        let output = CheckAction::new(project, TypstDriver::new(fake_typst(temp.path())), None)
+            .run(&mut |_| {}, &mut |_| {})
+            .unwrap();
+
+        let ids: Vec<_> = output.papers.iter().map(|p| p.paper_id.as_str()).collect();
+        assert_eq!(ids, vec!["p01", "p02", "p03"]);
+        assert!(output.papers[0].compiled);
+        assert!(!output.papers[1].compiled);
+        assert_eq!(
+            output.papers[1].diagnostics[0].message,
+            "unknown variable: fail"
+        );
+        assert!(output.papers[2].compiled);
+        assert_eq!(output.papers[2].count(DiagnosticSeverity::Warning), 1);
+        assert!(!output.passed());
+
+        // dist もビルドキャッシュも作られず、作業場所も残らない
+        assert!(!root.join("dist").exists());
+        let cached: Vec<_> = std::fs::read_dir(cache_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(cached, vec![tmp_dir.clone()]);
+        assert_eq!(std::fs::read_dir(tmp_dir).unwrap().count(), 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_deny_warnings_fails_on_warnings_only() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("project");
+        let paper = root.join("papers").join("p01");
+        std::fs::create_dir_all(&paper).unwrap();
+        std::fs::write(paper.join("paper.toml"), "[paper]\ntitle = \"T\"\n").unwrap();
+        std::fs::write(paper.join("main.typ"), "#warn\n").unwrap();
+        let project = || Loaded {
+            actual: Project::new(root.clone()),
+            config: ProjectConfig::default(),
+        };
+        let typst = fake_typst(temp.path());
+
+        let output = CheckAction::new(project(), TypstDriver::new(typst.clone()), None)
+            .run(&mut |_| {}, &mut |_| {})
+            .unwrap();
+        assert!(output.passed());
+
+        let output = CheckAction::new(project(), TypstDriver::new(typst), None)
+            .with_deny_warnings(true)
+            .run(&mut |_| {}, &mut |_| {})
+            .unwrap();
+        assert!(!output.passed());
+    }
+}
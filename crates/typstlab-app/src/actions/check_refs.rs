@@ -0,0 +1,560 @@
+use crate::actions::build::{BuildOverrides, compile_options};
+use crate::actions::discovery::{DiscoveryAction, DiscoveryError};
+use crate::models::{
+    CollectionError, Paper, PaperConfig, PaperError, PaperHandle, Project, ProjectConfig,
+    ProjectHandle,
+};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use typstlab_base::bibliography::{bibliography_keys, bibliography_paths};
+use typstlab_base::diagnostics::{DiagnosticSeverity, parse_diagnostics};
+use typstlab_base::driver::{QueryFormat, TypstCommand, TypstDriver};
+use typstlab_proto::{Action, AppEvent, Collection, Entity, EventScope, Loadable, Loaded};
+
+/// 引用と相互参照をまとめて取り出すセレクタ
+const REFS_SELECTOR: &str = "selector(cite).or(ref)";
+
+/// 参照チェックで見つかった問題の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RefIssueKind {
+    /// 文献ファイルに無い引用キー
+    MissingCitation,
+    /// どこにも定義されていないラベルへの参照
+    UndefinedLabel,
+    /// 一度も引用されていない文献エントリ
+    UnusedEntry,
+}
+
+impl RefIssueKind {
+    pub fn severity(self) -> DiagnosticSeverity {
+        match self {
+            RefIssueKind::MissingCitation | RefIssueKind::UndefinedLabel => {
+                DiagnosticSeverity::Error
+            }
+            RefIssueKind::UnusedEntry => DiagnosticSeverity::Warning,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RefIssue {
+    pub kind: RefIssueKind,
+    pub severity: DiagnosticSeverity,
+    /// 引用キーまたはラベル名（`<>` は含まない）
+    pub key: String,
+    /// 参照箇所 (`file:line:column`) または未使用エントリのある文献ファイル
+    pub location: Option<String>,
+}
+
+impl RefIssue {
+    fn new(kind: RefIssueKind, key: String, location: Option<String>) -> Self {
+        Self {
+            kind,
+            severity: kind.severity(),
+            key,
+            location,
+        }
+    }
+}
+
+/// 論文 1 本分のチェック結果
+#[derive(Debug, Clone, Serialize)]
+pub struct PaperRefsReport {
+    pub paper_id: String,
+    /// `#bibliography(...)` から見つかった文献ファイル
+    pub bibliography: Vec<PathBuf>,
+    /// 引用されたキー（文書がコンパイルできなかった場合は空）
+    pub citations: Vec<String>,
+    /// `@label` / `ref` で参照されたラベル（同上）
+    pub references: Vec<String>,
+    pub issues: Vec<RefIssue>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CheckRefsOutput {
+    pub papers: Vec<PaperRefsReport>,
+}
+
+impl CheckRefsOutput {
+    pub fn count(&self, severity: DiagnosticSeverity) -> usize {
+        self.papers
+            .iter()
+            .flat_map(|paper| &paper.issues)
+            .filter(|issue| issue.severity == severity)
+            .count()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.count(DiagnosticSeverity::Error) > 0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum CheckRefsEvent {
+    CheckingPaper { paper_id: String },
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CheckRefsWarning {
+    NoTargetsFound,
+    /// 文書がコンパイルできず引用の一覧が得られないため、未使用エントリは調べていない
+    UnusedEntriesSkipped {
+        paper_id: String,
+    },
+}
+
+#[derive(Error, Debug)]
+pub enum CheckRefsError {
+    #[error("Discovery failure: {0:?}")]
+    Discovery(Vec<DiscoveryError>),
+    #[error("Discovery failure: {0}")]
+    GeneralDiscovery(#[from] CollectionError),
+    #[error("Failed to load paper '{paper_id}': {source}")]
+    PaperLoad {
+        paper_id: String,
+        #[source]
+        source: PaperError,
+    },
+    #[error("Failed to run typst query for '{paper_id}': {message}")]
+    Driver { paper_id: String, message: String },
+    #[error("typst query failed for '{paper_id}': {message}")]
+    Query { paper_id: String, message: String },
+    #[error("Failed to read {path}: {source}")]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// `typst query` で引用・参照を集め、文献ファイルやラベル定義と突き合わせるアクション
+pub struct CheckRefsAction {
+    pub loaded_project: Loaded<Project, ProjectConfig>,
+    pub typst_driver: TypstDriver,
+    /// None ならすべての論文
+    pub inputs: Option<Vec<String>>,
+}
+
+impl CheckRefsAction {
+    pub fn new(
+        loaded_project: Loaded<Project, ProjectConfig>,
+        typst_driver: TypstDriver,
+        inputs: Option<Vec<String>>,
+    ) -> Self {
+        Self {
+            loaded_project,
+            typst_driver,
+            inputs,
+        }
+    }
+
+    fn check_paper(
+        &self,
+        loaded_paper: &Loaded<Paper, PaperConfig>,
+        warning: &mut dyn FnMut(CheckRefsWarning),
+    ) -> Result<PaperRefsReport, CheckRefsError> {
+        let paper_id = loaded_paper.paper_id().to_string();
+        let options = compile_options(
+            &self.loaded_project,
+            loaded_paper,
+            None,
+            &BuildOverrides::default(),
+        );
+        let result = self
+            .typst_driver
+            .execute(TypstCommand::Query {
+                source: loaded_paper.main_typ_path(),
+                selector: REFS_SELECTOR.to_string(),
+                field: None,
+                one: false,
+                format: QueryFormat::Json,
+                options: Box::new(options),
+            })
+            .map_err(|error| CheckRefsError::Driver {
+                paper_id: paper_id.clone(),
+                message: error.to_string(),
+            })?;
+
+        let mut issues = Vec::new();
+        let mut citations = BTreeSet::new();
+        let mut references = BTreeSet::new();
+        let resolved = result.exit_code == 0;
+        if resolved {
+            let elements: Vec<serde_json::Value> =
+                serde_json::from_str(&result.stdout).map_err(|error| CheckRefsError::Query {
+                    paper_id: paper_id.clone(),
+                    message: format!("unexpected output: {}", error),
+                })?;
+            for element in &elements {
+                match element["func"].as_str() {
+                    Some("cite") => citations.extend(label_field(element, "key")),
+                    Some("ref") => references.extend(label_field(element, "target")),
+                    _ => {}
+                }
+            }
+        } else {
+            // 未解決の引用・参照があると Typst は文書を評価できないので、診断から拾う
+            let diagnostics = parse_diagnostics(&result.stderr);
+            let errors: Vec<_> = diagnostics
+                .iter()
+                .filter(|diagnostic| diagnostic.severity == DiagnosticSeverity::Error)
+                .collect();
+            for diagnostic in &errors {
+                if let Some((kind, key)) = unresolved_reference(&diagnostic.message) {
+                    issues.push(RefIssue::new(kind, key, diagnostic.location()));
+                }
+            }
+            if issues.is_empty() {
+                let message = errors
+                    .first()
+                    .map(|diagnostic| diagnostic.message.clone())
+                    .unwrap_or_else(|| result.stderr.trim().to_string());
+                return Err(CheckRefsError::Query { paper_id, message });
+            }
+        }
+
+        let bibliography = bibliography_files(&self.loaded_project, loaded_paper)
+            .map_err(|(path, source)| CheckRefsError::Read { path, source })?;
+        if resolved {
+            let cited: BTreeSet<_> = citations.union(&references).collect();
+            for path in &bibliography {
+                let content =
+                    std::fs::read_to_string(path).map_err(|source| CheckRefsError::Read {
+                        path: path.clone(),
+                        source,
+                    })?;
+                let location = path
+                    .strip_prefix(&self.loaded_project.actual.root)
+                    .unwrap_or(path)
+                    .display()
+                    .to_string();
+                for key in bibliography_keys(path, &content) {
+                    if !cited.contains(&key) {
+                        issues.push(RefIssue::new(
+                            RefIssueKind::UnusedEntry,
+                            key,
+                            Some(location.clone()),
+                        ));
+                    }
+                }
+            }
+        } else if !bibliography.is_empty() {
+            warning(CheckRefsWarning::UnusedEntriesSkipped {
+                paper_id: paper_id.clone(),
+            });
+        }
+
+        Ok(PaperRefsReport {
+            paper_id,
+            bibliography,
+            citations: citations.into_iter().collect(),
+            references: references.into_iter().collect(),
+            issues,
+        })
+    }
+}
+
+impl Action for CheckRefsAction {
+    type Output = CheckRefsOutput;
+    type Event = CheckRefsEvent;
+    type Warning = CheckRefsWarning;
+    type Error = CheckRefsError;
+
+    fn run(
+        self,
+        monitor: &mut dyn FnMut(AppEvent<Self::Event>),
+        warning: &mut dyn FnMut(Self::Warning),
+    ) -> Result<Self::Output, Vec<Self::Error>> {
+        let targets = match &self.inputs {
+            Some(inputs) => {
+                DiscoveryAction::new(self.loaded_project.papers_scope(), inputs.clone())
+                    .run(&mut |_| {}, &mut |_| {})
+                    .map_err(|errors| vec![CheckRefsError::Discovery(errors)])?
+            }
+            None => self
+                .loaded_project
+                .papers_scope()
+                .list()
+                .map_err(|error| vec![CheckRefsError::GeneralDiscovery(error)])?,
+        };
+
+        if targets.is_empty() {
+            warning(CheckRefsWarning::NoTargetsFound);
+            return Ok(CheckRefsOutput::default());
+        }
+
+        // 1 本が失敗しても残りの論文は調べる
+        let mut output = CheckRefsOutput::default();
+        let mut errors = Vec::new();
+        for paper in targets {
+            let paper_id = paper.id.clone();
+            monitor(AppEvent::verbose(
+                EventScope::labeled("check_refs", paper_id.clone()),
+                CheckRefsEvent::CheckingPaper {
+                    paper_id: paper_id.clone(),
+                },
+            ));
+            let loaded_paper = match paper.load() {
+                Ok(loaded_paper) => loaded_paper,
+                Err(source) => {
+                    errors.push(CheckRefsError::PaperLoad { paper_id, source });
+                    continue;
+                }
+            };
+            match self.check_paper(&loaded_paper, warning) {
+                Ok(report) => output.papers.push(report),
+                Err(error) => errors.push(error),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(output)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// クエリ結果の要素からラベル値 (`<key>`) を取り出す
+fn label_field(element: &serde_json::Value, field: &str) -> Option<String> {
+    let raw = element[field].as_str()?;
+    Some(strip_label(raw).to_string())
+}
+
+fn strip_label(raw: &str) -> &str {
+    raw.trim_matches('`')
+        .trim_start_matches('<')
+        .trim_end_matches('>')
+}
+
+/// 未解決の参照を表す Typst のエラーメッセージを読む。
+/// 例: ``label `<intro>` does not exist in the document``、``key `knuth` does not exist in the bibliography``
+fn unresolved_reference(message: &str) -> Option<(RefIssueKind, String)> {
+    let (kind, rest) = if let Some(rest) = message.strip_prefix("label ") {
+        (RefIssueKind::UndefinedLabel, rest)
+    } else if let Some(rest) = message.strip_prefix("key ") {
+        (RefIssueKind::MissingCitation, rest)
+    } else {
+        return None;
+    };
+    let (key, rest) = rest.split_once(' ')?;
+    rest.starts_with("does not exist")
+        .then(|| (kind, strip_label(key).to_string()))
+}
+
+/// 論文ディレクトリと共有ディレクトリのソースから、存在する文献ファイルを集める。
+/// 読めなかったファイルがあればそのパスとエラーを返す。
+pub(crate) fn bibliography_files(
+    loaded_project: &Loaded<Project, ProjectConfig>,
+    loaded_paper: &Loaded<Paper, PaperConfig>,
+) -> Result<Vec<PathBuf>, (PathBuf, std::io::Error)> {
+    let paper_dir = loaded_paper.actual.path();
+    // Typst の既定のルートはエントリファイルのディレクトリ
+    let root = loaded_project
+        .compile_root()
+        .or_else(|| loaded_paper.main_typ_path().parent().map(Path::to_path_buf))
+        .unwrap_or_else(|| paper_dir.clone());
+
+    let mut sources = Vec::new();
+    typ_files(&paper_dir, &mut sources)?;
+    if let Some(shared_dir) = loaded_project.shared_dir() {
+        typ_files(&shared_dir, &mut sources)?;
+    }
+
+    let mut files = Vec::new();
+    for source in sources {
+        let content = std::fs::read_to_string(&source).map_err(|e| (source.clone(), e))?;
+        for literal in bibliography_paths(&content) {
+            let path = match literal.strip_prefix('/') {
+                Some(rooted) => root.join(rooted),
+                None => source.parent().unwrap_or(&paper_dir).join(&literal),
+            };
+            if path.is_file() && !files.contains(&path) {
+                files.push(path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn typ_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), (PathBuf, std::io::Error)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Ok(());
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<_>>()
+        .map_err(|e| (dir.to_path_buf(), e))?;
+    paths.sort();
+
+    for path in paths {
+        let hidden = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with('.'));
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            typ_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "typ") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_unresolved_reference_reads_typst_messages() {
+        assert_eq!(
+            unresolved_reference("label `<intro>` does not exist in the document"),
+            Some((RefIssueKind::UndefinedLabel, "intro".to_string()))
+        );
+        assert_eq!(
+            unresolved_reference("key `knuth` does not exist in the bibliography"),
+            Some((RefIssueKind::MissingCitation, "knuth".to_string()))
+        );
+        assert_eq!(unresolved_reference("unknown variable: foo"), None);
+    }
+
+    #[cfg(unix)]
+    fn fake_typst(dir: &Path, stdout: &str, stderr: &str, exit_code: i32) -> PathBuf {
+        std::fs::write(dir.join("query.out"), stdout).unwrap();
+        std::fs::write(dir.join("query.err"), stderr).unwrap();
+        crate::actions::test_support::fake_typst(
+            dir,
+            &format!(
+                "cat \"{dir}/query.out\"\n\
+                 cat \"{dir}/query.err\" >&2\n\
+                 exit {exit_code}\n",
+                dir = dir.display(),
+            ),
+        )
+    }
+
+    #[cfg(unix)]
+    fn project_with_paper(root: &Path) -> Loaded<Project, ProjectConfig> {
+        let paper = root.join("papers").join("p01");
+        std::fs::create_dir_all(&paper).unwrap();
+        std::fs::write(paper.join("paper.toml"), "[paper]\ntitle = \"Refs\"\n").unwrap();
+        std::fs::write(
+            paper.join("main.typ"),
+            "See @intro and @knuth.\n#bibliography((\"refs.bib\", \"more.yml\"))\n",
+        )
+        .unwrap();
+        std::fs::write(
+            paper.join("refs.bib"),
+            "@article{knuth, title = {TAOCP}}\n@book{unused, title = {Nope}}\n",
+        )
+        .unwrap();
+        std::fs::write(paper.join("more.yml"), "cited:\n  type: Book\n").unwrap();
+        Loaded {
+            actual: Project::new(root.to_path_buf()),
+            config: ProjectConfig::default(),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_reports_unused_entries_when_document_resolves() {
+        let temp = TempDir::new().unwrap();
+        let project = project_with_paper(&temp.path().join("project"));
+        let typst = fake_typst(
+            temp.path(),
+            r#"[{"func":"ref","target":"<intro>"},{"func":"ref","target":"<knuth>"},
+                {"func":"cite","key":"<knuth>"},{"func":"cite","key":"<cited>"}]"#,
+            "",
+            0,
+        );
+
+        let output = CheckRefsAction::new(project, TypstDriver::new(typst), None)
+            .run(&mut |_| {}, &mut |_| {})
+            .unwrap();
+
+        let report = &output.papers[0];
+        assert_eq!(report.citations, vec!["cited", "knuth"]);
+        assert_eq!(report.references, vec!["intro", "knuth"]);
+        assert_eq!(report.bibliography.len(), 2);
+        assert_eq!(
+            report.issues,
+            vec![RefIssue::new(
+                RefIssueKind::UnusedEntry,
+                "unused".to_string(),
+                Some(format!(
+                    "papers{0}p01{0}refs.bib",
+                    std::path::MAIN_SEPARATOR
+                )),
+            )]
+        );
+        assert!(!output.has_errors());
+        assert_eq!(output.count(DiagnosticSeverity::Warning), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_reports_unresolved_references_from_diagnostics() {
+        let temp = TempDir::new().unwrap();
+        let project = project_with_paper(&temp.path().join("project"));
+        let typst = fake_typst(
+            temp.path(),
+            "",
+            "error: label `<intro>` does not exist in the document\n  ┌─ main.typ:1:5\n\n\
+             error: key `missing` does not exist in the bibliography\n  ┌─ main.typ:2:1\n",
+            1,
+        );
+        let mut warnings = Vec::new();
+
+        let output = CheckRefsAction::new(project, TypstDriver::new(typst), None)
+            .run(&mut |_| {}, &mut |warning| warnings.push(warning))
+            .unwrap();
+
+        assert_eq!(
+            output.papers[0].issues,
+            vec![
+                RefIssue::new(
+                    RefIssueKind::UndefinedLabel,
+                    "intro".to_string(),
+                    Some("main.typ:1:5".to_string()),
+                ),
+                RefIssue::new(
+                    RefIssueKind::MissingCitation,
+                    "missing".to_string(),
+                    Some("main.typ:2:1".to_string()),
+                ),
+            ]
+        );
+        assert!(output.has_errors());
+        assert_eq!(
+            warnings,
+            vec![CheckRefsWarning::UnusedEntriesSkipped {
+                paper_id: "p01".to_string()
+            }]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unrelated_compile_error_is_reported_as_failure() {
+        let temp = TempDir::new().unwrap();
+        let project = project_with_paper(&temp.path().join("project"));
+        let typst = fake_typst(temp.path(), "", "error: unknown variable: foo\n", 1);
+
+        let errors = CheckRefsAction::new(project, TypstDriver::new(typst), None)
+            .run(&mut |_| {}, &mut |_| {})
+            .unwrap_err();
+
+        assert!(matches!(
+            &errors[..],
+            [CheckRefsError::Query { paper_id, message }]
+                if paper_id == "p01" && message == "unknown variable: foo"
+        ));
+    }
+}
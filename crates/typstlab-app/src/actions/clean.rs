@@ -0,0 +1,624 @@
+use crate::actions::build::BuildFormat;
+use crate::actions::resolve_typst::StoreError;
+use crate::models::{
+    BuildArtifactScope, DocsStore, Project, ProjectConfig, ProjectHandle, TypstStore,
+};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use thiserror::Error;
+use typstlab_base::lock::FileLock;
+use typstlab_proto::{Action, AppEvent, Entity, Loaded};
+
+/// `.typstlab/.tmp` の作業場所はロックを取らない処理（check / stats / diff / test）も使うため、
+/// この時間より古いものだけを中断されたものとみなす
+const STALE_AFTER: Duration = Duration::from_secs(60 * 60);
+
+/// dist のうち削除する範囲
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DistSelection {
+    /// None なら dist 以下の全ての論文
+    pub papers: Option<Vec<String>>,
+    /// None なら論文の成果物を丸ごと
+    pub formats: Option<BuildFormat>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CleanKind {
+    Dist,
+    Staging,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct CleanEntry {
+    pub path: PathBuf,
+    pub kind: CleanKind,
+    pub bytes: u64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CleanOutput {
+    pub entries: Vec<CleanEntry>,
+    /// true なら何も削除していない
+    pub dry_run: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CleanWarning {
+    /// 指定された論文の成果物が dist に無い
+    NothingToClean(String),
+    ManifestWriteFailed(String),
+}
+
+#[derive(Debug, Error)]
+pub enum CleanError {
+    #[error("a build of this project is running (lock held at {0})")]
+    BuildInProgress(PathBuf),
+    #[error("failed to lock the project: {0}")]
+    Lock(#[source] std::io::Error),
+    #[error("failed to lock the toolchain store: {0}")]
+    StoreLock(#[source] StoreError),
+    #[error("failed to inspect '{path}': {source}")]
+    Inspect {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to remove '{path}': {source}")]
+    Remove {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+/// ビルド成果物や中断された作業場所を削除するアクション
+pub struct CleanAction {
+    pub loaded_project: Loaded<Project, ProjectConfig>,
+    pub typst_store: TypstStore,
+    pub docs_store: DocsStore,
+    pub dist: Option<DistSelection>,
+    /// true なら残された staging ディレクトリ（`.tmp`）を削除する
+    pub cache: bool,
+    pub dry_run: bool,
+}
+
+impl CleanAction {
+    pub fn new(
+        loaded_project: Loaded<Project, ProjectConfig>,
+        typst_store: TypstStore,
+        docs_store: DocsStore,
+    ) -> Self {
+        Self {
+            loaded_project,
+            typst_store,
+            docs_store,
+            dist: None,
+            cache: false,
+            dry_run: false,
+        }
+    }
+
+    pub fn with_dist(mut self, selection: DistSelection) -> Self {
+        self.dist = Some(selection);
+        self
+    }
+
+    pub fn with_cache(mut self, cache: bool) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+}
+
+impl Action for CleanAction {
+    type Output = CleanOutput;
+    type Event = ();
+    type Warning = CleanWarning;
+    type Error = CleanError;
+
+    fn run(
+        self,
+        _monitor: &mut dyn FnMut(AppEvent<Self::Event>),
+        warning: &mut dyn FnMut(Self::Warning),
+    ) -> Result<Self::Output, Vec<Self::Error>> {
+        // ビルド中の dist や作業場所を消さないようにロックを取る（drop まで保持）
+        let lock_path = self.loaded_project.build_lock_path();
+        let _lock = if self.dry_run {
+            None
+        } else {
+            match FileLock::try_acquire(&lock_path) {
+                Ok(Some(lock)) => Some(lock),
+                Ok(None) => return Err(vec![CleanError::BuildInProgress(lock_path)]),
+                Err(e) => return Err(vec![CleanError::Lock(e)]),
+            }
+        };
+
+        let scopes = dist_scopes(
+            self.loaded_project.build_artifact_scope(),
+            &self.loaded_project.config,
+        );
+        let mut targets = Vec::new();
+        if let Some(selection) = &self.dist {
+            collect_dist_targets(&scopes, selection, &mut targets, warning)?;
+        }
+        // 削除が終わるまで、作業場所を消すバージョンのインストールを止めておく
+        let mut store_locks = Vec::new();
+        if self.cache {
+            // dist の作業場所はビルドしか使わず、ビルドのロックで守られている
+            for scope in &scopes {
+                for path in children(&scope.staging_root())? {
+                    targets.push((path, CleanKind::Staging));
+                }
+            }
+            for path in children(&self.loaded_project.tmp_dir())? {
+                if is_stale(&path)? {
+                    targets.push((path, CleanKind::Staging));
+                }
+            }
+            for path in children(&self.typst_store.staging_root())? {
+                collect_store_staging(
+                    path,
+                    "staging-",
+                    |version| self.typst_store.try_lock(version),
+                    &mut store_locks,
+                    &mut targets,
+                )?;
+            }
+            for path in children(&self.docs_store.staging_root())? {
+                collect_store_staging(
+                    path,
+                    "staging-docs-",
+                    |version| self.docs_store.try_lock(version),
+                    &mut store_locks,
+                    &mut targets,
+                )?;
+            }
+        }
+
+        let mut entries = Vec::new();
+        for (path, kind) in targets {
+            // dist 全体を消す場合は dist/.tmp も含まれるため重複させない
+            if entries
+                .iter()
+                .any(|entry: &CleanEntry| path.starts_with(&entry.path))
+            {
+                continue;
+            }
+            let bytes = size_of(&path)?;
+            entries.push(CleanEntry { path, kind, bytes });
+        }
+
+        if !self.dry_run {
+            for entry in &entries {
+                remove(&entry.path)?;
+            }
+            if let Some(selection) = &self.dist {
+                for scope in &scopes {
+                    update_manifest(scope, selection, warning);
+                }
+            }
+        }
+        drop(store_locks);
+
+        Ok(CleanOutput {
+            entries,
+            dry_run: self.dry_run,
+        })
+    }
+}
+
+/// dist 本体と、プロファイルが `dist_subdir` で切り出した成果物領土（先頭が dist 本体）
+fn dist_scopes(root: BuildArtifactScope, config: &ProjectConfig) -> Vec<BuildArtifactScope> {
+    let subdirs: HashSet<&PathBuf> = config
+        .profiles
+        .values()
+        .filter_map(|profile| profile.dist_subdir.as_ref())
+        .collect();
+    let mut subdir_scopes: Vec<_> = subdirs.into_iter().map(|s| root.subdir(s)).collect();
+    subdir_scopes.sort_by_key(|scope| scope.path());
+    let mut scopes = vec![root];
+    scopes.extend(subdir_scopes);
+    scopes
+}
+
+fn collect_dist_targets(
+    scopes: &[BuildArtifactScope],
+    selection: &DistSelection,
+    targets: &mut Vec<(PathBuf, CleanKind)>,
+    warning: &mut dyn FnMut(CleanWarning),
+) -> Result<(), Vec<CleanError>> {
+    // 全論文の成果物を丸ごと消す場合は dist 直下を全て（manifest.json やプロファイルの領土も含む）
+    let Some(papers) = &selection.papers else {
+        if selection.formats.is_none() {
+            for path in children(&scopes[0].path())? {
+                targets.push((path, CleanKind::Dist));
+            }
+            return Ok(());
+        }
+        for scope in scopes {
+            for path in children(&scope.path())? {
+                // 他の成果物領土（プロファイルのサブディレクトリ）は論文ではない
+                let is_scope = scopes
+                    .iter()
+                    .any(|other| other.path() != scope.path() && other.path().starts_with(&path));
+                if is_scope {
+                    continue;
+                }
+                if let Some(id) = paper_dir_name(&path) {
+                    collect_paper_targets(scope, &id, selection.formats, targets)?;
+                }
+            }
+        }
+        return Ok(());
+    };
+
+    for id in papers {
+        let before = targets.len();
+        for scope in scopes {
+            collect_paper_targets(scope, id, selection.formats, targets)?;
+        }
+        if targets.len() == before {
+            warning(CleanWarning::NothingToClean(id.clone()));
+        }
+    }
+    Ok(())
+}
+
+fn collect_paper_targets(
+    scope: &BuildArtifactScope,
+    paper_id: &str,
+    formats: Option<BuildFormat>,
+    targets: &mut Vec<(PathBuf, CleanKind)>,
+) -> Result<(), Vec<CleanError>> {
+    let paper_scope = scope.paper_scope(paper_id);
+    let Some(formats) = formats else {
+        if exists(&paper_scope.path())? {
+            targets.push((paper_scope.path(), CleanKind::Dist));
+        }
+        return Ok(());
+    };
+
+    for fmt in formats.active_formats() {
+        let dir = paper_scope.format_artifact(fmt).path();
+        if fmt == "pdf" {
+            // pdf の領土は論文ルートなので、他フォーマットのサブディレクトリは残す
+            for path in children(&dir)? {
+                if path.is_file() {
+                    targets.push((path, CleanKind::Dist));
+                }
+            }
+        } else if exists(&dir)? {
+            targets.push((dir, CleanKind::Dist));
+        }
+    }
+    Ok(())
+}
+
+/// 削除した成果物の記録を dist/manifest.json から取り除く
+fn update_manifest(
+    scope: &BuildArtifactScope,
+    selection: &DistSelection,
+    warning: &mut dyn FnMut(CleanWarning),
+) {
+    let mut manifest = scope.manifest();
+    if !manifest.path.exists() {
+        return;
+    }
+
+    let papers = match &selection.papers {
+        Some(papers) => papers.clone(),
+        None => manifest.manifest.papers.keys().cloned().collect(),
+    };
+    for id in &papers {
+        match selection.formats {
+            Some(formats) => {
+                for fmt in formats.active_formats() {
+                    manifest.forget_format(id, fmt);
+                }
+            }
+            None => manifest.forget(id),
+        }
+    }
+    if let Err(e) = manifest.save() {
+        warning(CleanWarning::ManifestWriteFailed(e.to_string()));
+    }
+}
+
+/// ストアの作業場所は、同じバージョンのインストールが進行中（ロック中）なら残す。
+/// 名前からバージョンが読めない場合は古さで判断する
+fn collect_store_staging(
+    path: PathBuf,
+    prefix: &str,
+    try_lock: impl Fn(&str) -> Result<Option<FileLock>, StoreError>,
+    locks: &mut Vec<FileLock>,
+    targets: &mut Vec<(PathBuf, CleanKind)>,
+) -> Result<(), Vec<CleanError>> {
+    let version = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.strip_prefix(prefix))
+        .and_then(|rest| rest.rsplit_once('-'))
+        .map(|(version, _)| version.to_string());
+    match version {
+        Some(version) => match try_lock(&version).map_err(|e| vec![CleanError::StoreLock(e)])? {
+            Some(lock) => locks.push(lock),
+            None => return Ok(()),
+        },
+        None if !is_stale(&path)? => return Ok(()),
+        None => {}
+    }
+    targets.push((path, CleanKind::Staging));
+    Ok(())
+}
+
+/// 最終更新から `STALE_AFTER` 以上経っていれば、中断された作業場所とみなす
+fn is_stale(path: &Path) -> Result<bool, Vec<CleanError>> {
+    let modified = std::fs::symlink_metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .map_err(|source| {
+            vec![CleanError::Inspect {
+                path: path.to_path_buf(),
+                source,
+            }]
+        })?;
+    // 時計が巻き戻っている（未来の時刻）場合は使用中とみなす
+    Ok(SystemTime::now()
+        .duration_since(modified)
+        .is_ok_and(|age| age >= STALE_AFTER))
+}
+
+fn paper_dir_name(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_str()?;
+    (path.is_dir() && !name.starts_with('.')).then(|| name.to_string())
+}
+
+fn exists(path: &Path) -> Result<bool, Vec<CleanError>> {
+    path.try_exists().map_err(|source| {
+        vec![CleanError::Inspect {
+            path: path.to_path_buf(),
+            source,
+        }]
+    })
+}
+
+/// ディレクトリ直下のエントリ（存在しなければ空）
+fn children(dir: &Path) -> Result<Vec<PathBuf>, Vec<CleanError>> {
+    if !exists(dir)? {
+        return Ok(Vec::new());
+    }
+    let inspect = |source| {
+        vec![CleanError::Inspect {
+            path: dir.to_path_buf(),
+            source,
+        }]
+    };
+    let mut paths = std::fs::read_dir(dir)
+        .map_err(inspect)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(inspect)?;
+    paths.sort();
+    Ok(paths)
+}
+
+fn size_of(path: &Path) -> Result<u64, Vec<CleanError>> {
+    let metadata = std::fs::symlink_metadata(path).map_err(|source| {
+        vec![CleanError::Inspect {
+            path: path.to_path_buf(),
+            source,
+        }]
+    })?;
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+
+    let mut total = 0;
+    for child in children(path)? {
+        total += size_of(&child)?;
+    }
+    Ok(total)
+}
+
+fn remove(path: &Path) -> Result<(), Vec<CleanError>> {
+    let result = if path.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    };
+    result.map_err(|source| {
+        vec![CleanError::Remove {
+            path: path.to_path_buf(),
+            source,
+        }]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup() -> (TempDir, CleanAction) {
+        let temp = TempDir::new().unwrap();
+        let dist = temp.path().join("dist");
+        for id in ["p01", "p02"] {
+            std::fs::create_dir_all(dist.join(id).join("png")).unwrap();
+            std::fs::write(dist.join(id).join("main.pdf"), b"pdf").unwrap();
+            std::fs::write(dist.join(id).join("png").join("1.png"), b"png").unwrap();
+        }
+        std::fs::write(dist.join("manifest.json"), b"{}").unwrap();
+        for staging in [
+            ".typstlab/.tmp/staging-typst_docs-x",
+            "store/typst/.tmp/staging-0.14.2-x",
+        ] {
+            std::fs::create_dir_all(temp.path().join(staging)).unwrap();
+            backdate(&temp.path().join(staging));
+        }
+
+        let action = CleanAction::new(
+            Loaded {
+                actual: Project::new(temp.path().to_path_buf()),
+                config: ProjectConfig::default(),
+            },
+            TypstStore::new(temp.path().join("store/typst")),
+            DocsStore::new(temp.path().join("store/docs")),
+        );
+        (temp, action)
+    }
+
+    /// 中断されたまま放置された作業場所に見せかける
+    fn backdate(path: &Path) {
+        let modified = SystemTime::now() - STALE_AFTER - Duration::from_secs(60);
+        std::fs::File::open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    fn run(action: CleanAction) -> (CleanOutput, Vec<CleanWarning>) {
+        let mut warnings = Vec::new();
+        let output = action
+            .run(&mut |_| {}, &mut |warning| warnings.push(warning))
+            .unwrap();
+        (output, warnings)
+    }
+
+    #[test]
+    fn test_dry_run_lists_entries_without_removing() {
+        let (temp, action) = setup();
+
+        let (output, _) = run(action
+            .with_dist(DistSelection::default())
+            .with_cache(true)
+            .with_dry_run(true));
+
+        let paths: Vec<_> = output.entries.iter().map(|e| e.path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                temp.path().join("dist/manifest.json"),
+                temp.path().join("dist/p01"),
+                temp.path().join("dist/p02"),
+                temp.path().join(".typstlab/.tmp/staging-typst_docs-x"),
+                temp.path().join("store/typst/.tmp/staging-0.14.2-x"),
+            ]
+        );
+        assert_eq!(output.entries[1].bytes, 6);
+        assert!(temp.path().join("dist/p01/main.pdf").exists());
+        assert!(
+            temp.path()
+                .join(".typstlab/.tmp/staging-typst_docs-x")
+                .exists()
+        );
+    }
+
+    #[test]
+    fn test_selected_formats_keep_other_outputs() {
+        let (temp, action) = setup();
+
+        let (output, warnings) = run(action.with_dist(DistSelection {
+            papers: Some(vec!["p01".to_string(), "p09".to_string()]),
+            formats: Some(BuildFormat::default()),
+        }));
+
+        assert_eq!(output.entries.len(), 1);
+        assert!(!temp.path().join("dist/p01/main.pdf").exists());
+        assert!(temp.path().join("dist/p01/png/1.png").exists());
+        assert!(temp.path().join("dist/p02/main.pdf").exists());
+        assert_eq!(
+            warnings,
+            vec![CleanWarning::NothingToClean("p09".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_cache_only_leaves_dist_alone() {
+        let (temp, action) = setup();
+
+        run(action.with_cache(true));
+
+        assert!(temp.path().join("dist/p01/main.pdf").exists());
+        assert!(temp.path().join(".typstlab/.tmp").exists());
+        assert!(
+            !temp
+                .path()
+                .join(".typstlab/.tmp/staging-typst_docs-x")
+                .exists()
+        );
+        assert!(
+            !temp
+                .path()
+                .join("store/typst/.tmp/staging-0.14.2-x")
+                .exists()
+        );
+    }
+
+    #[test]
+    fn test_cache_keeps_staging_still_in_use() {
+        let (temp, action) = setup();
+        let fresh = temp.path().join(".typstlab/.tmp/staging-check-y");
+        std::fs::create_dir_all(&fresh).unwrap();
+        let installing = temp.path().join("store/typst/.tmp/staging-0.15.0-y");
+        std::fs::create_dir_all(&installing).unwrap();
+        let _install = action.typst_store.lock("0.15.0").unwrap();
+
+        let (output, _) = run(action.with_cache(true));
+
+        assert!(fresh.exists());
+        assert!(installing.exists());
+        assert_eq!(output.entries.len(), 2);
+        assert!(
+            !temp
+                .path()
+                .join("store/typst/.tmp/staging-0.14.2-x")
+                .exists()
+        );
+    }
+
+    #[test]
+    fn test_format_clean_covers_profile_dist_subdirs() {
+        let (temp, mut action) = setup();
+        action.loaded_project.config = toml::from_str(
+            "[project]\nname = \"demo\"\n\n[profiles.camera]\ndist_subdir = \"submission\"\n",
+        )
+        .unwrap();
+        let submission = temp.path().join("dist/submission/p01");
+        std::fs::create_dir_all(&submission).unwrap();
+        std::fs::write(submission.join("main.pdf"), b"pdf").unwrap();
+
+        let (_, warnings) = run(action.with_dist(DistSelection {
+            papers: None,
+            formats: Some(BuildFormat::default()),
+        }));
+
+        assert!(warnings.is_empty());
+        assert!(!submission.join("main.pdf").exists());
+        assert!(submission.exists());
+        assert!(!temp.path().join("dist/p01/main.pdf").exists());
+        assert!(temp.path().join("dist/p01/png/1.png").exists());
+    }
+
+    #[test]
+    fn test_clean_refuses_while_build_lock_is_held() {
+        let (_temp, action) = setup();
+        let _held = FileLock::acquire(action.loaded_project.build_lock_path()).unwrap();
+
+        let errors = match action
+            .with_dist(DistSelection::default())
+            .run(&mut |_| {}, &mut |_| {})
+        {
+            Ok(_) => panic!("expected clean to fail while a build is running"),
+            Err(errors) => errors,
+        };
+
+        assert!(matches!(
+            errors.as_slice(),
+            [CleanError::BuildInProgress(_)]
+        ));
+    }
+}
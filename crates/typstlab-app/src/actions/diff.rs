@@ -0,0 +1,541 @@
+use crate::actions::build::{BuildOverrides, compile_options, throwaway_command};
+use crate::actions::discovery::{DiscoveryAction, DiscoveryError};
+use crate::models::build_artifact_scope::OutputNaming;
+use crate::models::{
+    BuildArtifactScope, Paper, PaperConfig, PaperError, PaperHandle, Project, ProjectConfig,
+    ProjectError, ProjectHandle,
+};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use typstlab_base::diagnostics::{BuildDiagnostic, parse_diagnostics};
+use typstlab_base::driver::{DiagnosticFormat, TypstDriver};
+use typstlab_base::git::{GitError, export_git_tree};
+use typstlab_base::image_diff::{ImageDiffError, diff_images, read_png, write_png};
+use typstlab_proto::{Action, AppEvent, Entity, EventScope, Loadable, Loaded};
+
+/// `--against` を省略したときの比較対象
+const DEFAULT_REVISION: &str = "HEAD";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PageStatus {
+    Unchanged,
+    Changed,
+    /// 現在のソースにだけあるページ
+    Added,
+    /// 比較対象にだけあるページ
+    Removed,
+}
+
+/// 1 ページ分の比較結果
+#[derive(Debug, Clone, Serialize)]
+pub struct PageDiff {
+    /// 1 始まりのページ番号
+    pub page: usize,
+    pub status: PageStatus,
+    pub changed_pixels: u64,
+    pub total_pixels: u64,
+    /// 差分を塗った画像（変化したページのみ）
+    pub diff_image: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffOutput {
+    pub paper_id: String,
+    /// 比較対象（ディレクトリまたはリビジョン）
+    pub against: String,
+    /// 差分画像と `summary.json` の出力先
+    pub out_dir: PathBuf,
+    pub pages: Vec<PageDiff>,
+}
+
+impl DiffOutput {
+    pub fn changed_pages(&self) -> Vec<usize> {
+        self.pages
+            .iter()
+            .filter(|page| page.status != PageStatus::Unchanged)
+            .map(|page| page.page)
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum DiffEvent {
+    Rendering { paper_id: String, source: String },
+    Comparing { pages: usize },
+}
+
+#[derive(Error, Debug)]
+pub enum DiffError {
+    #[error("Discovery failure: {0:?}")]
+    Discovery(Vec<DiscoveryError>),
+    #[error("Failed to load paper '{paper_id}': {source}")]
+    PaperLoad {
+        paper_id: String,
+        #[source]
+        source: PaperError,
+    },
+    #[error("Failed to export revision '{rev}': {source}")]
+    Git {
+        rev: String,
+        #[source]
+        source: GitError,
+    },
+    #[error("Failed to load the project at revision '{rev}': {source}")]
+    ReferenceProject {
+        rev: String,
+        #[source]
+        source: ProjectError,
+    },
+    #[error("Paper '{paper_id}' does not exist at revision '{rev}'")]
+    ReferencePaperMissing { paper_id: String, rev: String },
+    #[error("Failed to run typst: {0}")]
+    Driver(String),
+    #[error("Failed to render {source_label} as PNG")]
+    Render {
+        source_label: String,
+        diagnostics: Vec<BuildDiagnostic>,
+        stderr: String,
+    },
+    #[error("No PNG pages found in {0}")]
+    NoPages(PathBuf),
+    #[error("Failed to compare {path}: {source}")]
+    Image {
+        path: PathBuf,
+        #[source]
+        source: ImageDiffError,
+    },
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// 現在のソースと比較対象を PNG に描画し、ページごとの画素差分を書き出すアクション
+pub struct DiffAction {
+    pub loaded_project: Loaded<Project, ProjectConfig>,
+    pub typst_driver: TypstDriver,
+    /// 論文 ID またはパス
+    pub paper_input: String,
+    /// PNG のディレクトリまたは git のリビジョン。None なら HEAD
+    pub against: Option<String>,
+}
+
+impl DiffAction {
+    pub fn new(
+        loaded_project: Loaded<Project, ProjectConfig>,
+        typst_driver: TypstDriver,
+        paper_input: String,
+    ) -> Self {
+        Self {
+            loaded_project,
+            typst_driver,
+            paper_input,
+            against: None,
+        }
+    }
+
+    pub fn with_against(mut self, against: Option<String>) -> Self {
+        self.against = against;
+        self
+    }
+
+    /// ビルドと同じ PNG 出力を `dir` に書き、ページ順に並べて返す
+    fn render_pages(
+        &self,
+        loaded_project: &Loaded<Project, ProjectConfig>,
+        loaded_paper: &Loaded<Paper, PaperConfig>,
+        artifact_scope: &BuildArtifactScope,
+        source_label: &str,
+        dir: &Path,
+    ) -> Result<Vec<PathBuf>, DiffError> {
+        std::fs::create_dir_all(dir)?;
+        let diagnostic_format = self
+            .typst_driver
+            .get_version()
+            .ok()
+            .as_ref()
+            .and_then(DiagnosticFormat::preferred_for);
+        let options = compile_options(
+            loaded_project,
+            loaded_paper,
+            None,
+            &BuildOverrides::default(),
+        );
+        let result = self
+            .typst_driver
+            .execute(throwaway_command(
+                loaded_paper,
+                artifact_scope,
+                "png",
+                diagnostic_format,
+                &options,
+                dir,
+            ))
+            .map_err(|e| DiffError::Driver(e.to_string()))?;
+        if result.exit_code != 0 {
+            return Err(DiffError::Render {
+                source_label: source_label.to_string(),
+                diagnostics: parse_diagnostics(&result.stderr),
+                stderr: result.stderr,
+            });
+        }
+        Ok(png_pages(dir)?)
+    }
+
+    /// リビジョン時点のプロジェクトを作業場所に展開し、同じ論文を描画する
+    fn render_revision(
+        &self,
+        paper_id: &str,
+        rev: &str,
+        scratch: &Path,
+    ) -> Result<Vec<PathBuf>, DiffError> {
+        let tree = scratch.join("tree");
+        export_git_tree(&self.loaded_project.actual.root, rev, &tree).map_err(|source| {
+            DiffError::Git {
+                rev: rev.to_string(),
+                source,
+            }
+        })?;
+        let reference_project =
+            Project::new(tree)
+                .load()
+                .map_err(|source| DiffError::ReferenceProject {
+                    rev: rev.to_string(),
+                    source,
+                })?;
+        let missing = || DiffError::ReferencePaperMissing {
+            paper_id: paper_id.to_string(),
+            rev: rev.to_string(),
+        };
+        let reference_paper =
+            DiscoveryAction::new(reference_project.papers_scope(), vec![paper_id.to_string()])
+                .run(&mut |_| {}, &mut |_| {})
+                .map_err(|_| missing())?
+                .remove(0)
+                .load()
+                .map_err(|_| missing())?;
+
+        self.render_pages(
+            &reference_project,
+            &reference_paper,
+            &reference_project.build_artifact_scope(),
+            rev,
+            &scratch.join("reference"),
+        )
+    }
+
+    fn diff(&self, monitor: &mut dyn FnMut(AppEvent<DiffEvent>)) -> Result<DiffOutput, DiffError> {
+        let paper = DiscoveryAction::new(
+            self.loaded_project.papers_scope(),
+            vec![self.paper_input.clone()],
+        )
+        .run(&mut |_| {}, &mut |_| {})
+        .map_err(DiffError::Discovery)?
+        .remove(0);
+        let paper_id = paper.id.clone();
+        let loaded_paper = paper
+            .load()
+            .map_err(|source| DiffError::PaperLoad { paper_id, source })?;
+        let paper_id = loaded_paper.paper_id().to_string();
+        let scope = EventScope::labeled("diff", paper_id.clone());
+
+        let tmp_dir = self.loaded_project.tmp_dir();
+        std::fs::create_dir_all(&tmp_dir)?;
+        let scratch = tempfile::Builder::new()
+            .prefix("diff-")
+            .tempdir_in(&tmp_dir)?;
+        let artifact_scope = self.loaded_project.build_artifact_scope();
+
+        monitor(AppEvent::line(
+            scope.clone(),
+            DiffEvent::Rendering {
+                paper_id: paper_id.clone(),
+                source: "working tree".to_string(),
+            },
+        ));
+        let current = self.render_pages(
+            &self.loaded_project,
+            &loaded_paper,
+            &artifact_scope,
+            "the working tree",
+            &scratch.path().join("current"),
+        )?;
+
+        let against = self
+            .against
+            .clone()
+            .unwrap_or_else(|| DEFAULT_REVISION.to_string());
+        let against_dir = PathBuf::from(&against);
+        let reference = if against_dir.is_dir() {
+            // 以前のビルドの PNG などをそのまま比較対象にする。
+            // dist の png ディレクトリは接尾辞付きのプロファイルと共有されるので、既定の出力のページに絞る
+            let naming = OutputNaming::new(loaded_paper.output_base_name(), None);
+            let pages: Vec<PathBuf> = png_pages(&against_dir)?
+                .into_iter()
+                .filter(|path| {
+                    path.file_name()
+                        .is_some_and(|name| naming.is_page(&name.to_string_lossy()))
+                })
+                .collect();
+            if pages.is_empty() {
+                return Err(DiffError::NoPages(against_dir));
+            }
+            pages
+        } else {
+            monitor(AppEvent::line(
+                scope.clone(),
+                DiffEvent::Rendering {
+                    paper_id: paper_id.clone(),
+                    source: against.clone(),
+                },
+            ));
+            self.render_revision(&paper_id, &against, scratch.path())?
+        };
+
+        // 前回の差分画像が残らないよう出力先を作り直す
+        let out_dir = artifact_scope
+            .paper_scope(&paper_id)
+            .format_artifact("diff")
+            .path();
+        if out_dir.exists() {
+            std::fs::remove_dir_all(&out_dir)?;
+        }
+        std::fs::create_dir_all(&out_dir)?;
+
+        let page_count = current.len().max(reference.len());
+        monitor(AppEvent::verbose(
+            scope,
+            DiffEvent::Comparing { pages: page_count },
+        ));
+        let pages = compare_pages(&current, &reference, &out_dir)
+            .map_err(|(path, source)| DiffError::Image { path, source })?;
+
+        let output = DiffOutput {
+            paper_id,
+            against,
+            out_dir: out_dir.clone(),
+            pages,
+        };
+        let summary = serde_json::to_vec_pretty(&output).map_err(std::io::Error::other)?;
+        std::fs::write(out_dir.join("summary.json"), summary)?;
+        Ok(output)
+    }
+}
+
+impl Action for DiffAction {
+    type Output = DiffOutput;
+    type Event = DiffEvent;
+    type Warning = ();
+    type Error = DiffError;
+
+    fn run(
+        self,
+        monitor: &mut dyn FnMut(AppEvent<Self::Event>),
+        _warning: &mut dyn FnMut(Self::Warning),
+    ) -> Result<Self::Output, Vec<Self::Error>> {
+        self.diff(monitor).map_err(|error| vec![error])
+    }
+}
+
+/// ページ順に並んだ 2 組の PNG を比べ、変化したページの差分画像を `out_dir` に書く。
+/// 読み書きに失敗した場合はそのファイルとエラーを返す。
+pub(crate) fn compare_pages(
+    current: &[PathBuf],
+    reference: &[PathBuf],
+    out_dir: &Path,
+) -> Result<Vec<PageDiff>, (PathBuf, ImageDiffError)> {
+    let page_count = current.len().max(reference.len());
+    let width = page_count.to_string().len();
+    let mut pages = Vec::with_capacity(page_count);
+
+    for index in 0..page_count {
+        let page = index + 1;
+        let (current_page, reference_page) = match (current.get(index), reference.get(index)) {
+            (Some(current), Some(reference)) => (current, reference),
+            (current, _) => {
+                pages.push(PageDiff {
+                    page,
+                    status: if current.is_some() {
+                        PageStatus::Added
+                    } else {
+                        PageStatus::Removed
+                    },
+                    changed_pixels: 0,
+                    total_pixels: 0,
+                    diff_image: None,
+                });
+                continue;
+            }
+        };
+
+        let read = |path: &PathBuf| read_png(path).map_err(|e| (path.clone(), e));
+        let diff = diff_images(&read(current_page)?, &read(reference_page)?);
+        let diff_image = if diff.changed_pixels > 0 {
+            let path = out_dir.join(format!("page-{:0width$}.png", page));
+            write_png(&path, &diff.image).map_err(|e| (path.clone(), e))?;
+            Some(path)
+        } else {
+            None
+        };
+        pages.push(PageDiff {
+            page,
+            status: if diff.changed_pixels > 0 {
+                PageStatus::Changed
+            } else {
+                PageStatus::Unchanged
+            },
+            changed_pixels: diff.changed_pixels,
+            total_pixels: diff.total_pixels,
+            diff_image,
+        });
+    }
+
+    Ok(pages)
+}
+
+/// ディレクトリ内の PNG をファイル名順（Typst の `{0p}` はゼロ埋めなのでページ順）に返す
+pub(crate) fn png_pages(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut pages: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()?
+        .into_iter()
+        .filter(|path| path.extension().is_some_and(|ext| ext == "png"))
+        .collect();
+    pages.sort();
+    Ok(pages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use typstlab_base::image_diff::RgbaImage;
+
+    fn page(value: u8) -> RgbaImage {
+        RgbaImage {
+            width: 2,
+            height: 2,
+            pixels: [value, value, value, 0xFF].repeat(4),
+        }
+    }
+
+    /// `main.typ` の各行の数値を 1 ページずつ、その明るさの PNG として描く偽の typst
+    #[cfg(unix)]
+    fn fake_typst(dir: &Path, pages: &Path) -> PathBuf {
+        crate::actions::test_support::fake_typst(
+            dir,
+            &format!(
+                "for arg; do case \"$arg\" in *.typ) source=\"$arg\";; esac; done\n\
+                 for arg; do last=\"$arg\"; done\n\
+                 out=$(dirname \"$last\")\n\
+                 n=1\n\
+                 while read -r value; do cp \"{pages}/$value.png\" \"$out/$n.png\"; n=$((n+1)); done < \"$source\"\n",
+                pages = pages.display()
+            ),
+        )
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_diff_against_png_directory() {
+        let temp = TempDir::new().unwrap();
+        let palette = temp.path().join("palette");
+        std::fs::create_dir_all(&palette).unwrap();
+        for value in [0u8, 128, 255] {
+            write_png(&palette.join(format!("{}.png", value)), &page(value)).unwrap();
+        }
+        let root = temp.path().join("project");
+        let paper = root.join("papers").join("p01");
+        std::fs::create_dir_all(&paper).unwrap();
+        std::fs::write(paper.join("paper.toml"), "[paper]\ntitle = \"T\"\n").unwrap();
+        std::fs::write(paper.join("main.typ"), "0\n128\n255\n").unwrap();
+        // 以前のビルド: 1 ページ目は同じ、2 ページ目は違う、3 ページ目は無い
+        let previous = temp.path().join("previous");
+        std::fs::create_dir_all(&previous).unwrap();
+        write_png(&previous.join("1.png"), &page(0)).unwrap();
+        write_png(&previous.join("2.png"), &page(0)).unwrap();
+        // 接尾辞付きプロファイルのページは比較対象にならない
+        write_png(&previous.join("main-anon-3.png"), &page(255)).unwrap();
+        let project = Loaded {
+            actual: Project::new(root.clone()),
+            config: ProjectConfig::default(),
+        };
+
+        let output = DiffAction::new(
+            project,
+            TypstDriver::new(fake_typst(temp.path(), &palette)),
+            "p01".to_string(),
+        )
+        .with_against(Some(previous.display().to_string()))
+        .run(&mut |_| {}, &mut |_| {})
+        .unwrap();
+
+        let statuses: Vec<_> = output.pages.iter().map(|page| page.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                PageStatus::Unchanged,
+                PageStatus::Changed,
+                PageStatus::Added
+            ]
+        );
+        assert_eq!(output.changed_pages(), vec![2, 3]);
+        assert_eq!(output.pages[1].changed_pixels, 4);
+        let out_dir = root.join("dist").join("p01").join("diff");
+        assert_eq!(output.out_dir, out_dir);
+        assert_eq!(output.pages[1].diff_image, Some(out_dir.join("page-2.png")));
+        assert!(out_dir.join("summary.json").is_file());
+        assert!(!out_dir.join("page-1.png").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_diff_against_git_revision() {
+        let temp = TempDir::new().unwrap();
+        let palette = temp.path().join("palette");
+        std::fs::create_dir_all(&palette).unwrap();
+        for value in [0u8, 255] {
+            write_png(&palette.join(format!("{}.png", value)), &page(value)).unwrap();
+        }
+        let root = temp.path().join("project");
+        let paper = root.join("papers").join("p01");
+        std::fs::create_dir_all(&paper).unwrap();
+        std::fs::write(
+            root.join("typstlab.toml"),
+            "[project]\nname = \"demo\"\ninit_date = \"2026-04-23\"\n",
+        )
+        .unwrap();
+        std::fs::write(paper.join("paper.toml"), "[paper]\ntitle = \"T\"\n").unwrap();
+        std::fs::write(paper.join("main.typ"), "0\n0\n").unwrap();
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .arg("-C")
+                .arg(&root)
+                .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+                .args(args)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        };
+        git(&["init", "-q"]);
+        git(&["add", "-A"]);
+        git(&["commit", "-q", "-m", "init"]);
+        std::fs::write(paper.join("main.typ"), "0\n").unwrap();
+        let project = Loaded {
+            actual: Project::new(root.clone()),
+            config: ProjectConfig::default(),
+        };
+
+        let output = DiffAction::new(
+            project,
+            TypstDriver::new(fake_typst(temp.path(), &palette)),
+            "p01".to_string(),
+        )
+        .run(&mut |_| {}, &mut |_| {})
+        .unwrap();
+
+        assert_eq!(output.against, "HEAD");
+        let statuses: Vec<_> = output.pages.iter().map(|page| page.status).collect();
+        assert_eq!(statuses, vec![PageStatus::Unchanged, PageStatus::Removed]);
+    }
+}
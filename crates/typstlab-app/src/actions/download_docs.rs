@@ -199,6 +199,7 @@ mod tests {
         ResolvedLink {
             url: "https://example.com/docs.json".to_string(),
             format: SourceFormat::Raw,
+            sha256: None,
         }
     }
 
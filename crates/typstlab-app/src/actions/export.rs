@@ -0,0 +1,693 @@
+use crate::actions::discovery::{DiscoveryAction, DiscoveryError};
+use crate::models::{
+    Paper, PaperConfig, PaperError, PaperHandle, Project, ProjectConfig, ProjectHandle,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Component, Path, PathBuf};
+use thiserror::Error;
+use typstlab_base::archive::{ArchiveError, ArchiveSource, write_zip};
+use typstlab_base::driver::{CompileOptions, TypstCommand};
+use typstlab_base::source_refs::{PackageSpec, package_imports, string_literals};
+use typstlab_base::version_resolver::{VersionResolveError, resolve_typst_version};
+use typstlab_proto::{Action, AppEvent, Entity, Loadable, Loaded};
+
+const BUNDLE_README: &str = "README.md";
+const BUNDLE_PACKAGES_DIR: &str = "packages";
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ExportOutput {
+    pub path: PathBuf,
+    pub paper_id: String,
+    pub typst_version: String,
+    /// アーカイブに入れたファイル数（README を含む）
+    pub files: usize,
+    pub bytes: u64,
+    /// 同梱したローカルテンプレートの ID
+    pub templates: Vec<String>,
+    /// 同梱したパッケージ (`@namespace/name:version`)
+    pub packages: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ExportWarning {
+    /// パッケージディレクトリにもキャッシュにも見つからなかったパッケージ
+    PackageNotFound(String),
+}
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error("Discovery failure: {0:?}")]
+    Discovery(Vec<DiscoveryError>),
+    #[error("failed to load paper '{paper_id}': {source}")]
+    PaperLoad {
+        paper_id: String,
+        source: PaperError,
+    },
+    #[error("failed to read '{path}': {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to write bundle: {0}")]
+    Archive(#[from] ArchiveError),
+    #[error("{0}")]
+    VersionResolution(#[from] VersionResolveError),
+}
+
+/// 論文を typstlab 無しで `typst compile` できる zip にまとめるアクション
+pub struct ExportAction {
+    pub loaded_project: Loaded<Project, ProjectConfig>,
+    pub paper_input: String,
+    pub out: PathBuf,
+    /// パッケージを探すディレクトリ（Typst のデータ・キャッシュディレクトリの `packages`）
+    pub package_dirs: Vec<PathBuf>,
+}
+
+impl ExportAction {
+    pub fn new(
+        loaded_project: Loaded<Project, ProjectConfig>,
+        paper_input: String,
+        out: PathBuf,
+    ) -> Self {
+        Self {
+            loaded_project,
+            paper_input,
+            out,
+            package_dirs: Vec::new(),
+        }
+    }
+
+    pub fn with_package_dirs(mut self, package_dirs: Vec<PathBuf>) -> Self {
+        self.package_dirs = package_dirs;
+        self
+    }
+}
+
+impl Action for ExportAction {
+    type Output = ExportOutput;
+    type Event = ();
+    type Warning = ExportWarning;
+    type Error = ExportError;
+
+    fn run(
+        self,
+        _monitor: &mut dyn FnMut(AppEvent<Self::Event>),
+        warning: &mut dyn FnMut(Self::Warning),
+    ) -> Result<Self::Output, Vec<Self::Error>> {
+        let discovery = DiscoveryAction::<_, Paper>::new(
+            self.loaded_project.papers_scope(),
+            vec![self.paper_input.clone()],
+        );
+        let paper = discovery
+            .run(&mut |_| {}, &mut |_| {})
+            .map_err(|errors| vec![ExportError::Discovery(errors)])?
+            .remove(0);
+        let paper_id = paper.id.clone();
+        let loaded_paper = paper
+            .load()
+            .map_err(|source| vec![ExportError::PaperLoad { paper_id, source }])?;
+
+        let mut bundle = Bundle::new(self.loaded_project.actual.root.clone(), self.out.clone());
+        let templates_root = self.loaded_project.templates_scope().path();
+        let compile_root = self.loaded_project.compile_root();
+        let mut templates = BTreeSet::new();
+        let mut packages = BTreeSet::new();
+        let mut missing = BTreeSet::new();
+
+        // 1. 論文ディレクトリと共有ディレクトリ丸ごと
+        let paper_dir = loaded_paper.actual.path();
+        let mut sources = bundle.add_tree(&paper_dir, None).map_err(|e| vec![e])?;
+        if let Some(shared_dir) = self.loaded_project.shared_dir()
+            && shared_dir.is_dir()
+        {
+            let found = bundle.add_tree(&shared_dir, None).map_err(|e| vec![e])?;
+            sources.extend(found);
+        }
+
+        // 2. ソースから辿れるローカルテンプレートとパッケージ（推移的に）
+        while let Some(source) = sources.pop() {
+            let content = std::fs::read_to_string(&source.path).map_err(|e| {
+                vec![ExportError::Read {
+                    path: source.path.clone(),
+                    source: e,
+                }]
+            })?;
+
+            if !source.in_package {
+                for literal in string_literals(&content) {
+                    let Some(id) = referenced_template(
+                        &source.path,
+                        &literal,
+                        &templates_root,
+                        compile_root.as_deref(),
+                    ) else {
+                        continue;
+                    };
+                    if templates.insert(id.clone()) {
+                        let found = bundle
+                            .add_tree(&templates_root.join(&id), None)
+                            .map_err(|e| vec![e])?;
+                        sources.extend(found);
+                    }
+                }
+            }
+
+            for spec in package_imports(&content) {
+                if packages.contains(&spec) || missing.contains(&spec) {
+                    continue;
+                }
+                let Some(dir) = self
+                    .package_dirs
+                    .iter()
+                    .map(|root| root.join(spec.relative_dir()))
+                    .find(|dir| dir.is_dir())
+                else {
+                    warning(ExportWarning::PackageNotFound(spec.to_string()));
+                    missing.insert(spec);
+                    continue;
+                };
+                let prefix = Path::new(BUNDLE_PACKAGES_DIR).join(spec.relative_dir());
+                let found = bundle.add_tree(&dir, Some(&prefix)).map_err(|e| vec![e])?;
+                sources.extend(found.into_iter().map(|source| SourceFile {
+                    in_package: true,
+                    ..source
+                }));
+                packages.insert(spec);
+            }
+        }
+
+        // 3. プロジェクトのフォント
+        let mut font_dirs = Vec::new();
+        for dir in self.loaded_project.font_dirs() {
+            if dir.is_dir() {
+                bundle.add_tree(&dir, None).map_err(|e| vec![e])?;
+                font_dirs.push(bundle.archive_path(&dir));
+            }
+        }
+
+        // 4. そのままコンパイルするための README
+        // `v0.14.2` のような表記でもリリースのリンクが壊れないよう正規化する
+        let typst_version = resolve_typst_version(&self.loaded_project.toolchain().typst)
+            .map_err(|e| vec![e.into()])?;
+        let command = compile_command(
+            &bundle,
+            &self.loaded_project,
+            &loaded_paper,
+            font_dirs,
+            !templates.is_empty() || compile_root.is_some(),
+            !packages.is_empty(),
+        );
+        let readme = readme(
+            &loaded_paper.config.paper.title,
+            &typst_version,
+            &command,
+            &packages,
+        );
+        bundle.entries.insert(
+            BUNDLE_README.to_string(),
+            ArchiveSource::Bytes(readme.into_bytes()),
+        );
+
+        let entries: Vec<_> = bundle.entries.into_iter().collect();
+        let bytes = write_zip(&self.out, &entries).map_err(|e| vec![ExportError::Archive(e)])?;
+
+        Ok(ExportOutput {
+            path: self.out,
+            paper_id: loaded_paper.paper_id().to_string(),
+            typst_version,
+            files: entries.len(),
+            bytes,
+            templates: templates.into_iter().collect(),
+            packages: packages.iter().map(PackageSpec::to_string).collect(),
+        })
+    }
+}
+
+struct SourceFile {
+    path: PathBuf,
+    /// パッケージ内のファイルは相対参照がパッケージ内で閉じているので追わない
+    in_package: bool,
+}
+
+/// アーカイブに入れるファイル。名前はプロジェクトルートからの `/` 区切りの相対パス。
+struct Bundle {
+    project_root: PathBuf,
+    out: PathBuf,
+    entries: BTreeMap<String, ArchiveSource>,
+}
+
+impl Bundle {
+    fn new(project_root: PathBuf, out: PathBuf) -> Self {
+        Self {
+            project_root,
+            out,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    fn archive_path(&self, path: &Path) -> PathBuf {
+        path.strip_prefix(&self.project_root)
+            .unwrap_or(path)
+            .to_path_buf()
+    }
+
+    /// ディレクトリ以下の全ファイルを追加し、`.typ` ファイルを返す。
+    /// `prefix` が無ければプロジェクト内での配置をそのまま使う。
+    fn add_tree(
+        &mut self,
+        dir: &Path,
+        prefix: Option<&Path>,
+    ) -> Result<Vec<SourceFile>, ExportError> {
+        let prefix = match prefix {
+            Some(prefix) => prefix.to_path_buf(),
+            None => self.archive_path(dir),
+        };
+        let mut sources = Vec::new();
+        self.walk(dir, &prefix, &mut sources)?;
+        Ok(sources)
+    }
+
+    fn walk(
+        &mut self,
+        dir: &Path,
+        prefix: &Path,
+        sources: &mut Vec<SourceFile>,
+    ) -> Result<(), ExportError> {
+        let read_error = |source| ExportError::Read {
+            path: dir.to_path_buf(),
+            source,
+        };
+        let mut entries = std::fs::read_dir(dir)
+            .map_err(read_error)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(read_error)?;
+        entries.sort();
+
+        for path in entries {
+            let Some(name) = path.file_name() else {
+                continue;
+            };
+            let archive_path = prefix.join(name);
+            if path.is_dir() {
+                self.walk(&path, &archive_path, sources)?;
+            } else if path.is_file() && path != self.out {
+                if path.extension().is_some_and(|ext| ext == "typ") {
+                    sources.push(SourceFile {
+                        path: path.clone(),
+                        in_package: false,
+                    });
+                }
+                self.entries
+                    .insert(to_archive_name(&archive_path), ArchiveSource::File(path));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn to_archive_name(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// 文字列リテラルが templates 以下の既存ファイルを指していればそのテンプレート ID を返す。
+/// `/` 始まりのパスは `compile_root` がある場合のみそこからの参照として解決する。
+fn referenced_template(
+    source: &Path,
+    literal: &str,
+    templates_root: &Path,
+    compile_root: Option<&Path>,
+) -> Option<String> {
+    if literal.is_empty() || literal.starts_with('@') {
+        return None;
+    }
+    let target = match literal.strip_prefix('/') {
+        Some(rooted) => normalize(&compile_root?.join(rooted)),
+        None => normalize(&source.parent()?.join(literal)),
+    };
+    let relative = target.strip_prefix(normalize(templates_root)).ok()?;
+    let id = relative.components().next()?.as_os_str().to_str()?;
+    target.exists().then(|| id.to_string())
+}
+
+/// `..` や `.` を字句的に畳み込む（シンボリックリンクは解決しない）
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// 展開したアーカイブのルートで実行する `typst compile` の引数
+fn compile_command(
+    bundle: &Bundle,
+    loaded_project: &Loaded<Project, ProjectConfig>,
+    loaded_paper: &Loaded<Paper, PaperConfig>,
+    font_dirs: Vec<PathBuf>,
+    needs_root: bool,
+    has_packages: bool,
+) -> Vec<String> {
+    let build = loaded_paper.build_config();
+    let mut font_paths: Vec<PathBuf> = loaded_paper
+        .font_paths()
+        .iter()
+        .map(|path| bundle.archive_path(path))
+        .collect();
+    font_paths.extend(font_dirs);
+    // README には OS に依らず `/` 区切りで書く（--input などの値はそのまま渡す）
+    let font_paths = font_paths.iter().map(|path| archive_arg(path)).collect();
+
+    let command = TypstCommand::Compile {
+        source: archive_arg(&bundle.archive_path(&loaded_paper.main_typ_path())),
+        output: Some(PathBuf::from(format!(
+            "{}.pdf",
+            loaded_paper.output_base_name()
+        ))),
+        features: Vec::new(),
+        diagnostic_format: None,
+        options: Box::new(CompileOptions {
+            // 論文ディレクトリの外にあるテンプレートや共有ディレクトリを読めるようにする
+            root: needs_root.then(|| PathBuf::from(".")),
+            pages: build.pages.clone(),
+            inputs: build.inputs.clone(),
+            font_paths,
+            ignore_system_fonts: loaded_project.fonts().ignore_system_fonts,
+            pdf_standard: build.pdf_standard.clone(),
+            package_path: has_packages.then(|| PathBuf::from(BUNDLE_PACKAGES_DIR)),
+            ..Default::default()
+        }),
+    };
+
+    let mut args = vec!["typst".to_string()];
+    args.extend(command.to_args());
+    args
+}
+
+fn archive_arg(path: &Path) -> PathBuf {
+    PathBuf::from(to_archive_name(path))
+}
+
+fn readme(
+    title: &str,
+    typst_version: &str,
+    command: &[String],
+    packages: &BTreeSet<PackageSpec>,
+) -> String {
+    let mut readme = format!(
+        "# {}\n\n\
+         This archive contains everything needed to compile the paper with plain Typst.\n\n\
+         ## Requirements\n\n\
+         - Typst {} (<https://github.com/typst/typst/releases/tag/v{}>)\n\n\
+         ## Build\n\n\
+         Run the following command from the directory this archive was extracted into:\n\n\
+         ```sh\n{}\n```\n",
+        title,
+        typst_version,
+        typst_version,
+        shell_join(command)
+    );
+    if !packages.is_empty() {
+        readme.push_str(&format!(
+            "\nPackages are vendored under `{}/` so no network access is required:\n\n",
+            BUNDLE_PACKAGES_DIR
+        ));
+        for spec in packages {
+            readme.push_str(&format!("- `{}`\n", spec));
+        }
+    }
+    readme
+}
+
+fn shell_join(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| {
+            let plain = !arg.is_empty()
+                && arg
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_./=:,@".contains(c));
+            if plain {
+                arg.clone()
+            } else {
+                format!("'{}'", arg.replace('\'', r"'\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use tempfile::TempDir;
+
+    fn read_entry(archive: &mut zip::ZipArchive<std::fs::File>, name: &str) -> String {
+        let mut content = String::new();
+        archive
+            .by_name(name)
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        content
+    }
+
+    #[test]
+    fn test_export_bundles_templates_packages_and_fonts() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("project");
+        let paper_dir = root.join("papers").join("p01");
+        std::fs::create_dir_all(&paper_dir).unwrap();
+        std::fs::write(
+            paper_dir.join("paper.toml"),
+            "[paper]\ntitle = \"My Paper\"\n\n[build]\ninputs = { draft = \"false\" }\n",
+        )
+        .unwrap();
+        std::fs::write(
+            paper_dir.join("main.typ"),
+            "#import \"../../templates/ieee/lib.typ\": conf\n\
+             #import \"@preview/cetz:0.3.4\"\n\
+             #import \"@preview/missing:1.0.0\"\n= Hello\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(root.join("templates").join("ieee")).unwrap();
+        std::fs::create_dir_all(root.join("templates").join("unused")).unwrap();
+        std::fs::write(
+            root.join("templates").join("ieee").join("lib.typ"),
+            "#import \"@preview/oxifmt:0.2.1\": strfmt\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("templates").join("unused").join("lib.typ"), "").unwrap();
+        std::fs::create_dir_all(root.join("fonts")).unwrap();
+        std::fs::write(root.join("fonts").join("Serif.otf"), b"font").unwrap();
+
+        let packages = temp.path().join("typst-packages");
+        for (name, version) in [("cetz", "0.3.4"), ("oxifmt", "0.2.1")] {
+            let dir = packages.join("preview").join(name).join(version);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("lib.typ"), "").unwrap();
+            std::fs::write(dir.join("typst.toml"), "").unwrap();
+        }
+
+        let out = temp.path().join("bundle.zip");
+        let mut warnings = Vec::new();
+        let output = ExportAction::new(
+            Loaded {
+                actual: Project::new(root.clone()),
+                config: ProjectConfig::default(),
+            },
+            "p01".to_string(),
+            out.clone(),
+        )
+        .with_package_dirs(vec![packages])
+        .run(&mut |_| {}, &mut |warning| warnings.push(warning))
+        .unwrap();
+
+        assert_eq!(output.templates, vec!["ieee".to_string()]);
+        assert_eq!(
+            output.packages,
+            vec![
+                "@preview/cetz:0.3.4".to_string(),
+                "@preview/oxifmt:0.2.1".to_string()
+            ]
+        );
+        assert_eq!(
+            warnings,
+            vec![ExportWarning::PackageNotFound(
+                "@preview/missing:1.0.0".to_string()
+            )]
+        );
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&out).unwrap()).unwrap();
+        let mut names: Vec<_> = archive.file_names().map(str::to_string).collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "README.md",
+                "fonts/Serif.otf",
+                "packages/preview/cetz/0.3.4/lib.typ",
+                "packages/preview/cetz/0.3.4/typst.toml",
+                "packages/preview/oxifmt/0.2.1/lib.typ",
+                "packages/preview/oxifmt/0.2.1/typst.toml",
+                "papers/p01/main.typ",
+                "papers/p01/paper.toml",
+                "templates/ieee/lib.typ",
+            ]
+        );
+        assert_eq!(output.files, names.len());
+
+        let readme = read_entry(&mut archive, "README.md");
+        let version = ProjectConfig::default().toolchain.typst;
+        assert!(readme.contains(&format!("Typst {}", version)));
+        assert!(readme.contains(
+            "typst compile papers/p01/main.typ --root . --package-path packages \
+             --input draft=false --font-path fonts main.pdf"
+        ));
+    }
+
+    #[test]
+    fn test_export_command_keeps_non_path_arguments_verbatim() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("project");
+        let paper_dir = root.join("papers").join("p01");
+        std::fs::create_dir_all(&paper_dir).unwrap();
+        std::fs::write(
+            paper_dir.join("paper.toml"),
+            "[paper]\ntitle = \"My Paper\"\n\n[build]\ninputs = { url = \"https://x//y/../z\" }\n",
+        )
+        .unwrap();
+        std::fs::write(paper_dir.join("main.typ"), "= Hello\n").unwrap();
+
+        let out = temp.path().join("bundle.zip");
+        ExportAction::new(
+            Loaded {
+                actual: Project::new(root),
+                config: ProjectConfig::default(),
+            },
+            "p01".to_string(),
+            out.clone(),
+        )
+        .run(&mut |_| {}, &mut |_| {})
+        .unwrap();
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&out).unwrap()).unwrap();
+        let readme = read_entry(&mut archive, "README.md");
+        assert!(
+            readme.contains(
+                "typst compile papers/p01/main.typ --input url=https://x//y/../z main.pdf"
+            ),
+            "{}",
+            readme
+        );
+    }
+
+    #[test]
+    fn test_export_readme_links_normalized_typst_version() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("project");
+        let paper_dir = root.join("papers").join("p01");
+        std::fs::create_dir_all(&paper_dir).unwrap();
+        std::fs::write(paper_dir.join("paper.toml"), "[paper]\ntitle = \"T\"\n").unwrap();
+        std::fs::write(paper_dir.join("main.typ"), "= Hello\n").unwrap();
+        let mut config = ProjectConfig::default();
+        let version = config.toolchain.typst.clone();
+        config.toolchain.typst = format!("v{}", version);
+
+        let out = temp.path().join("bundle.zip");
+        let output = ExportAction::new(
+            Loaded {
+                actual: Project::new(root),
+                config,
+            },
+            "p01".to_string(),
+            out.clone(),
+        )
+        .run(&mut |_| {}, &mut |_| {})
+        .unwrap();
+
+        assert_eq!(output.typst_version, version);
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&out).unwrap()).unwrap();
+        let readme = read_entry(&mut archive, "README.md");
+        assert!(readme.contains(&format!(
+            "Typst {} (<https://github.com/typst/typst/releases/tag/v{}>)",
+            version, version
+        )));
+    }
+
+    #[test]
+    fn test_export_bundles_shared_dir_and_rooted_template_imports() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().to_path_buf();
+        let paper_dir = root.join("papers").join("p01");
+        std::fs::create_dir_all(&paper_dir).unwrap();
+        std::fs::write(
+            paper_dir.join("paper.toml"),
+            "[paper]\ntitle = \"Shared\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            paper_dir.join("main.typ"),
+            "#import \"/lib/macros.typ\": *\n= Hello\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(root.join("lib")).unwrap();
+        std::fs::write(
+            root.join("lib").join("macros.typ"),
+            "#import \"/templates/acm/lib.typ\": conf\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(root.join("templates").join("acm")).unwrap();
+        std::fs::write(root.join("templates").join("acm").join("lib.typ"), "").unwrap();
+
+        let mut config = ProjectConfig::default();
+        config.structure.shared_dir = Some(PathBuf::from("lib"));
+        let out = temp.path().join("bundle.zip");
+        let output = ExportAction::new(
+            Loaded {
+                actual: Project::new(root.clone()),
+                config,
+            },
+            "p01".to_string(),
+            out.clone(),
+        )
+        .run(&mut |_| {}, &mut |_| {})
+        .unwrap();
+
+        assert_eq!(output.templates, vec!["acm".to_string()]);
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&out).unwrap()).unwrap();
+        assert!(archive.by_name("lib/macros.typ").is_ok());
+        assert!(archive.by_name("templates/acm/lib.typ").is_ok());
+        let readme = read_entry(&mut archive, "README.md");
+        assert!(readme.contains("typst compile papers/p01/main.typ --root . main.pdf"));
+    }
+
+    #[test]
+    fn test_export_fails_for_unknown_paper() {
+        let temp = TempDir::new().unwrap();
+
+        let errors = ExportAction::new(
+            Loaded {
+                actual: Project::new(temp.path().to_path_buf()),
+                config: ProjectConfig::default(),
+            },
+            "nope".to_string(),
+            temp.path().join("bundle.zip"),
+        )
+        .run(&mut |_| {}, &mut |_| {})
+        .unwrap_err();
+
+        assert!(matches!(errors.as_slice(), [ExportError::Discovery(_)]));
+        assert!(!temp.path().join("bundle.zip").exists());
+    }
+}
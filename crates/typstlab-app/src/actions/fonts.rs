@@ -0,0 +1,156 @@
+use crate::models::{Project, ProjectConfig, ProjectHandle};
+use serde::Serialize;
+use std::path::PathBuf;
+use thiserror::Error;
+use typstlab_base::driver::{TypstCommand, TypstDriver};
+use typstlab_proto::{Action, AppEvent, Loaded};
+
+/// プロジェクトの設定で Typst から見えるフォントの一覧
+#[derive(Serialize, Debug, Clone)]
+pub struct FontList {
+    pub families: Vec<String>,
+    /// 実際に検索対象になったプロジェクトのフォントディレクトリ
+    pub font_dirs: Vec<PathBuf>,
+    pub ignore_system_fonts: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum FontsWarning {
+    /// `[fonts].dirs` に書かれているが存在しないディレクトリ
+    FontDirNotFound(PathBuf),
+}
+
+#[derive(Debug, Error)]
+pub enum FontsError {
+    #[error("failed to run typst fonts: {0}")]
+    Driver(String),
+    #[error("typst fonts exited with code {exit_code}: {stderr}")]
+    Typst { exit_code: i32, stderr: String },
+}
+
+/// `typst fonts` をプロジェクトのフォント設定付きで実行するアクション
+pub struct FontsAction {
+    pub loaded_project: Loaded<Project, ProjectConfig>,
+    pub typst_driver: TypstDriver,
+}
+
+impl FontsAction {
+    pub fn new(loaded_project: Loaded<Project, ProjectConfig>, typst_driver: TypstDriver) -> Self {
+        Self {
+            loaded_project,
+            typst_driver,
+        }
+    }
+}
+
+impl Action for FontsAction {
+    type Output = FontList;
+    type Event = ();
+    type Warning = FontsWarning;
+    type Error = FontsError;
+
+    fn run(
+        self,
+        _monitor: &mut dyn FnMut(AppEvent<Self::Event>),
+        warning: &mut dyn FnMut(Self::Warning),
+    ) -> Result<Self::Output, Vec<Self::Error>> {
+        let mut font_dirs = Vec::new();
+        for dir in self.loaded_project.font_dirs() {
+            if dir.is_dir() {
+                font_dirs.push(dir);
+            } else {
+                warning(FontsWarning::FontDirNotFound(dir));
+            }
+        }
+        let ignore_system_fonts = self.loaded_project.fonts().ignore_system_fonts;
+
+        let result = self
+            .typst_driver
+            .execute(TypstCommand::Fonts {
+                font_paths: font_dirs.clone(),
+                ignore_system_fonts,
+            })
+            .map_err(|error| vec![FontsError::Driver(error.to_string())])?;
+
+        if result.exit_code != 0 {
+            return Err(vec![FontsError::Typst {
+                exit_code: result.exit_code,
+                stderr: result.stderr,
+            }]);
+        }
+
+        Ok(FontList {
+            families: parse_families(&result.stdout),
+            font_dirs,
+            ignore_system_fonts,
+        })
+    }
+}
+
+/// `typst fonts` の出力 (1 行 1 ファミリー) を読む
+fn parse_families(stdout: &str) -> Vec<String> {
+    stdout
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_families_skips_blank_lines() {
+        let families = parse_families("Libertinus Serif\n\nNew Computer Modern\r\n");
+
+        assert_eq!(
+            families,
+            vec![
+                "Libertinus Serif".to_string(),
+                "New Computer Modern".to_string()
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fonts_passes_project_font_settings_to_typst() {
+        let temp = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp.path().join("fonts")).unwrap();
+        let typst = crate::actions::test_support::fake_typst(
+            temp.path(),
+            "for arg; do echo \"$arg\"; done\n",
+        );
+
+        let mut project = Loaded {
+            actual: Project::new(temp.path().to_path_buf()),
+            config: ProjectConfig::default(),
+        };
+        project.config.fonts.dirs.push(PathBuf::from("missing"));
+        project.config.fonts.ignore_system_fonts = true;
+        let mut warnings = Vec::new();
+
+        let list = FontsAction::new(project, TypstDriver::new(typst))
+            .run(&mut |_| {}, &mut |warning| warnings.push(warning))
+            .unwrap();
+
+        let fonts_dir = temp.path().join("fonts");
+        assert_eq!(
+            list.families,
+            vec![
+                "fonts".to_string(),
+                "--font-path".to_string(),
+                fonts_dir.to_string_lossy().to_string(),
+                "--ignore-system-fonts".to_string(),
+            ]
+        );
+        assert_eq!(list.font_dirs, vec![fonts_dir]);
+        assert_eq!(
+            warnings,
+            vec![FontsWarning::FontDirNotFound(temp.path().join("missing"))]
+        );
+    }
+}
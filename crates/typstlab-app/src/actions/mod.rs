@@ -1,25 +1,69 @@
 pub mod bootstrap;
 pub mod build;
+pub mod check;
+pub mod check_refs;
+pub mod clean;
 pub mod create;
+pub mod diff;
 pub mod discovery;
 pub mod download_docs;
+pub mod export;
+pub mod fonts;
 pub mod gen_paper;
 pub mod gen_template;
 pub mod load;
+pub mod query;
 pub mod resolve_docs;
 pub mod resolve_typst;
+pub mod snapshot_test;
+pub mod stats;
 pub mod status;
+#[cfg(all(test, unix))]
+mod test_support;
+pub mod toolchain;
 pub mod toolchain_resolve;
+pub mod watch;
 
-pub use bootstrap::{AppContext, BootstrapAction, BootstrapError, BootstrapEvent};
-pub use build::{BuildAction, BuildError, BuildEvent, BuildFormat, BuildWarning, DistObject};
+pub use bootstrap::{
+    AppContext, BootstrapAction, BootstrapError, BootstrapEvent, BootstrapWarning, DOCS_MIRROR_ENV,
+    OFFLINE_ENV, TYPST_MIRROR_ENV, mirrors_from_env, offline_from_env,
+};
+pub use build::{
+    BuildAction, BuildError, BuildEvent, BuildFormat, BuildOverrides, BuildWarning, DistObject,
+    default_jobs,
+};
+pub use check::{CheckAction, CheckError, CheckEvent, CheckOutput, CheckWarning, PaperCheck};
+pub use check_refs::{
+    CheckRefsAction, CheckRefsError, CheckRefsEvent, CheckRefsOutput, CheckRefsWarning,
+    PaperRefsReport, RefIssue, RefIssueKind,
+};
+pub use clean::{
+    CleanAction, CleanEntry, CleanError, CleanKind, CleanOutput, CleanWarning, DistSelection,
+};
 pub use create::{CreateAction, CreateError, CreateEvent};
+pub use diff::{DiffAction, DiffError, DiffEvent, DiffOutput, PageDiff, PageStatus};
 pub use discovery::{DiscoveryAction, DiscoveryError};
 pub use download_docs::{DownloadDocsAction, DownloadDocsError, DownloadDocsEvent};
+pub use export::{ExportAction, ExportError, ExportOutput, ExportWarning};
+pub use fonts::{FontList, FontsAction, FontsError, FontsWarning};
+pub use query::{QueryAction, QueryError, QueryOutput};
 pub use resolve_docs::ResolveDocsAction;
-pub use resolve_typst::{ResolveEvent, ResolveTypstAction, StoreError};
+pub use resolve_typst::{ResolveEvent, ResolveTypstAction, ResolveWarning, StoreError};
+pub use snapshot_test::{
+    SnapshotKind, SnapshotResult, SnapshotStatus, SnapshotTestAction, SnapshotTestError,
+    SnapshotTestEvent, SnapshotTestOutput, SnapshotTestWarning,
+};
+pub use stats::{
+    OutlineEntry, PaperStats, StatsAction, StatsError, StatsEvent, StatsOutput, StatsWarning,
+};
 pub use status::{StatusAction, StatusError, StatusOutput, StatusWarning};
+pub use toolchain::{
+    InstalledTypst, ToolchainError, ToolchainInstallAction, ToolchainInstallOutput,
+    ToolchainListAction, ToolchainListOutput, ToolchainRemoveAction, ToolchainUseAction,
+    ToolchainUseOutput, ToolchainWarning,
+};
 pub use toolchain_resolve::{
     ToolChain, ToolchainResolveAction, ToolchainResolveError, ToolchainResolveEvent,
-    ToolchainResolveInput,
+    ToolchainResolveInput, ToolchainResolveWarning,
 };
+pub use watch::{WatchAction, WatchError, WatchEvent, WatchWarning};
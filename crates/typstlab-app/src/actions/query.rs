@@ -0,0 +1,211 @@
+use crate::actions::build::{BuildOverrides, compile_options};
+use crate::actions::discovery::{DiscoveryAction, DiscoveryError};
+use crate::models::{PaperError, PaperHandle, Project, ProjectConfig, ProjectHandle};
+use thiserror::Error;
+use typstlab_base::diagnostics::{BuildDiagnostic, parse_diagnostics};
+use typstlab_base::driver::{QueryFormat, TypstCommand, TypstDriver};
+use typstlab_proto::{Action, AppEvent, Loadable, Loaded};
+
+/// `typst query` の出力（JSON / YAML のまま）
+#[derive(Debug, Clone)]
+pub struct QueryOutput {
+    pub paper_id: String,
+    pub format: QueryFormat,
+    pub content: String,
+}
+
+#[derive(Error, Debug)]
+pub enum QueryError {
+    #[error("Discovery failure: {0:?}")]
+    Discovery(Vec<DiscoveryError>),
+    #[error("Failed to load paper '{paper_id}': {source}")]
+    PaperLoad {
+        paper_id: String,
+        #[source]
+        source: PaperError,
+    },
+    #[error("Failed to run typst query: {0}")]
+    Driver(String),
+    #[error("typst query failed for '{paper_id}'")]
+    Typst {
+        paper_id: String,
+        diagnostics: Vec<BuildDiagnostic>,
+        stderr: String,
+    },
+}
+
+/// 論文のエントリポイントと `[build]` の入力を使って `typst query` を実行するアクション
+pub struct QueryAction {
+    pub loaded_project: Loaded<Project, ProjectConfig>,
+    pub typst_driver: TypstDriver,
+    /// 論文 ID またはパス
+    pub paper_input: String,
+    pub selector: String,
+    pub field: Option<String>,
+    pub one: bool,
+    pub format: QueryFormat,
+}
+
+impl QueryAction {
+    pub fn new(
+        loaded_project: Loaded<Project, ProjectConfig>,
+        typst_driver: TypstDriver,
+        paper_input: String,
+        selector: String,
+    ) -> Self {
+        Self {
+            loaded_project,
+            typst_driver,
+            paper_input,
+            selector,
+            field: None,
+            one: false,
+            format: QueryFormat::default(),
+        }
+    }
+
+    pub fn with_field(mut self, field: Option<String>) -> Self {
+        self.field = field;
+        self
+    }
+
+    pub fn with_one(mut self, one: bool) -> Self {
+        self.one = one;
+        self
+    }
+
+    pub fn with_format(mut self, format: QueryFormat) -> Self {
+        self.format = format;
+        self
+    }
+}
+
+impl Action for QueryAction {
+    type Output = QueryOutput;
+    type Event = ();
+    type Warning = ();
+    type Error = QueryError;
+
+    fn run(
+        self,
+        _monitor: &mut dyn FnMut(AppEvent<Self::Event>),
+        _warning: &mut dyn FnMut(Self::Warning),
+    ) -> Result<Self::Output, Vec<Self::Error>> {
+        let paper = DiscoveryAction::new(
+            self.loaded_project.papers_scope(),
+            vec![self.paper_input.clone()],
+        )
+        .run(&mut |_| {}, &mut |_| {})
+        .map_err(|errors| vec![QueryError::Discovery(errors)])?
+        .remove(0);
+        let paper_id = paper.id.clone();
+        let loaded_paper = paper
+            .load()
+            .map_err(|source| vec![QueryError::PaperLoad { paper_id, source }])?;
+        let paper_id = loaded_paper.paper_id().to_string();
+
+        let options = compile_options(
+            &self.loaded_project,
+            &loaded_paper,
+            None,
+            &BuildOverrides::default(),
+        );
+        let result = self
+            .typst_driver
+            .execute(TypstCommand::Query {
+                source: loaded_paper.main_typ_path(),
+                selector: self.selector,
+                field: self.field,
+                one: self.one,
+                format: self.format,
+                options: Box::new(options),
+            })
+            .map_err(|error| vec![QueryError::Driver(error.to_string())])?;
+
+        if result.exit_code != 0 {
+            return Err(vec![QueryError::Typst {
+                paper_id,
+                diagnostics: parse_diagnostics(&result.stderr),
+                stderr: result.stderr,
+            }]);
+        }
+
+        Ok(QueryOutput {
+            paper_id,
+            format: self.format,
+            content: result.stdout,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+    use tempfile::TempDir;
+
+    #[cfg(unix)]
+    fn fake_typst(dir: &Path) -> PathBuf {
+        crate::actions::test_support::fake_typst(
+            dir,
+            "for arg; do last=\"$arg\"; done\n\
+             if [ \"$last\" = \"bad\" ]; then echo \"error: unknown variable: bad\" >&2; exit 1; fi\n\
+             echo \"$@\"\n",
+        )
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_query_uses_entry_point_and_inputs() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("project");
+        let paper = root.join("papers").join("p01");
+        std::fs::create_dir_all(&paper).unwrap();
+        std::fs::write(
+            paper.join("paper.toml"),
+            "[paper]\ntitle = \"T\"\nentry_point = \"thesis.typ\"\n\n[build.inputs]\nlang = \"ja\"\n",
+        )
+        .unwrap();
+        std::fs::write(paper.join("thesis.typ"), "").unwrap();
+        let project = || Loaded {
+            actual: Project::new(root.clone()),
+            config: ProjectConfig::default(),
+        };
+        let typst = fake_typst(temp.path());
+
+        let output = QueryAction::new(
+            project(),
+            TypstDriver::new(typst.clone()),
+            "p01".to_string(),
+            "<title>".to_string(),
+        )
+        .with_field(Some("value".to_string()))
+        .with_one(true)
+        .with_format(QueryFormat::Yaml)
+        .run(&mut |_| {}, &mut |_| {})
+        .unwrap();
+
+        assert_eq!(output.paper_id, "p01");
+        assert_eq!(
+            output.content.trim(),
+            format!(
+                "query --input lang=ja --field value --one --format yaml {} <title>",
+                paper.join("thesis.typ").display()
+            )
+        );
+
+        let errors = QueryAction::new(
+            project(),
+            TypstDriver::new(typst),
+            "p01".to_string(),
+            "bad".to_string(),
+        )
+        .run(&mut |_| {}, &mut |_| {})
+        .unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [QueryError::Typst { paper_id, diagnostics, .. }]
+                if paper_id == "p01" && diagnostics[0].message == "unknown variable: bad"
+        ));
+    }
+}
@@ -6,7 +6,7 @@ use typstlab_base::project_docs::{ProjectDocs, sync_project_docs};
 use typstlab_proto::{Action, AppEvent, Collection, EventScope, Installer, Store};
 
 use crate::actions::download_docs::{DownloadDocsAction, DownloadDocsError, DownloadDocsEvent};
-use crate::actions::resolve_typst::{ResolveEvent, StoreError};
+use crate::actions::resolve_typst::{ResolveEvent, ResolveWarning, StoreError};
 use crate::models::{Docs, DocsStore};
 
 #[derive(Debug, Error)]
@@ -41,15 +41,16 @@ where
 {
     type Output = Docs;
     type Event = ResolveEvent;
-    type Warning = ();
+    type Warning = ResolveWarning;
     type Error = ResolveDocsError<I::Error>;
 
     fn run(
         self,
         monitor: &mut dyn FnMut(AppEvent<ResolveEvent>),
-        _warning: &mut dyn FnMut(Self::Warning),
+        warning: &mut dyn FnMut(Self::Warning),
     ) -> Result<Self::Output, Vec<Self::Error>> {
-        self.run_inner(monitor).map_err(|error| vec![error])
+        self.run_inner(monitor, warning)
+            .map_err(|error| vec![error])
     }
 }
 
@@ -60,6 +61,7 @@ where
     fn run_inner(
         self,
         monitor: &mut dyn FnMut(AppEvent<ResolveEvent>),
+        warning: &mut dyn FnMut(ResolveWarning),
     ) -> Result<Docs, ResolveDocsError<I::Error>> {
         let scope = EventScope::labeled("resolve_docs", self.version.clone());
         monitor(AppEvent::verbose(
@@ -74,7 +76,32 @@ where
             return Ok(Docs::new(synced));
         }
 
+        let _lock = match self.store.try_lock(&self.version)? {
+            Some(lock) => lock,
+            None => {
+                monitor(AppEvent::line(
+                    scope.clone(),
+                    ResolveEvent::WaitingForLock {
+                        path: self.store.lock_path(&self.version),
+                    },
+                ));
+                self.store.lock(&self.version)?
+            }
+        };
+        // 待っている間に他のプロセスが入れ終えていればそれを使う
+        if let Some(docs) = self.store.resolve(&self.version)? {
+            monitor(AppEvent::verbose(scope.clone(), ResolveEvent::CacheHit));
+            let synced = sync_project_docs(&self.project_root, ProjectDocs::Typst, docs.path)?;
+            monitor(AppEvent::verbose(scope, ResolveEvent::Completed));
+            return Ok(Docs::new(synced));
+        }
+
         monitor(AppEvent::line(scope.clone(), ResolveEvent::CacheMiss));
+        if self.link.sha256.is_none() {
+            warning(ResolveWarning::UnverifiedDownload {
+                url: self.link.url.clone(),
+            });
+        }
 
         let download = DownloadDocsAction {
             installer: self.installer,
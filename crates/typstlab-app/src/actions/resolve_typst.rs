@@ -1,4 +1,5 @@
 use crate::models::{Typst, TypstStore};
+use std::path::PathBuf;
 use thiserror::Error;
 use typstlab_base::install::{InstallProvider, TypstInstaller};
 use typstlab_base::link_resolver::ResolvedLink;
@@ -28,10 +29,21 @@ where
 pub enum ResolveEvent {
     CheckingCache,
     CacheHit,
+    /// 他のプロセスが同じバージョンをインストールしている
+    WaitingForLock {
+        path: PathBuf,
+    },
     CacheMiss,
     Completed,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolveWarning {
+    /// チェックサム表にバージョンごと載っていないため、内容を検証せずにインストールした
+    /// （載っているバージョンで配布物が欠けていればリンク解決の段階でエラーになる）
+    UnverifiedDownload { url: String },
+}
+
 pub struct ResolveTypstAction<P>
 where
     P: InstallProvider,
@@ -48,15 +60,16 @@ where
 {
     type Output = Typst;
     type Event = ResolveEvent;
-    type Warning = ();
+    type Warning = ResolveWarning;
     type Error = ResolveTypstError<typstlab_base::install::TypstInstallError>;
 
     fn run(
         self,
         monitor: &mut dyn FnMut(AppEvent<ResolveEvent>),
-        _warning: &mut dyn FnMut(Self::Warning),
+        warning: &mut dyn FnMut(Self::Warning),
     ) -> Result<Self::Output, Vec<Self::Error>> {
-        self.run_inner(monitor).map_err(|error| vec![error])
+        self.run_inner(monitor, warning)
+            .map_err(|error| vec![error])
     }
 }
 
@@ -67,6 +80,7 @@ where
     fn run_inner(
         self,
         monitor: &mut dyn FnMut(AppEvent<ResolveEvent>),
+        warning: &mut dyn FnMut(ResolveWarning),
     ) -> Result<Typst, ResolveTypstError<typstlab_base::install::TypstInstallError>> {
         let scope = EventScope::labeled("resolve_typst", self.version.clone());
         monitor(AppEvent::verbose(
@@ -80,7 +94,31 @@ where
             return Ok(typst);
         }
 
+        let _lock = match self.store.try_lock(&self.version)? {
+            Some(lock) => lock,
+            None => {
+                monitor(AppEvent::line(
+                    scope.clone(),
+                    ResolveEvent::WaitingForLock {
+                        path: self.store.lock_path(&self.version),
+                    },
+                ));
+                self.store.lock(&self.version)?
+            }
+        };
+        // 待っている間に他のプロセスが入れ終えていればそれを使う
+        if let Some(typst) = self.store.resolve(&self.version)? {
+            monitor(AppEvent::verbose(scope.clone(), ResolveEvent::CacheHit));
+            monitor(AppEvent::verbose(scope, ResolveEvent::Completed));
+            return Ok(typst);
+        }
+
         monitor(AppEvent::line(scope.clone(), ResolveEvent::CacheMiss));
+        if self.link.sha256.is_none() {
+            warning(ResolveWarning::UnverifiedDownload {
+                url: self.link.url.clone(),
+            });
+        }
 
         let installation = self
             .installer
@@ -176,6 +214,7 @@ mod tests {
             format: SourceFormat::TarXz {
                 strip_components: 1,
             },
+            sha256: None,
         }
     }
 
@@ -192,14 +231,24 @@ mod tests {
             link: link(),
         };
         let mut events = Vec::new();
+        let mut warnings = Vec::new();
 
         let typst = action
-            .run(&mut |event| events.push(event), &mut |_| {})
+            .run(&mut |event| events.push(event), &mut |warning| {
+                warnings.push(warning)
+            })
             .unwrap();
 
         assert!(called.load(Ordering::SeqCst));
         assert_eq!(typst.version, "0.14.2");
         assert!(typst.binary_path.exists());
+        // チェックサムが無いリンクは検証できなかったことを知らせる
+        assert_eq!(
+            warnings,
+            vec![ResolveWarning::UnverifiedDownload {
+                url: "https://example.com/typst.tar.xz".to_string()
+            }]
+        );
         assert!(store.resolve("0.14.2").unwrap().is_some());
         assert!(
             events
@@ -228,13 +277,17 @@ mod tests {
             link: link(),
         };
         let mut events = Vec::new();
+        let mut warnings = Vec::new();
 
         let typst = action
-            .run(&mut |event| events.push(event), &mut |_| {})
+            .run(&mut |event| events.push(event), &mut |warning| {
+                warnings.push(warning)
+            })
             .unwrap();
 
         assert!(!called.load(Ordering::SeqCst));
         assert_eq!(typst.version, "0.14.2");
+        assert!(warnings.is_empty());
         assert!(
             events
                 .iter()
@@ -247,6 +300,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_resolve_typst_waits_for_another_install_and_reuses_it() {
+        let temp = TempDir::new().unwrap();
+        let store = TypstStore::new(temp.path().join("typst"));
+        let held = store.try_lock("0.14.2").unwrap().unwrap();
+        let provider = FakeProvider::new(empty_archive());
+        let called = provider.called.clone();
+        let action = ResolveTypstAction {
+            store: store.clone(),
+            version: "0.14.2".to_string(),
+            installer: TypstInstaller::new(provider),
+            link: link(),
+        };
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        let resolver = std::thread::spawn(move || {
+            action.run(
+                &mut |event| sender.send(event.payload).unwrap(),
+                &mut |_| {},
+            )
+        });
+        // 待ち始めたのを確かめてから、他のプロセスとしてインストールを終える
+        while !matches!(
+            receiver.recv().unwrap(),
+            ResolveEvent::WaitingForLock { .. }
+        ) {}
+        std::fs::create_dir_all(store.typst_path("0.14.2")).unwrap();
+        std::fs::write(store.binary_path("0.14.2"), b"typst").unwrap();
+        drop(held);
+
+        let typst = resolver.join().unwrap().unwrap();
+        let events = receiver.iter().collect::<Vec<_>>();
+
+        assert!(!called.load(Ordering::SeqCst));
+        assert_eq!(typst.version, "0.14.2");
+        assert!(events.contains(&ResolveEvent::CacheHit));
+        assert!(!events.contains(&ResolveEvent::CacheMiss));
+    }
+
     #[test]
     fn test_resolve_typst_errors_when_install_does_not_produce_binary() {
         let temp = TempDir::new().unwrap();
@@ -0,0 +1,526 @@
+use crate::actions::build::{BuildOverrides, compile_options, throwaway_command};
+use crate::actions::check::error_diagnostic;
+use crate::actions::diff::{PageDiff, PageStatus, compare_pages, png_pages};
+use crate::models::{
+    CollectionError, Paper, Project, ProjectConfig, ProjectHandle, Template, TestConfig,
+};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use typstlab_base::diagnostics::{BuildDiagnostic, DiagnosticSeverity, parse_diagnostics};
+use typstlab_base::driver::{CompileOptions, DiagnosticFormat, TypstCommand, TypstDriver};
+use typstlab_proto::{Action, AppEvent, Collection, Entity, EventScope, Loadable, Loaded};
+
+/// 失敗したスナップショットの差分画像を置く `.typstlab/` 以下のディレクトリ
+const REPORT_DIR: &str = "test-report";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotKind {
+    Paper,
+    Template,
+}
+
+impl SnapshotKind {
+    /// 基準画像・レポートのサブディレクトリ名
+    fn dir_name(self) -> &'static str {
+        match self {
+            SnapshotKind::Paper => "papers",
+            SnapshotKind::Template => "templates",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotStatus {
+    Passed,
+    Failed,
+    /// 基準画像がまだ無い
+    Missing,
+    /// `--update` で基準画像を書き換えた
+    Updated,
+    /// PNG に描画できなかった
+    Error,
+}
+
+/// 論文またはテンプレート 1 つ分の比較結果
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotResult {
+    pub kind: SnapshotKind,
+    pub id: String,
+    pub status: SnapshotStatus,
+    pub pages: Vec<PageDiff>,
+    /// 差分画像の出力先（失敗した場合のみ）
+    pub report_dir: Option<PathBuf>,
+    pub diagnostics: Vec<BuildDiagnostic>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SnapshotTestOutput {
+    pub results: Vec<SnapshotResult>,
+    pub tolerance: f64,
+}
+
+impl SnapshotTestOutput {
+    pub fn count(&self, status: SnapshotStatus) -> usize {
+        self.results
+            .iter()
+            .filter(|result| result.status == status)
+            .count()
+    }
+
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(|result| {
+            matches!(
+                result.status,
+                SnapshotStatus::Passed | SnapshotStatus::Updated
+            )
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SnapshotTestEvent {
+    Testing { kind: SnapshotKind, id: String },
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SnapshotTestWarning {
+    NoTargetsFound,
+}
+
+#[derive(Error, Debug)]
+pub enum SnapshotTestError {
+    #[error("Discovery failure: {0}")]
+    Discovery(#[from] CollectionError),
+    #[error("'{0}' is neither a paper nor a template")]
+    UnknownTarget(String),
+    #[error("Tolerance must be between 0.0 and 1.0, got {0}")]
+    InvalidTolerance(f64),
+    #[error("Failed to prepare a scratch directory: {0}")]
+    Scratch(#[source] std::io::Error),
+    #[error("Failed to write snapshots for '{id}': {source}")]
+    Io {
+        id: String,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+enum Target {
+    Paper(Paper),
+    Template(Template),
+}
+
+/// 論文とテンプレートを PNG に描画し、`tests/` の基準画像と比べるアクション
+pub struct SnapshotTestAction {
+    pub loaded_project: Loaded<Project, ProjectConfig>,
+    pub typst_driver: TypstDriver,
+    /// 論文またはテンプレートの ID・パス。None ならすべて
+    pub inputs: Option<Vec<String>>,
+    /// None なら `[test].tolerance`
+    pub tolerance: Option<f64>,
+    /// true なら一致しなかった基準画像を現在の出力で置き換える
+    pub update: bool,
+}
+
+impl SnapshotTestAction {
+    pub fn new(
+        loaded_project: Loaded<Project, ProjectConfig>,
+        typst_driver: TypstDriver,
+        inputs: Option<Vec<String>>,
+    ) -> Self {
+        Self {
+            loaded_project,
+            typst_driver,
+            inputs,
+            tolerance: None,
+            update: false,
+        }
+    }
+
+    pub fn with_tolerance(mut self, tolerance: Option<f64>) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn with_update(mut self, update: bool) -> Self {
+        self.update = update;
+        self
+    }
+
+    fn targets(&self) -> Result<Vec<Target>, SnapshotTestError> {
+        let papers = self.loaded_project.papers_scope();
+        let templates = self.loaded_project.templates_scope();
+        let Some(inputs) = &self.inputs else {
+            let mut all_templates = templates.list()?;
+            all_templates.sort_by(|a, b| a.id.cmp(&b.id));
+            return Ok(papers
+                .list()?
+                .into_iter()
+                .map(Target::Paper)
+                .chain(all_templates.into_iter().map(Target::Template))
+                .collect());
+        };
+
+        let mut targets = Vec::new();
+        for input in inputs {
+            if let Some(paper) = papers.resolve(input)? {
+                targets.push(Target::Paper(paper));
+            } else if let Some(template) = templates.resolve(input)? {
+                targets.push(Target::Template(template));
+            } else {
+                return Err(SnapshotTestError::UnknownTarget(input.clone()));
+            }
+        }
+        Ok(targets)
+    }
+
+    /// 描画コマンドを組み立てる。論文はビルドと同じ PNG 出力、テンプレートは `main.typ` をそのまま描く
+    fn render_command(
+        &self,
+        target: &Target,
+        diagnostic_format: Option<DiagnosticFormat>,
+        dir: &Path,
+    ) -> Result<TypstCommand, String> {
+        match target {
+            Target::Paper(paper) => {
+                let loaded_paper = paper.clone().load().map_err(|e| e.to_string())?;
+                let options = compile_options(
+                    &self.loaded_project,
+                    &loaded_paper,
+                    None,
+                    &BuildOverrides::default(),
+                );
+                Ok(throwaway_command(
+                    &loaded_paper,
+                    &self.loaded_project.build_artifact_scope(),
+                    "png",
+                    diagnostic_format,
+                    &options,
+                    dir,
+                ))
+            }
+            Target::Template(template) => {
+                let source = template.path().join("main.typ");
+                if !source.is_file() {
+                    return Err(format!("{} not found", source.display()));
+                }
+                let options = CompileOptions {
+                    root: self.loaded_project.compile_root(),
+                    font_paths: self
+                        .loaded_project
+                        .font_dirs()
+                        .into_iter()
+                        .filter(|dir| dir.is_dir())
+                        .collect(),
+                    ignore_system_fonts: self.loaded_project.fonts().ignore_system_fonts,
+                    ..Default::default()
+                };
+                Ok(TypstCommand::Compile {
+                    source,
+                    output: Some(dir.join("{0p}.png")),
+                    features: Vec::new(),
+                    diagnostic_format,
+                    options: Box::new(options),
+                })
+            }
+        }
+    }
+
+    fn test_target(
+        &self,
+        kind: SnapshotKind,
+        id: &str,
+        target: &Target,
+        tolerance: f64,
+        diagnostic_format: Option<DiagnosticFormat>,
+        scratch: &Path,
+    ) -> std::io::Result<SnapshotResult> {
+        let mut result = SnapshotResult {
+            kind,
+            id: id.to_string(),
+            status: SnapshotStatus::Passed,
+            pages: Vec::new(),
+            report_dir: None,
+            diagnostics: Vec::new(),
+        };
+
+        let dir = scratch.join(kind.dir_name()).join(id);
+        std::fs::create_dir_all(&dir)?;
+        let rendered = self
+            .render_command(target, diagnostic_format, &dir)
+            .and_then(|command| {
+                self.typst_driver
+                    .execute(command)
+                    .map_err(|e| e.to_string())
+            });
+        let diagnostics = match rendered {
+            Ok(res) if res.exit_code == 0 => parse_diagnostics(&res.stderr),
+            Ok(res) => {
+                let diagnostics = parse_diagnostics(&res.stderr);
+                result.status = SnapshotStatus::Error;
+                if diagnostics
+                    .iter()
+                    .any(|d| d.severity == DiagnosticSeverity::Error)
+                {
+                    diagnostics
+                } else {
+                    vec![error_diagnostic(res.stderr.trim().to_string())]
+                }
+            }
+            Err(message) => {
+                result.status = SnapshotStatus::Error;
+                vec![error_diagnostic(message)]
+            }
+        };
+        result.diagnostics = diagnostics;
+        if result.status == SnapshotStatus::Error {
+            return Ok(result);
+        }
+        let current = png_pages(&dir)?;
+
+        let snapshot_dir = self
+            .loaded_project
+            .snapshots_dir()
+            .join(kind.dir_name())
+            .join(id);
+        let reference = if snapshot_dir.is_dir() {
+            png_pages(&snapshot_dir)?
+        } else {
+            Vec::new()
+        };
+
+        if !reference.is_empty() {
+            let report_dir = self
+                .loaded_project
+                .cache_dir()
+                .join(REPORT_DIR)
+                .join(kind.dir_name())
+                .join(id);
+            // 前回の差分画像が残らないよう出力先を作り直す
+            if report_dir.exists() {
+                std::fs::remove_dir_all(&report_dir)?;
+            }
+            std::fs::create_dir_all(&report_dir)?;
+            result.pages = compare_pages(&current, &reference, &report_dir)
+                .map_err(|(path, e)| std::io::Error::other(format!("{}: {}", path.display(), e)))?;
+            let failed = result.pages.iter().any(|page| match page.status {
+                PageStatus::Unchanged => false,
+                PageStatus::Changed => {
+                    page.changed_pixels as f64 > tolerance * page.total_pixels as f64
+                }
+                PageStatus::Added | PageStatus::Removed => true,
+            });
+            if !failed {
+                std::fs::remove_dir_all(&report_dir)?;
+                return Ok(result);
+            }
+            result.status = SnapshotStatus::Failed;
+            result.report_dir = Some(report_dir);
+        } else {
+            result.status = SnapshotStatus::Missing;
+        }
+
+        if self.update {
+            if snapshot_dir.exists() {
+                std::fs::remove_dir_all(&snapshot_dir)?;
+            }
+            std::fs::create_dir_all(&snapshot_dir)?;
+            for page in &current {
+                if let Some(name) = page.file_name() {
+                    std::fs::copy(page, snapshot_dir.join(name))?;
+                }
+            }
+            result.status = SnapshotStatus::Updated;
+            result.report_dir = None;
+        }
+        Ok(result)
+    }
+}
+
+impl Action for SnapshotTestAction {
+    type Output = SnapshotTestOutput;
+    type Event = SnapshotTestEvent;
+    type Warning = SnapshotTestWarning;
+    type Error = SnapshotTestError;
+
+    fn run(
+        self,
+        monitor: &mut dyn FnMut(AppEvent<Self::Event>),
+        warning: &mut dyn FnMut(Self::Warning),
+    ) -> Result<Self::Output, Vec<Self::Error>> {
+        let tolerance = self
+            .tolerance
+            .unwrap_or(self.loaded_project.test_config().tolerance);
+        if !TestConfig::is_valid_tolerance(tolerance) {
+            return Err(vec![SnapshotTestError::InvalidTolerance(tolerance)]);
+        }
+        let targets = self.targets().map_err(|error| vec![error])?;
+        let mut output = SnapshotTestOutput {
+            results: Vec::new(),
+            tolerance,
+        };
+        if targets.is_empty() {
+            warning(SnapshotTestWarning::NoTargetsFound);
+            return Ok(output);
+        }
+
+        // 前回の失敗レポートが残らないようにする
+        let report_root = self.loaded_project.cache_dir().join(REPORT_DIR);
+        if report_root.exists() {
+            std::fs::remove_dir_all(&report_root)
+                .map_err(|e| vec![SnapshotTestError::Scratch(e)])?;
+        }
+        let tmp_dir = self.loaded_project.tmp_dir();
+        let scratch = std::fs::create_dir_all(&tmp_dir)
+            .and_then(|_| {
+                tempfile::Builder::new()
+                    .prefix("test-")
+                    .tempdir_in(&tmp_dir)
+            })
+            .map_err(|e| vec![SnapshotTestError::Scratch(e)])?;
+        let diagnostic_format = self
+            .typst_driver
+            .get_version()
+            .ok()
+            .as_ref()
+            .and_then(DiagnosticFormat::preferred_for);
+
+        for target in &targets {
+            let (kind, id) = match target {
+                Target::Paper(paper) => (SnapshotKind::Paper, paper.id.clone()),
+                Target::Template(template) => (SnapshotKind::Template, template.id.clone()),
+            };
+            monitor(AppEvent::line(
+                EventScope::labeled("test", id.clone()),
+                SnapshotTestEvent::Testing {
+                    kind,
+                    id: id.clone(),
+                },
+            ));
+            let result = self
+                .test_target(
+                    kind,
+                    &id,
+                    target,
+                    tolerance,
+                    diagnostic_format,
+                    scratch.path(),
+                )
+                .map_err(|source| vec![SnapshotTestError::Io { id, source }])?;
+            output.results.push(result);
+        }
+
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use typstlab_base::image_diff::{RgbaImage, write_png};
+
+    /// ソースの各行の数値を明るさとして、2x2 の PNG を 1 ページずつ描く偽の typst
+    #[cfg(unix)]
+    fn fake_typst(dir: &Path) -> PathBuf {
+        let palette = dir.join("palette");
+        std::fs::create_dir_all(&palette).unwrap();
+        for value in [0u8, 10, 255] {
+            let image = RgbaImage {
+                width: 2,
+                height: 2,
+                pixels: [value, value, value, 0xFF].repeat(4),
+            };
+            write_png(&palette.join(format!("{}.png", value)), &image).unwrap();
+        }
+        crate::actions::test_support::fake_typst(
+            dir,
+            &format!(
+                "for arg; do case \"$arg\" in *.typ) source=\"$arg\";; esac; done\n\
+                 for arg; do last=\"$arg\"; done\n\
+                 out=$(dirname \"$last\")\n\
+                 n=1\n\
+                 while read -r value; do cp \"{}/$value.png\" \"$out/$n.png\"; n=$((n+1)); done < \"$source\"\n",
+                palette.display()
+            ),
+        )
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_snapshots_for_papers_and_templates() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("project");
+        let paper = root.join("papers").join("p01");
+        std::fs::create_dir_all(&paper).unwrap();
+        std::fs::write(paper.join("paper.toml"), "[paper]\ntitle = \"T\"\n").unwrap();
+        std::fs::write(paper.join("main.typ"), "0\n").unwrap();
+        let template = root.join("templates").join("letter");
+        std::fs::create_dir_all(&template).unwrap();
+        std::fs::write(template.join("main.typ"), "255\n").unwrap();
+        let project = || Loaded {
+            actual: Project::new(root.clone()),
+            config: ProjectConfig::default(),
+        };
+        let typst = fake_typst(temp.path());
+        let run = |update: bool, tolerance: Option<f64>| {
+            SnapshotTestAction::new(project(), TypstDriver::new(typst.clone()), None)
+                .with_update(update)
+                .with_tolerance(tolerance)
+                .run(&mut |_| {}, &mut |_| {})
+                .unwrap()
+        };
+
+        // 基準画像が無ければ失敗し、--update で作られる
+        let output = run(false, None);
+        assert_eq!(output.count(SnapshotStatus::Missing), 2);
+        assert!(!output.passed());
+        let output = run(true, None);
+        assert_eq!(output.count(SnapshotStatus::Updated), 2);
+        assert!(root.join("tests/papers/p01/1.png").is_file());
+        assert!(root.join("tests/templates/letter/1.png").is_file());
+        assert!(run(false, None).passed());
+
+        // 論文の出力が変わると差分画像付きで失敗し、許容量を超えなければ通る
+        std::fs::write(paper.join("main.typ"), "10\n").unwrap();
+        let output = run(false, None);
+        let result = &output.results[0];
+        assert_eq!(
+            (result.kind, result.status),
+            (SnapshotKind::Paper, SnapshotStatus::Failed)
+        );
+        let report_dir = result.report_dir.clone().unwrap();
+        assert!(report_dir.join("page-1.png").is_file());
+        assert_eq!(output.results[1].status, SnapshotStatus::Passed);
+        assert!(run(false, Some(1.0)).passed());
+
+        // ページ数の違いは許容量に関係なく失敗する
+        std::fs::write(paper.join("main.typ"), "0\n0\n").unwrap();
+        assert!(!run(false, Some(1.0)).passed());
+    }
+
+    #[test]
+    fn test_snapshots_reject_tolerance_outside_unit_range() {
+        let temp = TempDir::new().unwrap();
+        for tolerance in [f64::NAN, f64::INFINITY, -0.5, 1.5] {
+            let result = SnapshotTestAction::new(
+                Loaded {
+                    actual: Project::new(temp.path().to_path_buf()),
+                    config: ProjectConfig::default(),
+                },
+                TypstDriver::new(fake_typst(temp.path())),
+                None,
+            )
+            .with_tolerance(Some(tolerance))
+            .run(&mut |_| {}, &mut |_| {});
+
+            assert!(matches!(
+                result.map(|_| ()).unwrap_err().as_slice(),
+                [SnapshotTestError::InvalidTolerance(_)]
+            ));
+        }
+    }
+}
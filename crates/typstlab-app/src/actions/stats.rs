@@ -0,0 +1,452 @@
+use crate::actions::build::{BuildOverrides, compile_options, throwaway_command};
+use crate::actions::check_refs::bibliography_files;
+use crate::actions::discovery::{DiscoveryAction, DiscoveryError};
+use crate::models::build_artifact_scope::OutputNaming;
+use crate::models::{
+    BuildArtifactScope, CollectionError, Paper, PaperConfig, PaperError, PaperHandle, Project,
+    ProjectConfig, ProjectHandle,
+};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use typstlab_base::bibliography::bibliography_keys;
+use typstlab_base::diagnostics::{DiagnosticSeverity, parse_diagnostics};
+use typstlab_base::driver::{DiagnosticFormat, QueryFormat, TypstCommand, TypstDriver};
+use typstlab_base::pdf::pdf_page_count;
+use typstlab_base::text_stats::html_word_count;
+use typstlab_proto::{Action, AppEvent, Collection, Entity, EventScope, Loadable, Loaded};
+
+/// 見出し・図表・数式をまとめて取り出すセレクタ
+const STATS_SELECTOR: &str = "selector(heading).or(figure).or(math.equation)";
+
+/// 見出し 1 つ分
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OutlineEntry {
+    pub level: usize,
+    pub title: String,
+}
+
+/// 論文 1 本分の統計
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PaperStats {
+    pub paper_id: String,
+    pub title: String,
+    /// ビルド済みの成果物から数えたページ数（未ビルドなら None）
+    pub pages: Option<usize>,
+    /// ページ数を数えた成果物のフォーマット (`pdf` / `png`)
+    pub pages_from: Option<&'static str>,
+    /// HTML 出力の本文の語数（HTML 出力に失敗した場合は None）
+    pub words: Option<usize>,
+    pub outline: Vec<OutlineEntry>,
+    /// 表以外の図（画像・コード等）
+    pub figures: usize,
+    pub tables: usize,
+    /// 別行立ての数式
+    pub equations: usize,
+    /// 文献ファイルのエントリ数（重複キーは 1 つ）
+    pub bibliography_entries: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StatsOutput {
+    pub papers: Vec<PaperStats>,
+}
+
+#[derive(Debug, Clone)]
+pub enum StatsEvent {
+    Collecting { paper_id: String },
+}
+
+#[derive(Debug, PartialEq)]
+pub enum StatsWarning {
+    NoTargetsFound,
+    /// PDF も PNG も見つからずページ数を数えられない
+    NotBuilt {
+        paper_id: String,
+    },
+    WordCountUnavailable {
+        paper_id: String,
+        reason: String,
+    },
+}
+
+#[derive(Error, Debug)]
+pub enum StatsError {
+    #[error("Discovery failure: {0:?}")]
+    Discovery(Vec<DiscoveryError>),
+    #[error("Discovery failure: {0}")]
+    GeneralDiscovery(#[from] CollectionError),
+    #[error("Failed to load paper '{paper_id}': {source}")]
+    PaperLoad {
+        paper_id: String,
+        #[source]
+        source: PaperError,
+    },
+    #[error("Failed to run typst for '{paper_id}': {message}")]
+    Driver { paper_id: String, message: String },
+    #[error("typst query failed for '{paper_id}': {message}")]
+    Query { paper_id: String, message: String },
+    #[error("Failed to prepare a scratch directory: {0}")]
+    Scratch(#[source] std::io::Error),
+    #[error("Failed to read {path}: {source}")]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// 論文ごとのページ数・語数・見出し構成・図表数・文献数を集めるアクション
+pub struct StatsAction {
+    pub loaded_project: Loaded<Project, ProjectConfig>,
+    pub typst_driver: TypstDriver,
+    /// None ならすべての論文
+    pub inputs: Option<Vec<String>>,
+}
+
+impl StatsAction {
+    pub fn new(
+        loaded_project: Loaded<Project, ProjectConfig>,
+        typst_driver: TypstDriver,
+        inputs: Option<Vec<String>>,
+    ) -> Self {
+        Self {
+            loaded_project,
+            typst_driver,
+            inputs,
+        }
+    }
+
+    fn paper_stats(
+        &self,
+        loaded_paper: &Loaded<Paper, PaperConfig>,
+        artifact_scope: &BuildArtifactScope,
+        scratch: &Path,
+        warning: &mut dyn FnMut(StatsWarning),
+    ) -> Result<PaperStats, StatsError> {
+        let paper_id = loaded_paper.paper_id().to_string();
+        let options = compile_options(
+            &self.loaded_project,
+            loaded_paper,
+            None,
+            &BuildOverrides::default(),
+        );
+        let mut stats = PaperStats {
+            paper_id: paper_id.clone(),
+            title: loaded_paper.config.paper.title.clone(),
+            ..PaperStats::default()
+        };
+
+        let result = self
+            .typst_driver
+            .execute(TypstCommand::Query {
+                source: loaded_paper.main_typ_path(),
+                selector: STATS_SELECTOR.to_string(),
+                field: None,
+                one: false,
+                format: QueryFormat::Json,
+                options: Box::new(options.clone()),
+            })
+            .map_err(|error| StatsError::Driver {
+                paper_id: paper_id.clone(),
+                message: error.to_string(),
+            })?;
+        if result.exit_code != 0 {
+            let message = parse_diagnostics(&result.stderr)
+                .into_iter()
+                .find(|diagnostic| diagnostic.severity == DiagnosticSeverity::Error)
+                .map(|diagnostic| diagnostic.message)
+                .unwrap_or_else(|| result.stderr.trim().to_string());
+            return Err(StatsError::Query { paper_id, message });
+        }
+        let elements: Vec<serde_json::Value> =
+            serde_json::from_str(&result.stdout).map_err(|error| StatsError::Query {
+                paper_id: paper_id.clone(),
+                message: format!("unexpected output: {}", error),
+            })?;
+        for element in &elements {
+            match element["func"].as_str() {
+                Some("heading") => stats.outline.push(OutlineEntry {
+                    level: element["level"]
+                        .as_u64()
+                        .or_else(|| element["depth"].as_u64())
+                        .unwrap_or(1) as usize,
+                    title: plain_text(&element["body"]).trim().to_string(),
+                }),
+                Some("figure") if element["kind"].as_str() == Some("table") => stats.tables += 1,
+                Some("figure") => stats.figures += 1,
+                Some("equation") if element["block"].as_bool() == Some(true) => {
+                    stats.equations += 1
+                }
+                _ => {}
+            }
+        }
+
+        // 語数は HTML 出力の本文から数える。出力は作業場所へ捨てる
+        let diagnostic_format = self
+            .typst_driver
+            .get_version()
+            .ok()
+            .as_ref()
+            .and_then(DiagnosticFormat::preferred_for);
+        let dir = scratch.join(&paper_id);
+        let html = std::fs::create_dir_all(&dir)
+            .map_err(|e| e.to_string())
+            .and_then(|_| {
+                let command = throwaway_command(
+                    loaded_paper,
+                    artifact_scope,
+                    "html",
+                    diagnostic_format,
+                    &options,
+                    &dir,
+                );
+                self.typst_driver
+                    .execute(command)
+                    .map_err(|e| e.to_string())
+            })
+            .and_then(|res| {
+                if res.exit_code == 0 {
+                    let output = dir.join(format!("{}.html", loaded_paper.output_base_name()));
+                    std::fs::read_to_string(output).map_err(|e| e.to_string())
+                } else {
+                    Err(res.stderr.trim().to_string())
+                }
+            });
+        match html {
+            Ok(html) => stats.words = Some(html_word_count(&html)),
+            Err(reason) => warning(StatsWarning::WordCountUnavailable {
+                paper_id: paper_id.clone(),
+                reason,
+            }),
+        }
+
+        // ページ数はビルド済みの PDF、無ければ PNG の枚数から
+        let paper_scope = artifact_scope.paper_scope(&paper_id);
+        let pdf = paper_scope
+            .format_artifact("pdf")
+            .path()
+            .join(format!("{}.pdf", loaded_paper.output_base_name()));
+        // png ディレクトリは接尾辞付きのプロファイルと共有されるので、既定の出力のページだけを数える
+        let naming = OutputNaming::new(loaded_paper.output_base_name(), None);
+        let png_count = std::fs::read_dir(paper_scope.format_artifact("png").path())
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter(|entry| {
+                        naming.is_page(&entry.file_name().to_string_lossy())
+                            && entry.path().extension().is_some_and(|ext| ext == "png")
+                    })
+                    .count()
+            })
+            .unwrap_or(0);
+        if let Some(pages) = std::fs::read(&pdf)
+            .ok()
+            .and_then(|bytes| pdf_page_count(&bytes))
+        {
+            stats.pages = Some(pages);
+            stats.pages_from = Some("pdf");
+        } else if png_count > 0 {
+            stats.pages = Some(png_count);
+            stats.pages_from = Some("png");
+        } else {
+            warning(StatsWarning::NotBuilt {
+                paper_id: paper_id.clone(),
+            });
+        }
+
+        let mut keys = BTreeSet::new();
+        let bibliography = bibliography_files(&self.loaded_project, loaded_paper)
+            .map_err(|(path, source)| StatsError::Read { path, source })?;
+        for path in bibliography {
+            let content = std::fs::read_to_string(&path).map_err(|source| StatsError::Read {
+                path: path.clone(),
+                source,
+            })?;
+            keys.extend(bibliography_keys(&path, &content));
+        }
+        stats.bibliography_entries = keys.len();
+
+        Ok(stats)
+    }
+}
+
+impl Action for StatsAction {
+    type Output = StatsOutput;
+    type Event = StatsEvent;
+    type Warning = StatsWarning;
+    type Error = StatsError;
+
+    fn run(
+        self,
+        monitor: &mut dyn FnMut(AppEvent<Self::Event>),
+        warning: &mut dyn FnMut(Self::Warning),
+    ) -> Result<Self::Output, Vec<Self::Error>> {
+        let targets = match &self.inputs {
+            Some(inputs) => {
+                DiscoveryAction::new(self.loaded_project.papers_scope(), inputs.clone())
+                    .run(&mut |_| {}, &mut |_| {})
+                    .map_err(|errors| vec![StatsError::Discovery(errors)])?
+            }
+            None => self
+                .loaded_project
+                .papers_scope()
+                .list()
+                .map_err(|error| vec![StatsError::GeneralDiscovery(error)])?,
+        };
+
+        let mut output = StatsOutput::default();
+        if targets.is_empty() {
+            warning(StatsWarning::NoTargetsFound);
+            return Ok(output);
+        }
+
+        let tmp_dir = self.loaded_project.tmp_dir();
+        let scratch = std::fs::create_dir_all(&tmp_dir)
+            .and_then(|_| {
+                tempfile::Builder::new()
+                    .prefix("stats-")
+                    .tempdir_in(&tmp_dir)
+            })
+            .map_err(|e| vec![StatsError::Scratch(e)])?;
+        let artifact_scope = self.loaded_project.build_artifact_scope();
+
+        let mut errors = Vec::new();
+        for paper in targets {
+            let paper_id = paper.id.clone();
+            monitor(AppEvent::line(
+                EventScope::labeled("stats", paper_id.clone()),
+                StatsEvent::Collecting {
+                    paper_id: paper_id.clone(),
+                },
+            ));
+            let loaded_paper = match paper.load() {
+                Ok(loaded_paper) => loaded_paper,
+                Err(source) => {
+                    errors.push(StatsError::PaperLoad { paper_id, source });
+                    continue;
+                }
+            };
+            match self.paper_stats(&loaded_paper, &artifact_scope, scratch.path(), warning) {
+                Ok(stats) => output.papers.push(stats),
+                Err(error) => errors.push(error),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(output)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// `typst query` が返すコンテンツの JSON から文字列だけを取り出す
+fn plain_text(content: &serde_json::Value) -> String {
+    match content {
+        serde_json::Value::String(text) => text.clone(),
+        serde_json::Value::Array(children) => children.iter().map(plain_text).collect(),
+        serde_json::Value::Object(fields) => match fields.get("func").and_then(|f| f.as_str()) {
+            Some("space") | Some("linebreak") => " ".to_string(),
+            Some("smartquote") => "\"".to_string(),
+            _ => ["text", "children", "body"]
+                .iter()
+                .find_map(|key| fields.get(*key))
+                .map(plain_text)
+                .unwrap_or_default(),
+        },
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_plain_text_flattens_sequences() {
+        let body = serde_json::json!({
+            "func": "sequence",
+            "children": [
+                { "func": "text", "text": "Related" },
+                { "func": "space" },
+                { "func": "strong", "body": { "func": "text", "text": "work" } }
+            ]
+        });
+
+        assert_eq!(plain_text(&body), "Related work");
+    }
+
+    /// `query` には固定の要素を返し、`compile` では HTML を書き出す偽の typst
+    #[cfg(unix)]
+    fn fake_typst(dir: &Path) -> PathBuf {
+        crate::actions::test_support::fake_typst(
+            dir,
+            r#"if [ "$1" = "query" ]; then
+  echo '[{"func":"heading","level":1,"body":{"func":"text","text":"Intro"}},
+         {"func":"heading","level":2,"body":{"func":"text","text":"Scope"}},
+         {"func":"figure","kind":"image"},{"func":"figure","kind":"table"},
+         {"func":"equation","block":true},{"func":"equation","block":false}]'
+  exit 0
+fi
+for arg; do last="$arg"; done
+echo '<html><head><title>T</title></head><body><h1>Intro</h1><p>Three short words</p></body></html>' > "$last"
+"#,
+        )
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stats_reports_outline_counts_and_pages() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("project");
+        let paper = root.join("papers").join("p01");
+        std::fs::create_dir_all(&paper).unwrap();
+        std::fs::write(paper.join("paper.toml"), "[paper]\ntitle = \"Demo\"\n").unwrap();
+        std::fs::write(paper.join("main.typ"), "#bibliography(\"refs.bib\")\n").unwrap();
+        std::fs::write(
+            paper.join("refs.bib"),
+            "@book{knuth, title={TAOCP}}\n@article{lamport, title={LaTeX}}\n",
+        )
+        .unwrap();
+        let png_dir = root.join("dist").join("p01").join("png");
+        std::fs::create_dir_all(&png_dir).unwrap();
+        // 接尾辞付きプロファイルのページは数えない
+        for page in ["1.png", "2.png", "3.png", "main-anon-1.png"] {
+            std::fs::write(png_dir.join(page), "").unwrap();
+        }
+        let project = Loaded {
+            actual: Project::new(root.clone()),
+            config: ProjectConfig::default(),
+        };
+
+        let mut warnings = Vec::new();
+        let output = StatsAction::new(project, TypstDriver::new(fake_typst(temp.path())), None)
+            .run(&mut |_| {}, &mut |w| warnings.push(w))
+            .unwrap();
+
+        assert!(warnings.is_empty());
+        let stats = &output.papers[0];
+        assert_eq!(stats.title, "Demo");
+        assert_eq!(stats.pages, Some(3));
+        assert_eq!(stats.pages_from, Some("png"));
+        assert_eq!(stats.words, Some(4));
+        assert_eq!(
+            stats.outline,
+            vec![
+                OutlineEntry {
+                    level: 1,
+                    title: "Intro".to_string()
+                },
+                OutlineEntry {
+                    level: 2,
+                    title: "Scope".to_string()
+                },
+            ]
+        );
+        assert_eq!((stats.figures, stats.tables, stats.equations), (1, 1, 1));
+        assert_eq!(stats.bibliography_entries, 2);
+    }
+}
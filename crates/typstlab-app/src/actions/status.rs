@@ -202,6 +202,7 @@ mod tests {
                 },
                 toolchain: ProjectToolChain::default(),
                 structure: StructureConfig::default(),
+                ..Default::default()
             },
         }
     }
@@ -0,0 +1,21 @@
+use std::path::{Path, PathBuf};
+
+/// `dir/fake-typst` に偽の typst を作る。
+/// `--version` には `typst 0.14.2` と答え、それ以外の呼び出しでは `body` のシェルスクリプトを実行する。
+pub(crate) fn fake_typst(dir: &Path, body: &str) -> PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    let typst = dir.join("fake-typst");
+    std::fs::write(
+        &typst,
+        format!(
+            "#!/bin/sh\n\
+             if [ \"$1\" = \"--version\" ]; then echo \"typst 0.14.2\"; exit 0; fi\n\
+             {}",
+            body
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(&typst, std::fs::Permissions::from_mode(0o755)).unwrap();
+    typst
+}
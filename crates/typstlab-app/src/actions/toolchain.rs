@@ -0,0 +1,510 @@
+use crate::actions::resolve_typst::StoreError;
+use crate::actions::toolchain_resolve::{
+    ToolchainResolveAction, ToolchainResolveError, ToolchainResolveEvent, ToolchainResolveWarning,
+};
+use crate::models::{Project, ProjectConfig, ProjectHandle, Typst, TypstStore};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use typstlab_base::persistence::Persistence;
+use typstlab_base::version_resolver::normalize_version;
+use typstlab_base::{
+    ProjectToolChain, ResolvedToolChain, VersionResolveError, known_typst_versions,
+    resolve_toolchain, resolve_typst_version,
+};
+use typstlab_proto::{Action, AppEvent, Collection, Loaded};
+
+/// ストアに入っている Typst
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstalledTypst {
+    pub version: String,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+pub struct ToolchainListOutput {
+    /// プロジェクトが使っているバージョン（プロジェクト外なら None）
+    pub current: Option<String>,
+    /// 新しい順
+    pub installed: Vec<InstalledTypst>,
+    /// 互換表に載っているバージョン（新しい順）
+    pub available: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ToolchainInstallOutput {
+    pub typst: Typst,
+    /// true ならストアに既にあった
+    pub already_installed: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct ToolchainUseOutput {
+    pub previous: String,
+    pub resolved: ResolvedToolChain,
+    /// false なら次回の起動時にダウンロードされる
+    pub installed: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ToolchainWarning {
+    /// 削除したバージョンをプロジェクトが使っている
+    RemovedVersionInUse { version: String },
+}
+
+#[derive(Error, Debug)]
+pub enum ToolchainError {
+    #[error("{0}")]
+    VersionResolution(#[from] VersionResolveError),
+    #[error("store failed: {0}")]
+    Store(#[from] StoreError),
+    #[error("{0}")]
+    Install(#[from] ToolchainResolveError),
+    #[error("typst {0} is not installed")]
+    NotInstalled(String),
+    #[error("failed to remove '{path}': {source}")]
+    Remove {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to read '{path}': {source}")]
+    ReadConfig {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to parse '{path}': {message}")]
+    ParseConfig { path: PathBuf, message: String },
+    #[error("failed to write '{path}': {message}")]
+    WriteConfig { path: PathBuf, message: String },
+}
+
+/// ストアの中身と互換表のバージョンを並べるアクション
+pub struct ToolchainListAction {
+    pub store: TypstStore,
+    pub current: Option<String>,
+}
+
+impl ToolchainListAction {
+    pub fn new(store: TypstStore) -> Self {
+        Self {
+            store,
+            current: None,
+        }
+    }
+
+    pub fn with_current(mut self, current: Option<String>) -> Self {
+        self.current = current;
+        self
+    }
+}
+
+impl Action for ToolchainListAction {
+    type Output = ToolchainListOutput;
+    type Event = ();
+    type Warning = ();
+    type Error = ToolchainError;
+
+    fn run(
+        self,
+        _monitor: &mut dyn FnMut(AppEvent<()>),
+        _warning: &mut dyn FnMut(()),
+    ) -> Result<Self::Output, Vec<Self::Error>> {
+        let available = known_typst_versions().map_err(|error| vec![error.into()])?;
+        let mut installed = self
+            .store
+            .list()
+            .map_err(|error| vec![error.into()])?
+            .into_iter()
+            .map(|typst| InstalledTypst {
+                path: self.store.typst_path(&typst.version),
+                version: typst.version,
+            })
+            .collect::<Vec<_>>();
+        // 互換表に無いものは後ろへ
+        installed.sort_by_cached_key(|typst| {
+            (
+                available
+                    .iter()
+                    .position(|version| *version == typst.version)
+                    .unwrap_or(usize::MAX),
+                typst.version.clone(),
+            )
+        });
+
+        Ok(ToolchainListOutput {
+            current: self.current.as_deref().map(normalize_version),
+            installed,
+            available,
+        })
+    }
+}
+
+/// 指定したバージョンの Typst をストアへ入れるアクション
+pub struct ToolchainInstallAction {
+    pub store: TypstStore,
+    pub version: String,
+    /// true ならダウンロードせず、ストアに無ければ失敗する
+    pub offline: bool,
+    /// ダウンロード URL のテンプレート。None なら GitHub Releases
+    pub mirror: Option<String>,
+}
+
+impl ToolchainInstallAction {
+    pub fn new(store: TypstStore, version: String) -> Self {
+        Self {
+            store,
+            version,
+            offline: false,
+            mirror: None,
+        }
+    }
+
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    pub fn with_mirror(mut self, mirror: Option<String>) -> Self {
+        self.mirror = mirror;
+        self
+    }
+}
+
+impl Action for ToolchainInstallAction {
+    type Output = ToolchainInstallOutput;
+    type Event = ToolchainResolveEvent;
+    type Warning = ToolchainResolveWarning;
+    type Error = ToolchainError;
+
+    fn run(
+        self,
+        monitor: &mut dyn FnMut(AppEvent<ToolchainResolveEvent>),
+        warning: &mut dyn FnMut(ToolchainResolveWarning),
+    ) -> Result<Self::Output, Vec<Self::Error>> {
+        let version = resolve_typst_version(&self.version).map_err(|error| vec![error.into()])?;
+        if let Some(typst) = self
+            .store
+            .resolve(&version)
+            .map_err(|error| vec![error.into()])?
+        {
+            return Ok(ToolchainInstallOutput {
+                typst,
+                already_installed: true,
+            });
+        }
+
+        if self.offline {
+            return Err(vec![
+                ToolchainResolveError::TypstMissingOffline { version }.into(),
+            ]);
+        }
+        let typst = ToolchainResolveAction::resolve_typst(
+            &self.store,
+            version,
+            self.mirror.as_deref(),
+            monitor,
+            warning,
+        )
+        .map_err(|error| vec![error.into()])?;
+        Ok(ToolchainInstallOutput {
+            typst,
+            already_installed: false,
+        })
+    }
+}
+
+/// ストアから Typst を削除するアクション
+pub struct ToolchainRemoveAction {
+    pub store: TypstStore,
+    pub version: String,
+    pub current: Option<String>,
+}
+
+impl ToolchainRemoveAction {
+    pub fn new(store: TypstStore, version: String) -> Self {
+        Self {
+            store,
+            version,
+            current: None,
+        }
+    }
+
+    pub fn with_current(mut self, current: Option<String>) -> Self {
+        self.current = current;
+        self
+    }
+}
+
+impl Action for ToolchainRemoveAction {
+    type Output = PathBuf;
+    type Event = ();
+    type Warning = ToolchainWarning;
+    type Error = ToolchainError;
+
+    fn run(
+        self,
+        _monitor: &mut dyn FnMut(AppEvent<()>),
+        warning: &mut dyn FnMut(ToolchainWarning),
+    ) -> Result<Self::Output, Vec<Self::Error>> {
+        // 互換表から外れた古いバージョンも消せるように、表は引かない
+        let version = normalize_version(&self.version);
+        let path = self.store.typst_path(&version);
+        // `.tmp` やストア外を指す指定は受け付けない
+        if version.starts_with('.') || version.contains(['/', '\\']) || !path.is_dir() {
+            return Err(vec![ToolchainError::NotInstalled(version)]);
+        }
+
+        // インストール中のものを消さないよう、終わるまで待つ
+        let _lock = self
+            .store
+            .lock(&version)
+            .map_err(|error| vec![error.into()])?;
+        std::fs::remove_dir_all(&path).map_err(|source| {
+            vec![ToolchainError::Remove {
+                path: path.clone(),
+                source,
+            }]
+        })?;
+
+        if self.current.as_deref().map(normalize_version).as_ref() == Some(&version) {
+            warning(ToolchainWarning::RemovedVersionInUse { version });
+        }
+        Ok(path)
+    }
+}
+
+/// `[toolchain] typst` を書き換えるアクション
+pub struct ToolchainUseAction {
+    pub loaded_project: Loaded<Project, ProjectConfig>,
+    pub store: TypstStore,
+    pub version: String,
+}
+
+impl ToolchainUseAction {
+    pub fn new(
+        loaded_project: Loaded<Project, ProjectConfig>,
+        store: TypstStore,
+        version: String,
+    ) -> Self {
+        Self {
+            loaded_project,
+            store,
+            version,
+        }
+    }
+}
+
+impl Action for ToolchainUseAction {
+    type Output = ToolchainUseOutput;
+    type Event = ();
+    type Warning = ();
+    type Error = ToolchainError;
+
+    fn run(
+        self,
+        _monitor: &mut dyn FnMut(AppEvent<()>),
+        _warning: &mut dyn FnMut(()),
+    ) -> Result<Self::Output, Vec<Self::Error>> {
+        self.run_inner().map_err(|error| vec![error])
+    }
+}
+
+impl ToolchainUseAction {
+    fn run_inner(self) -> Result<ToolchainUseOutput, ToolchainError> {
+        let previous = self.loaded_project.toolchain().clone();
+        // docs / typstyle の指定はそのままに、新しい Typst と両立するか確かめる
+        let resolved = resolve_toolchain(&ProjectToolChain {
+            typst: self.version,
+            ..previous.clone()
+        })?;
+
+        write_typst_version(&self.loaded_project.actual.config_path(), &resolved.typst)?;
+        let installed = self.store.resolve(&resolved.typst)?.is_some();
+
+        Ok(ToolchainUseOutput {
+            previous: previous.typst,
+            resolved,
+            installed,
+        })
+    }
+}
+
+/// コメントや並びを保ったまま `[toolchain] typst` だけを書き換える
+fn write_typst_version(config_path: &Path, version: &str) -> Result<(), ToolchainError> {
+    let content =
+        std::fs::read_to_string(config_path).map_err(|source| ToolchainError::ReadConfig {
+            path: config_path.to_path_buf(),
+            source,
+        })?;
+    let mut document =
+        content
+            .parse::<toml_edit::DocumentMut>()
+            .map_err(|error| ToolchainError::ParseConfig {
+                path: config_path.to_path_buf(),
+                message: error.to_string(),
+            })?;
+
+    if document
+        .get("toolchain")
+        .and_then(toml_edit::Item::as_table_like)
+        .is_none()
+    {
+        document["toolchain"] = toml_edit::table();
+    }
+    // 値に付いた行末コメント（`# pinned` など）を保ったまま書き換える
+    match document["toolchain"]["typst"].as_value_mut() {
+        Some(value) => {
+            let decor = value.decor().clone();
+            *value = version.into();
+            *value.decor_mut() = decor;
+        }
+        None => document["toolchain"]["typst"] = toml_edit::value(version),
+    }
+
+    Persistence::write_file(config_path, document.to_string().as_bytes()).map_err(|error| {
+        ToolchainError::WriteConfig {
+            path: config_path.to_path_buf(),
+            message: error.to_string(),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use typstlab_base::ToolChoice;
+    use typstlab_proto::{Loadable, TYPST_BINARY_NAME};
+
+    fn install_fake(store: &TypstStore, version: &str) {
+        std::fs::create_dir_all(store.typst_path(version)).unwrap();
+        std::fs::write(store.binary_path(version), b"fake").unwrap();
+    }
+
+    type ActionRun<A> = (
+        Result<<A as Action>::Output, Vec<<A as Action>::Error>>,
+        Vec<<A as Action>::Warning>,
+    );
+
+    fn run_action<A: Action>(action: A) -> ActionRun<A> {
+        let mut warnings = Vec::new();
+        let result = action.run(&mut |_| {}, &mut |warning| warnings.push(warning));
+        (result, warnings)
+    }
+
+    #[test]
+    fn test_list_install_and_remove_work_on_the_store() {
+        let temp = TempDir::new().unwrap();
+        let store = TypstStore::new(temp.path().to_path_buf());
+        install_fake(&store, "0.12.0");
+        install_fake(&store, "0.14.2");
+        std::fs::create_dir_all(store.staging_root()).unwrap();
+
+        let (listed, _) = run_action(
+            ToolchainListAction::new(store.clone()).with_current(Some("v0.14.2".to_string())),
+        );
+        let listed = listed.unwrap();
+        assert_eq!(listed.current.as_deref(), Some("0.14.2"));
+        assert_eq!(
+            listed
+                .installed
+                .iter()
+                .map(|typst| typst.version.as_str())
+                .collect::<Vec<_>>(),
+            vec!["0.14.2", "0.12.0"]
+        );
+        assert_eq!(listed.available.first().map(String::as_str), Some("0.14.2"));
+
+        let (installed, _) = run_action(ToolchainInstallAction::new(
+            store.clone(),
+            "v0.12.0".to_string(),
+        ));
+        let installed = installed.unwrap();
+        assert!(installed.already_installed);
+        assert!(installed.typst.binary_path.ends_with(TYPST_BINARY_NAME));
+        let (unknown, _) = run_action(ToolchainInstallAction::new(
+            store.clone(),
+            "9.9.9".to_string(),
+        ));
+        assert!(matches!(
+            unknown.unwrap_err().as_slice(),
+            [ToolchainError::VersionResolution(
+                VersionResolveError::TypstVersionNotFound { .. }
+            )]
+        ));
+
+        let (removed, warnings) = run_action(
+            ToolchainRemoveAction::new(store.clone(), "0.14.2".to_string())
+                .with_current(Some("0.14.2".to_string())),
+        );
+        assert_eq!(removed.unwrap(), store.typst_path("0.14.2"));
+        assert!(!store.typst_path("0.14.2").exists());
+        assert_eq!(
+            warnings,
+            vec![ToolchainWarning::RemovedVersionInUse {
+                version: "0.14.2".to_string()
+            }]
+        );
+        let (missing, _) = run_action(ToolchainRemoveAction::new(store.clone(), ".tmp".into()));
+        assert!(matches!(
+            missing.unwrap_err().as_slice(),
+            [ToolchainError::NotInstalled(_)]
+        ));
+        assert!(store.staging_root().exists());
+    }
+
+    #[test]
+    fn test_use_rewrites_typst_version_and_keeps_comments() {
+        let temp = TempDir::new().unwrap();
+        let store = TypstStore::new(temp.path().join("store"));
+        install_fake(&store, "0.13.1");
+        let project = Project::new(temp.path().join("project"));
+        std::fs::create_dir_all(&project.root).unwrap();
+        std::fs::write(
+            project.config_path(),
+            "# paper project\n[project]\nname = \"demo\"\ninit_date = \"2026-01-01\"\n\n[toolchain]\ntypst = \"0.14.2\" # pinned\ntypst_docs = \"none\"\n",
+        )
+        .unwrap();
+        let loaded = Project::new(project.root.clone()).load().unwrap();
+
+        let (output, _) = run_action(ToolchainUseAction::new(
+            loaded,
+            store.clone(),
+            "v0.13.1".to_string(),
+        ));
+        let output = output.unwrap();
+
+        assert_eq!(output.previous, "0.14.2");
+        assert_eq!(output.resolved.typst, "0.13.1");
+        assert!(output.installed);
+        let content = std::fs::read_to_string(project.config_path()).unwrap();
+        assert!(content.starts_with("# paper project\n"));
+        assert!(content.contains("typst = \"0.13.1\" # pinned\n"));
+        let reloaded = Project::new(project.root.clone()).load().unwrap();
+        assert_eq!(reloaded.toolchain().typst, "0.13.1");
+        assert_eq!(reloaded.toolchain().typst_docs, ToolChoice::None);
+    }
+
+    #[test]
+    fn test_use_rejects_version_incompatible_with_pinned_docs() {
+        let temp = TempDir::new().unwrap();
+        let store = TypstStore::new(temp.path().join("store"));
+        let project = Project::new(temp.path().join("project"));
+        std::fs::create_dir_all(&project.root).unwrap();
+        let original = "[project]\nname = \"demo\"\ninit_date = \"2026-01-01\"\n\n[toolchain]\ntypst = \"0.14.2\"\ntypst_docs = \"0.14.2\"\n";
+        std::fs::write(project.config_path(), original).unwrap();
+        let loaded = Project::new(project.root.clone()).load().unwrap();
+
+        let (output, _) = run_action(ToolchainUseAction::new(loaded, store, "0.12.0".into()));
+
+        assert!(matches!(
+            output.unwrap_err().as_slice(),
+            [ToolchainError::VersionResolution(
+                VersionResolveError::IncompatibleToolVersion { .. }
+            )]
+        ));
+        assert_eq!(
+            std::fs::read_to_string(project.config_path()).unwrap(),
+            original
+        );
+    }
+}
@@ -1,5 +1,7 @@
 use crate::actions::resolve_docs::{ResolveDocsAction, ResolveDocsError};
-use crate::actions::resolve_typst::{ResolveEvent, ResolveTypstAction, ResolveTypstError};
+use crate::actions::resolve_typst::{
+    ResolveEvent, ResolveTypstAction, ResolveTypstError, ResolveWarning, StoreError,
+};
 use crate::models::{Docs, DocsStore, ProjectToolChain, Typst, TypstStore};
 use std::path::{Path, PathBuf};
 use thiserror::Error;
@@ -13,13 +15,15 @@ use typstlab_base::link_resolver::{
 };
 use typstlab_base::platform::Platform;
 use typstlab_base::resolve_toolchain;
-use typstlab_proto::{Action, AppEvent};
+use typstlab_proto::{Action, AppEvent, Collection};
 
 pub struct ToolchainResolveInput {
     pub project_root: PathBuf,
     pub toolchain: ProjectToolChain,
     pub typst_store: TypstStore,
     pub docs_store: DocsStore,
+    /// true ならストアにあるものだけを使い、ダウンロードしない
+    pub offline: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -43,6 +47,22 @@ pub enum ToolchainResolveEvent {
     Completed,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToolchainResolveWarning {
+    /// オフラインのため、ストアに無い docs を使わずに進めた
+    DocsSkippedOffline { version: String },
+    /// チェックサム表に載っていない配布物を検証せずにインストールした
+    UnverifiedDownload { url: String },
+}
+
+impl From<ResolveWarning> for ToolchainResolveWarning {
+    fn from(warning: ResolveWarning) -> Self {
+        match warning {
+            ResolveWarning::UnverifiedDownload { url } => Self::UnverifiedDownload { url },
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum ToolchainResolveError {
     #[error("Toolchain version resolution failed: {0}")]
@@ -53,10 +73,18 @@ pub enum ToolchainResolveError {
     TypstInstallInit(reqwest::Error),
     #[error("Typst resolution failed: {0:?}")]
     TypstResolution(Vec<ResolveTypstError<TypstInstallError>>),
+    #[error("Docs link resolution failed: {0}")]
+    DocsLinkResolution(LinkResolveError),
     #[error("Failed to initialize HTTP provider: {0}")]
     DocsInstallInit(reqwest::Error),
     #[error("Docs resolution failed: {0:?}")]
     DocsResolution(Vec<ResolveDocsError<DocsInstallError>>),
+    #[error(
+        "Typst {version} is not installed and offline mode is on (run `typstlab toolchain install {version}` while online)"
+    )]
+    TypstMissingOffline { version: String },
+    #[error("Failed to inspect the toolchain store: {0}")]
+    Store(#[from] StoreError),
 }
 
 pub struct ToolchainResolveAction {
@@ -66,15 +94,17 @@ pub struct ToolchainResolveAction {
 impl Action for ToolchainResolveAction {
     type Output = ToolChain;
     type Event = ToolchainResolveEvent;
-    type Warning = ();
+    type Warning = ToolchainResolveWarning;
     type Error = ToolchainResolveError;
 
     fn run(
         self,
         monitor: &mut dyn FnMut(AppEvent<Self::Event>),
-        _warning: &mut dyn FnMut(Self::Warning),
+        warning: &mut dyn FnMut(Self::Warning),
     ) -> Result<Self::Output, Vec<Self::Error>> {
-        let toolchain = self.resolve(monitor).map_err(|error| vec![error])?;
+        let toolchain = self
+            .resolve(monitor, warning)
+            .map_err(|error| vec![error])?;
         monitor(AppEvent::line(
             typstlab_proto::EventScope::labeled("toolchain_resolve", "done"),
             ToolchainResolveEvent::Completed,
@@ -87,22 +117,48 @@ impl ToolchainResolveAction {
     fn resolve(
         self,
         monitor: &mut dyn FnMut(AppEvent<ToolchainResolveEvent>),
+        warning: &mut dyn FnMut(ToolchainResolveWarning),
     ) -> Result<ToolChain, ToolchainResolveError> {
         let ToolchainResolveInput {
             project_root,
             toolchain,
             typst_store,
             docs_store,
+            offline,
         } = self.input;
         let resolved_toolchain = resolve_toolchain(&toolchain)?;
 
-        let typst = Self::resolve_typst(&typst_store, resolved_toolchain.typst, monitor)?;
-        let typst_docs_version = resolved_toolchain.typst_docs.clone();
+        // オフラインではストアにあるものだけを使う。あればダウンロードは起きない
+        if offline && typst_store.resolve(&resolved_toolchain.typst)?.is_none() {
+            return Err(ToolchainResolveError::TypstMissingOffline {
+                version: resolved_toolchain.typst,
+            });
+        }
+        let typst = Self::resolve_typst(
+            &typst_store,
+            resolved_toolchain.typst,
+            toolchain.mirrors.typst.as_deref(),
+            monitor,
+            warning,
+        )?;
+
+        let mut typst_docs_version = resolved_toolchain.typst_docs.clone();
+        if offline
+            && let Some(version) = &typst_docs_version
+            && docs_store.resolve(version)?.is_none()
+        {
+            warning(ToolchainResolveWarning::DocsSkippedOffline {
+                version: version.clone(),
+            });
+            typst_docs_version = None;
+        }
         let typst_docs = Self::resolve_typst_docs(
             &project_root,
             &docs_store,
             typst_docs_version.clone(),
+            toolchain.mirrors.docs.as_deref(),
             monitor,
+            warning,
         )?;
         let typst_docs_cache = typst_docs_version.map(|_| docs_store.root.clone());
 
@@ -114,20 +170,24 @@ impl ToolchainResolveAction {
         })
     }
 
-    fn resolve_typst(
+    pub(crate) fn resolve_typst(
         typst_store: &TypstStore,
         version: String,
+        mirror: Option<&str>,
         monitor: &mut dyn FnMut(AppEvent<ToolchainResolveEvent>),
+        warning: &mut dyn FnMut(ToolchainResolveWarning),
     ) -> Result<Typst, ToolchainResolveError> {
         let platform = Platform::current();
         let typst_link = resolve_typst_link(TypstLinkRequest {
             platform,
             version: Version::new(&version),
+            mirror,
         })?;
 
         let typst_installer = TypstInstaller::new(
             HttpProvider::try_new().map_err(ToolchainResolveError::TypstInstallInit)?,
-        );
+        )
+        .with_sha256(typst_link.sha256.clone());
         let typst_resolver = ResolveTypstAction {
             store: typst_store.clone(),
             version: version.clone(),
@@ -145,7 +205,7 @@ impl ToolchainResolveAction {
                         }),
                     );
                 },
-                &mut |_| {},
+                &mut |w| warning(w.into()),
             )
             .map_err(ToolchainResolveError::TypstResolution)
     }
@@ -154,7 +214,9 @@ impl ToolchainResolveAction {
         project_root: &Path,
         docs_store: &DocsStore,
         version: Option<String>,
+        mirror: Option<&str>,
         monitor: &mut dyn FnMut(AppEvent<ToolchainResolveEvent>),
+        warning: &mut dyn FnMut(ToolchainResolveWarning),
     ) -> Result<Option<Docs>, ToolchainResolveError> {
         let Some(version) = version else {
             return Ok(None);
@@ -162,10 +224,13 @@ impl ToolchainResolveAction {
 
         let docs_link = resolve_docs_link(DocsLinkRequest {
             version: Version::new(&version),
-        });
+            mirror,
+        })
+        .map_err(ToolchainResolveError::DocsLinkResolution)?;
         let docs_installer = DocsInstaller::new(
             HttpProvider::try_new().map_err(ToolchainResolveError::DocsInstallInit)?,
-        );
+        )
+        .with_sha256(docs_link.sha256.clone());
         let docs_resolver = ResolveDocsAction {
             project_root: project_root.to_path_buf(),
             store: docs_store.clone(),
@@ -184,9 +249,70 @@ impl ToolchainResolveAction {
                         }),
                     );
                 },
-                &mut |_| {},
+                &mut |w| warning(w.into()),
             )
             .map_err(ToolchainResolveError::DocsResolution)
             .map(Some)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use typstlab_base::ToolChoice;
+
+    fn input(temp: &TempDir, offline: bool) -> ToolchainResolveInput {
+        ToolchainResolveInput {
+            project_root: temp.path().join("project"),
+            toolchain: ProjectToolChain {
+                typst: "0.14.2".to_string(),
+                typst_docs: ToolChoice::Auto,
+                typstyle: ToolChoice::None,
+                mirrors: Default::default(),
+            },
+            typst_store: TypstStore::new(temp.path().join("typst")),
+            docs_store: DocsStore::new(temp.path().join("docs")),
+            offline,
+        }
+    }
+
+    #[test]
+    fn test_offline_fails_with_missing_typst_version() {
+        let temp = TempDir::new().unwrap();
+        let action = ToolchainResolveAction {
+            input: input(&temp, true),
+        };
+
+        let errors = action.run(&mut |_| {}, &mut |_| {}).unwrap_err();
+
+        assert!(matches!(
+            errors.as_slice(),
+            [ToolchainResolveError::TypstMissingOffline { version }] if version == "0.14.2"
+        ));
+    }
+
+    #[test]
+    fn test_offline_uses_stored_typst_and_skips_missing_docs() {
+        let temp = TempDir::new().unwrap();
+        let input = input(&temp, true);
+        std::fs::create_dir_all(input.typst_store.typst_path("0.14.2")).unwrap();
+        std::fs::write(input.typst_store.binary_path("0.14.2"), b"typst").unwrap();
+        let action = ToolchainResolveAction { input };
+        let mut warnings = Vec::new();
+
+        let toolchain = action
+            .run(&mut |_| {}, &mut |warning| warnings.push(warning))
+            .unwrap();
+
+        assert_eq!(toolchain.typst.version, "0.14.2");
+        assert!(toolchain.typst_docs.is_none());
+        assert!(toolchain.typst_docs_cache.is_none());
+        assert_eq!(
+            warnings,
+            vec![ToolchainResolveWarning::DocsSkippedOffline {
+                version: "0.14.2".to_string()
+            }]
+        );
+    }
+}
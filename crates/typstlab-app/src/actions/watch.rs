@@ -0,0 +1,578 @@
+use crate::actions::build::{
+    BuildAction, BuildError, BuildEvent, BuildFormat, BuildOverrides, BuildWarning, default_jobs,
+};
+use crate::actions::discovery::{DiscoveryAction, DiscoveryError};
+use crate::models::{Project, ProjectConfig, ProjectError, ProjectHandle};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime};
+use thiserror::Error;
+use typstlab_base::driver::TypstDriver;
+use typstlab_proto::{Action, AppEvent, Collection, Entity, EventScope, Loadable, Loaded};
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(300);
+/// ファイルシステムの時刻の粒度を吸収するため、フックの実行期間を前後に広げる幅
+const HOOK_WINDOW_SLACK: Duration = Duration::from_secs(1);
+
+#[derive(Error, Debug)]
+pub enum WatchError {
+    #[error("Discovery failure: {0:?}")]
+    Discovery(Vec<DiscoveryError>),
+    #[error("Unknown build profile '{0}' (define it under [profiles] in typstlab.toml)")]
+    UnknownProfile(String),
+}
+
+/// 監視中に発生した、ループを止めない問題
+#[derive(Debug)]
+pub enum WatchWarning {
+    Build(BuildWarning),
+    BuildFailed(Vec<BuildError>),
+    ProjectReloadFailed(ProjectError),
+    ScanFailed {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub enum WatchEvent {
+    Watching { paths: Vec<PathBuf> },
+    ChangeDetected { paper_ids: Vec<String> },
+    Build(BuildEvent),
+}
+
+/// ソースの変更を監視し、影響を受けた論文だけを再ビルドし続けるアクション
+pub struct WatchAction {
+    pub loaded_project: Loaded<Project, ProjectConfig>,
+    pub typst_driver: TypstDriver,
+    pub inputs: Option<Vec<String>>,
+    /// None なら論文ごとの `[build].formats` に従う
+    pub format: Option<BuildFormat>,
+    pub overrides: BuildOverrides,
+    pub profile: Option<String>,
+    pub jobs: usize,
+    /// 再ビルド時にビルドキャッシュを無視する
+    pub force: bool,
+    /// 再ビルドのたびにタイミングトレースを出力させる
+    pub timings: bool,
+    pub poll_interval: Duration,
+    pub debounce: Duration,
+    /// true になった時点で監視ループを抜ける
+    pub stop: Arc<AtomicBool>,
+}
+
+impl WatchAction {
+    pub fn new(
+        loaded_project: Loaded<Project, ProjectConfig>,
+        typst_driver: TypstDriver,
+        inputs: Option<Vec<String>>,
+        format: Option<BuildFormat>,
+    ) -> Self {
+        Self {
+            loaded_project,
+            typst_driver,
+            inputs,
+            format,
+            overrides: BuildOverrides::default(),
+            profile: None,
+            jobs: default_jobs(),
+            force: false,
+            timings: false,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            debounce: DEFAULT_DEBOUNCE,
+            stop: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// 再ビルド時の並列ワーカー数を指定する
+    pub fn with_jobs(mut self, jobs: usize) -> Self {
+        self.jobs = jobs.max(1);
+        self
+    }
+
+    pub fn with_force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    pub fn with_timings(mut self, timings: bool) -> Self {
+        self.timings = timings;
+        self
+    }
+
+    pub fn with_overrides(mut self, overrides: BuildOverrides) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
+    pub fn with_profile(mut self, profile: Option<String>) -> Self {
+        self.profile = profile;
+        self
+    }
+}
+
+impl Action for WatchAction {
+    type Output = ();
+    type Event = WatchEvent;
+    type Warning = WatchWarning;
+    type Error = WatchError;
+
+    fn run(
+        mut self,
+        monitor: &mut dyn FnMut(AppEvent<WatchEvent>),
+        warning: &mut dyn FnMut(WatchWarning),
+    ) -> Result<Self::Output, Vec<Self::Error>> {
+        let scope = EventScope::new("watch");
+
+        // 1. 監視対象の論文を確定 (入力が無ければ papers 全体を監視する)
+        if let Some(name) = &self.profile
+            && self.loaded_project.profile(name).is_none()
+        {
+            return Err(vec![WatchError::UnknownProfile(name.clone())]);
+        }
+        let targets = self.watched_paper_ids()?;
+
+        // 2. 初回ビルド（監視のスナップショットはこの後に取る）
+        self.rebuild(self.inputs.clone(), monitor, warning);
+
+        let watch_roots = self.watch_roots(&targets);
+        monitor(AppEvent::line(
+            scope.clone(),
+            WatchEvent::Watching {
+                paths: watch_roots.clone(),
+            },
+        ));
+
+        let mut snapshot = scan(&watch_roots, warning);
+
+        // 3. 変更の監視ループ
+        while !self.stop.load(Ordering::SeqCst) {
+            std::thread::sleep(self.poll_interval);
+
+            let mut current = scan(&watch_roots, warning);
+            if current == snapshot {
+                continue;
+            }
+
+            // 書き込みが落ち着くまで待つ (debounce)
+            loop {
+                std::thread::sleep(self.debounce);
+                let settled = scan(&watch_roots, warning);
+                if settled == current {
+                    break;
+                }
+                current = settled;
+            }
+
+            let changed = changed_paths(&snapshot, &current);
+            snapshot = current;
+
+            if changed.contains(&self.loaded_project.actual.config_path()) {
+                match Project::new(self.loaded_project.actual.root.clone()).load() {
+                    Ok(reloaded) => self.loaded_project = reloaded,
+                    Err(error) => warning(WatchWarning::ProjectReloadFailed(error)),
+                }
+            }
+
+            let paper_ids = self.affected_paper_ids(&changed, &targets);
+            if paper_ids.is_empty() {
+                continue;
+            }
+
+            monitor(AppEvent::line(
+                scope.clone(),
+                WatchEvent::ChangeDetected {
+                    paper_ids: paper_ids.clone(),
+                },
+            ));
+            // フックが監視対象に書き込んだ変更で再ビルドが繰り返されないようにする。
+            // ビルド中に編集されたソースは次の周回で検出されるよう、フックの実行中に
+            // 更新されたファイルだけを取り込む
+            let hook_windows = self.rebuild(Some(paper_ids), monitor, warning);
+            if !hook_windows.is_empty() {
+                absorb_hook_writes(&mut snapshot, &scan(&watch_roots, warning), &hook_windows);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl WatchAction {
+    /// 監視対象の論文 ID。`None` は papers ディレクトリ全体を意味する。
+    fn watched_paper_ids(&self) -> Result<Option<BTreeSet<String>>, Vec<WatchError>> {
+        let Some(inputs) = &self.inputs else {
+            return Ok(None);
+        };
+
+        let discovery = DiscoveryAction::new(self.loaded_project.papers_scope(), inputs.clone());
+        let papers = discovery
+            .run(&mut |_| {}, &mut |_| {})
+            .map_err(|errors| vec![WatchError::Discovery(errors)])?;
+
+        Ok(Some(papers.into_iter().map(|paper| paper.id).collect()))
+    }
+
+    fn watch_roots(&self, targets: &Option<BTreeSet<String>>) -> Vec<PathBuf> {
+        let papers_root = self.loaded_project.papers_scope().path();
+        let mut roots = match targets {
+            Some(ids) => ids.iter().map(|id| papers_root.join(id)).collect(),
+            None => vec![papers_root],
+        };
+        roots.extend(self.loaded_project.shared_source_dirs());
+        roots.push(self.loaded_project.actual.config_path());
+        roots
+    }
+
+    /// 変更されたパスから再ビルドが必要な論文 ID を求める。
+    /// 共有ディレクトリや設定ファイルの変更は全対象の再ビルドを意味する。
+    fn affected_paper_ids(
+        &self,
+        changed: &BTreeSet<PathBuf>,
+        targets: &Option<BTreeSet<String>>,
+    ) -> Vec<String> {
+        let papers_root = self.loaded_project.papers_scope().path();
+        let shared_roots = self.loaded_project.shared_source_dirs();
+        let config_path = self.loaded_project.actual.config_path();
+
+        let touches_shared = changed.iter().any(|path| {
+            path == &config_path || shared_roots.iter().any(|root| path.starts_with(root))
+        });
+
+        let mut affected = BTreeSet::new();
+        if touches_shared {
+            match targets {
+                Some(ids) => affected.extend(ids.iter().cloned()),
+                None => affected.extend(self.current_paper_ids()),
+            }
+        }
+
+        for path in changed {
+            let Some(id) = paper_id_of(&papers_root, path) else {
+                continue;
+            };
+            let is_target = targets.as_ref().is_none_or(|ids| ids.contains(&id));
+            if is_target && papers_root.join(&id).is_dir() {
+                affected.insert(id);
+            }
+        }
+
+        affected.into_iter().collect()
+    }
+
+    fn current_paper_ids(&self) -> Vec<String> {
+        self.loaded_project
+            .papers_scope()
+            .list()
+            .map(|papers| papers.into_iter().map(|paper| paper.id).collect())
+            .unwrap_or_default()
+    }
+
+    /// 再ビルドし、フックが実行されていた期間を返す。
+    fn rebuild(
+        &self,
+        inputs: Option<Vec<String>>,
+        monitor: &mut dyn FnMut(AppEvent<WatchEvent>),
+        warning: &mut dyn FnMut(WatchWarning),
+    ) -> Vec<HookWindow> {
+        let project = Loaded {
+            actual: Project::new(self.loaded_project.actual.root.clone()),
+            config: self.loaded_project.config.clone(),
+        };
+        let action = BuildAction::new(project, self.typst_driver.clone(), inputs, self.format)
+            .with_overrides(self.overrides.clone())
+            .with_profile(self.profile.clone())
+            .with_jobs(self.jobs)
+            .with_force(self.force)
+            .with_timings(self.timings);
+
+        // フックは 1 つずつ実行され、次のイベントはフックが終わってから届く
+        let mut windows = Vec::new();
+        let mut running_since = None;
+        let result = action.run(
+            &mut |event| {
+                let now = SystemTime::now();
+                if let Some(start) = running_since.take() {
+                    windows.push(HookWindow { start, end: now });
+                }
+                if matches!(event.payload, BuildEvent::RunningHook { .. }) {
+                    running_since = Some(now);
+                }
+                monitor(event.map_payload(WatchEvent::Build))
+            },
+            &mut |build_warning| warning(WatchWarning::Build(build_warning)),
+        );
+        if let Some(start) = running_since {
+            windows.push(HookWindow {
+                start,
+                end: SystemTime::now(),
+            });
+        }
+        if let Err(errors) = result {
+            warning(WatchWarning::BuildFailed(errors));
+        }
+        windows
+    }
+}
+
+/// 1 つのフックが実行されていた期間
+#[derive(Debug, Clone, Copy)]
+struct HookWindow {
+    start: SystemTime,
+    end: SystemTime,
+}
+
+impl HookWindow {
+    fn contains(&self, time: SystemTime) -> bool {
+        let start = self
+            .start
+            .checked_sub(HOOK_WINDOW_SLACK)
+            .unwrap_or(self.start);
+        time >= start && time <= self.end + HOOK_WINDOW_SLACK
+    }
+}
+
+/// フックの実行中に書き込まれたファイルだけを既知の状態として取り込む。
+/// それ以外の変更（ビルド中のソース編集や削除）は残し、次の周回で再ビルドさせる
+fn absorb_hook_writes(snapshot: &mut Snapshot, current: &Snapshot, windows: &[HookWindow]) {
+    for path in changed_paths(snapshot, current) {
+        if let Some(stamp @ (Some(modified), _)) = current.get(&path)
+            && windows.iter().any(|window| window.contains(*modified))
+        {
+            snapshot.insert(path, *stamp);
+        }
+    }
+}
+
+type Snapshot = BTreeMap<PathBuf, (Option<SystemTime>, u64)>;
+
+/// 監視ルート以下の全ファイルの更新時刻とサイズを収集する
+fn scan(roots: &[PathBuf], warning: &mut dyn FnMut(WatchWarning)) -> Snapshot {
+    let mut snapshot = Snapshot::new();
+    for root in roots {
+        if let Err(source) = scan_into(root, &mut snapshot) {
+            warning(WatchWarning::ScanFailed {
+                path: root.clone(),
+                source,
+            });
+        }
+    }
+    snapshot
+}
+
+fn scan_into(path: &Path, snapshot: &mut Snapshot) -> std::io::Result<()> {
+    let metadata = match std::fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(error) => return Err(error),
+    };
+
+    if metadata.is_dir() {
+        for entry in std::fs::read_dir(path)? {
+            scan_into(&entry?.path(), snapshot)?;
+        }
+    } else {
+        snapshot.insert(
+            path.to_path_buf(),
+            (metadata.modified().ok(), metadata.len()),
+        );
+    }
+    Ok(())
+}
+
+fn changed_paths(before: &Snapshot, after: &Snapshot) -> BTreeSet<PathBuf> {
+    let mut changed = BTreeSet::new();
+    for (path, stamp) in after {
+        if before.get(path) != Some(stamp) {
+            changed.insert(path.clone());
+        }
+    }
+    for path in before.keys() {
+        if !after.contains_key(path) {
+            changed.insert(path.clone());
+        }
+    }
+    changed
+}
+
+fn paper_id_of(papers_root: &Path, path: &Path) -> Option<String> {
+    path.strip_prefix(papers_root)
+        .ok()?
+        .components()
+        .next()
+        .and_then(|component| component.as_os_str().to_str())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ProjectToolChain;
+    use crate::models::project::{ProjectInfo, StructureConfig};
+    use tempfile::TempDir;
+
+    fn loaded_project(root: &Path) -> Loaded<Project, ProjectConfig> {
+        Loaded {
+            actual: Project::new(root.to_path_buf()),
+            config: ProjectConfig {
+                project: ProjectInfo {
+                    name: "demo".to_string(),
+                    init_date: "2026-04-23".to_string(),
+                },
+                toolchain: ProjectToolChain::default(),
+                structure: StructureConfig::default(),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn action(root: &Path, inputs: Option<Vec<String>>) -> WatchAction {
+        WatchAction::new(
+            loaded_project(root),
+            TypstDriver::new(PathBuf::from("typst")),
+            inputs,
+            None,
+        )
+    }
+
+    #[test]
+    fn test_changed_paths_reports_modified_added_and_removed_files() {
+        let stamp = (None, 1);
+        let before = Snapshot::from([
+            (PathBuf::from("a.typ"), stamp),
+            (PathBuf::from("b.typ"), stamp),
+        ]);
+        let after = Snapshot::from([
+            (PathBuf::from("a.typ"), (None, 2)),
+            (PathBuf::from("c.typ"), stamp),
+        ]);
+
+        let changed = changed_paths(&before, &after);
+
+        assert_eq!(
+            changed,
+            BTreeSet::from([
+                PathBuf::from("a.typ"),
+                PathBuf::from("b.typ"),
+                PathBuf::from("c.typ"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_absorb_hook_writes_keeps_source_edits_made_during_build() {
+        let start = SystemTime::now();
+        let window = HookWindow {
+            start,
+            end: start + Duration::from_secs(2),
+        };
+        let after_hook = start + Duration::from_secs(10);
+        let mut snapshot = Snapshot::from([
+            (PathBuf::from("generated.typ"), (Some(start), 1)),
+            (PathBuf::from("main.typ"), (Some(start), 1)),
+        ]);
+        let current = Snapshot::from([
+            (
+                PathBuf::from("generated.typ"),
+                (Some(start + Duration::from_secs(1)), 2),
+            ),
+            (PathBuf::from("main.typ"), (Some(after_hook), 2)),
+        ]);
+
+        absorb_hook_writes(&mut snapshot, &current, &[window]);
+
+        assert_eq!(
+            changed_paths(&snapshot, &current),
+            BTreeSet::from([PathBuf::from("main.typ")])
+        );
+    }
+
+    #[test]
+    fn test_affected_paper_ids_maps_paper_files_to_their_paper() {
+        let temp = TempDir::new().unwrap();
+        let papers = temp.path().join("papers");
+        std::fs::create_dir_all(papers.join("p01")).unwrap();
+        std::fs::create_dir_all(papers.join("p02")).unwrap();
+        let action = action(temp.path(), None);
+
+        let affected = action.affected_paper_ids(
+            &BTreeSet::from([papers.join("p02").join("main.typ")]),
+            &None,
+        );
+
+        assert_eq!(affected, vec!["p02".to_string()]);
+    }
+
+    #[test]
+    fn test_affected_paper_ids_ignores_papers_outside_targets() {
+        let temp = TempDir::new().unwrap();
+        let papers = temp.path().join("papers");
+        std::fs::create_dir_all(papers.join("p01")).unwrap();
+        std::fs::create_dir_all(papers.join("p02")).unwrap();
+        let action = action(temp.path(), Some(vec!["p01".to_string()]));
+
+        let affected = action.affected_paper_ids(
+            &BTreeSet::from([papers.join("p02").join("main.typ")]),
+            &Some(BTreeSet::from(["p01".to_string()])),
+        );
+
+        assert!(affected.is_empty());
+    }
+
+    #[test]
+    fn test_affected_paper_ids_rebuilds_all_targets_on_shared_template_change() {
+        let temp = TempDir::new().unwrap();
+        let papers = temp.path().join("papers");
+        std::fs::create_dir_all(papers.join("p01")).unwrap();
+        std::fs::create_dir_all(papers.join("p02")).unwrap();
+        let action = action(temp.path(), None);
+
+        let affected = action.affected_paper_ids(
+            &BTreeSet::from([temp.path().join("templates").join("ieee").join("lib.typ")]),
+            &None,
+        );
+
+        assert_eq!(affected, vec!["p01".to_string(), "p02".to_string()]);
+    }
+
+    #[test]
+    fn test_scan_collects_nested_files_and_skips_missing_roots() {
+        let temp = TempDir::new().unwrap();
+        let paper = temp.path().join("papers").join("p01");
+        std::fs::create_dir_all(paper.join("figures")).unwrap();
+        std::fs::write(paper.join("main.typ"), "= Hello").unwrap();
+        std::fs::write(paper.join("figures").join("plot.svg"), "<svg/>").unwrap();
+        let mut warnings = Vec::new();
+
+        let snapshot = scan(
+            &[paper.clone(), temp.path().join("missing")],
+            &mut |warning| warnings.push(warning),
+        );
+
+        assert_eq!(
+            snapshot.keys().cloned().collect::<Vec<_>>(),
+            vec![
+                paper.join("figures").join("plot.svg"),
+                paper.join("main.typ"),
+            ]
+        );
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_watch_rejects_unknown_paper_inputs() {
+        let temp = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp.path().join("papers")).unwrap();
+        let action = action(temp.path(), Some(vec!["missing".to_string()]));
+
+        let errors = match action.run(&mut |_| {}, &mut |_| {}) {
+            Ok(_) => panic!("expected unknown paper to fail"),
+            Err(errors) => errors,
+        };
+
+        assert!(matches!(errors.as_slice(), [WatchError::Discovery(_)]));
+    }
+}
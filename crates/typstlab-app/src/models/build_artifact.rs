@@ -1,4 +1,5 @@
 use std::path::PathBuf;
+use typstlab_base::diagnostics::BuildDiagnostic;
 use typstlab_proto::Artifact;
 
 /// ビルドの結果として生まれた事実を表す実体（証備）
@@ -8,6 +9,8 @@ pub struct BuildArtifact {
     pub absolute_path: PathBuf, // 実際の絶対パス
     pub success: bool,
     pub error_message: Option<String>,
+    /// コンパイラが報告したエラー・警告
+    pub diagnostics: Vec<BuildDiagnostic>,
 }
 
 typstlab_proto::impl_entity! {
@@ -18,6 +21,13 @@ typstlab_proto::impl_entity! {
     }
 }
 
+impl BuildArtifact {
+    /// フォーマット専用のサブディレクトリ（`p01/png` 等）か。pdf / html は論文の領土直下を共有する
+    pub fn is_format_dir(&self) -> bool {
+        self.root_name.components().count() > 1
+    }
+}
+
 impl Artifact for BuildArtifact {
     type Error = std::io::Error;
 
@@ -67,6 +77,7 @@ mod tests {
             absolute_path: artifact_dir.clone(),
             success: true,
             error_message: None,
+            diagnostics: Vec::new(),
         };
 
         let files = artifact.files().unwrap();
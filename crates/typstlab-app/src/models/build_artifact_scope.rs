@@ -1,7 +1,13 @@
 use crate::models::BuildArtifact;
-use std::path::PathBuf;
+use crate::models::dist_manifest::DistManifestFile;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+use typstlab_base::persistence::Persistence;
 use typstlab_proto::Entity;
 
+const DIST_MANIFEST_FILE: &str = "manifest.json";
+
 pub struct BuildArtifactScope {
     pub project_root: PathBuf,
     pub relative_path: PathBuf,
@@ -23,12 +29,177 @@ impl BuildArtifactScope {
         }
     }
 
+    /// dist 以下のサブディレクトリを成果物領土とするスコープ（プロファイル用）
+    pub fn subdir(&self, relative: &Path) -> Self {
+        Self::new(self.project_root.clone(), self.relative_path.join(relative))
+    }
+
     pub fn paper_scope(&self, paper_id: &str) -> PaperArtifactScope {
         PaperArtifactScope {
             paper_id: paper_id.to_string(),
             root: self.path().join(paper_id),
         }
     }
+
+    /// dist ルートの `manifest.json`
+    pub fn manifest(&self) -> DistManifestFile {
+        DistManifestFile::load(self.path().join(DIST_MANIFEST_FILE))
+    }
+
+    /// コンパイル出力の作業場所が置かれるディレクトリ
+    pub fn staging_root(&self) -> PathBuf {
+        self.path().join(".tmp")
+    }
+
+    /// コンパイル出力を受け取る一時作業場所を作る（drop されれば何も残らない）
+    pub fn create_staging_area(&self, id: &str) -> std::io::Result<TempDir> {
+        let prefix = format!("staging-{}-", id);
+        Persistence::create_temp_dir(self.staging_root(), &prefix).map_err(std::io::Error::other)
+    }
+
+    /// 空になった作業場所の置き場を片付ける（使用中のものが残っていれば何もしない）
+    pub fn remove_staging_root(&self) {
+        let _ = std::fs::remove_dir(self.staging_root());
+    }
+
+    /// 作業場所で完成した出力を成果物の領土へ反映する。
+    /// 領土は接尾辞違いのプロファイルと共有されるので、`naming` の出力以外には触れない。
+    pub fn commit_staged(
+        &self,
+        artifact: &BuildArtifact,
+        staging: TempDir,
+        naming: &OutputNaming,
+    ) -> std::io::Result<()> {
+        if artifact.is_format_dir() {
+            Self::swap_format_dir(&artifact.path(), staging, naming)
+        } else {
+            Self::replace_files(&artifact.path(), staging, naming)
+        }
+    }
+
+    /// png / svg のディレクトリを丸ごと入れ替える。途中で失敗しても新旧のページが混ざらない
+    fn swap_format_dir(
+        dest: &Path,
+        staging: TempDir,
+        naming: &OutputNaming,
+    ) -> std::io::Result<()> {
+        // 他の出力名のファイルは新しいディレクトリへ引き継ぐ
+        if dest.is_dir() {
+            for entry in std::fs::read_dir(dest)? {
+                let entry = entry?;
+                let name = entry.file_name();
+                if entry.file_type()?.is_file() && !naming.owns(&name.to_string_lossy()) {
+                    std::fs::copy(entry.path(), staging.path().join(&name))?;
+                }
+            }
+        }
+
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let staged = staging.keep();
+        let previous = staged.with_extension("previous");
+        let had_previous = dest.exists();
+        if had_previous && let Err(e) = std::fs::rename(dest, &previous) {
+            let _ = std::fs::remove_dir_all(&staged);
+            return Err(e);
+        }
+        if let Err(e) = std::fs::rename(&staged, dest) {
+            // 入れ替えられなければ元に戻す
+            if had_previous {
+                let _ = std::fs::rename(&previous, dest);
+            }
+            let _ = std::fs::remove_dir_all(&staged);
+            return Err(e);
+        }
+        if had_previous {
+            std::fs::remove_dir_all(&previous)?;
+        }
+        Ok(())
+    }
+
+    /// pdf / html は他フォーマットのサブディレクトリと同居するため、直下のファイルだけを入れ替える
+    fn replace_files(dest: &Path, staging: TempDir, naming: &OutputNaming) -> std::io::Result<()> {
+        std::fs::create_dir_all(dest)?;
+
+        let mut staged = HashSet::new();
+        for entry in std::fs::read_dir(staging.path())? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                std::fs::rename(entry.path(), dest.join(entry.file_name()))?;
+                staged.insert(entry.file_name());
+            }
+        }
+
+        // 前回のビルドにしか無いファイル（減ったページ等）を取り除く
+        for entry in std::fs::read_dir(dest)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file()
+                && !staged.contains(&entry.file_name())
+                && naming.owns(&entry.file_name().to_string_lossy())
+            {
+                std::fs::remove_file(entry.path())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 1 回のビルド（論文 × プロファイル）が dist に書くファイルの名前付け。
+/// PNG / SVG のページは接尾辞が無ければ従来どおり `1.png`、
+/// 接尾辞付きのプロファイルでは同じディレクトリを共有するため `main-anon-1.png` とする。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputNaming {
+    /// `main` や `main-anon`（pdf / html / トレースのファイル名）
+    pub name: String,
+    /// ページ番号の前に付ける文字列（接尾辞が無ければ空）
+    pub page_prefix: String,
+}
+
+impl OutputNaming {
+    pub fn new(base_name: &str, suffix: Option<&str>) -> Self {
+        match suffix.filter(|suffix| !suffix.is_empty()) {
+            Some(suffix) => {
+                let name = format!("{}{}", base_name, suffix);
+                Self {
+                    page_prefix: format!("{}-", name),
+                    name,
+                }
+            }
+            None => Self {
+                name: base_name.to_string(),
+                page_prefix: String::new(),
+            },
+        }
+    }
+
+    /// Typst に渡すページ出力のテンプレート（`{0p}.png` など）
+    pub fn page_template(&self, ext: &str) -> String {
+        format!("{}{{0p}}.{}", self.page_prefix, ext)
+    }
+
+    /// このビルドが dist に書くファイルか
+    /// (`main.pdf` / `main.html` / `main.timings.json` / `01.png` / `main-anon-01.svg` など)
+    pub fn owns(&self, file_name: &str) -> bool {
+        if let Some(rest) = file_name.strip_prefix(&self.name)
+            && matches!(rest, ".pdf" | ".html" | ".timings.json")
+        {
+            return true;
+        }
+        self.is_page(file_name)
+    }
+
+    /// このビルドの PNG / SVG のページか
+    pub fn is_page(&self, file_name: &str) -> bool {
+        file_name
+            .strip_prefix(&self.page_prefix)
+            .and_then(|page| page.split_once('.'))
+            .is_some_and(|(number, ext)| {
+                !number.is_empty()
+                    && number.bytes().all(|b| b.is_ascii_digit())
+                    && matches!(ext, "png" | "svg")
+            })
+    }
 }
 
 pub struct PaperArtifactScope {
@@ -62,14 +233,16 @@ impl PaperArtifactScope {
             absolute_path,
             success: false,
             error_message: None,
+            diagnostics: Vec::new(),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::BuildArtifactScope;
+    use super::{BuildArtifactScope, OutputNaming};
     use std::path::PathBuf;
+    use tempfile::TempDir;
     use typstlab_proto::Entity;
 
     #[test]
@@ -80,4 +253,78 @@ mod tests {
 
         assert_eq!(scope.path(), root.join("target").join("artifacts"));
     }
+
+    #[test]
+    fn test_commit_staged_replaces_files_but_keeps_format_subdirs() {
+        let temp = TempDir::new().unwrap();
+        let scope = BuildArtifactScope::new(temp.path().to_path_buf(), PathBuf::from("dist"));
+        let pdf = scope.paper_scope("p01").format_artifact("pdf");
+        let png = scope.paper_scope("p01").format_artifact("png");
+        std::fs::create_dir_all(png.path()).unwrap();
+        std::fs::write(png.path().join("1.png"), b"png").unwrap();
+        std::fs::write(pdf.path().join("old.pdf"), b"old").unwrap();
+
+        std::fs::write(pdf.path().join("main.timings.json"), b"old").unwrap();
+        std::fs::write(pdf.path().join("main-anon.pdf"), b"other profile").unwrap();
+
+        let staging = scope.create_staging_area("p01-pdf").unwrap();
+        let staging_path = staging.path().to_path_buf();
+        std::fs::write(staging_path.join("main.pdf"), b"new").unwrap();
+        scope
+            .commit_staged(&pdf, staging, &OutputNaming::new("main", None))
+            .unwrap();
+
+        assert_eq!(std::fs::read(pdf.path().join("main.pdf")).unwrap(), b"new");
+        assert!(!pdf.path().join("main.timings.json").exists());
+        // 別の出力名（他プロファイルや手で置いたファイル）には触れない
+        assert!(pdf.path().join("main-anon.pdf").exists());
+        assert!(pdf.path().join("old.pdf").exists());
+        assert!(png.path().join("1.png").exists());
+        assert!(!staging_path.exists());
+    }
+
+    #[test]
+    fn test_commit_staged_swaps_format_dir_and_keeps_other_outputs() {
+        let temp = TempDir::new().unwrap();
+        let scope = BuildArtifactScope::new(temp.path().to_path_buf(), PathBuf::from("dist"));
+        let png = scope.paper_scope("p01").format_artifact("png");
+        std::fs::create_dir_all(png.path()).unwrap();
+        for page in ["1.png", "2.png", "main-anon-1.png"] {
+            std::fs::write(png.path().join(page), b"old").unwrap();
+        }
+
+        let staging = scope.create_staging_area("p01-png").unwrap();
+        std::fs::write(staging.path().join("1.png"), b"new").unwrap();
+        scope
+            .commit_staged(&png, staging, &OutputNaming::new("main", None))
+            .unwrap();
+
+        assert_eq!(std::fs::read(png.path().join("1.png")).unwrap(), b"new");
+        assert!(!png.path().join("2.png").exists());
+        assert_eq!(
+            std::fs::read(png.path().join("main-anon-1.png")).unwrap(),
+            b"old"
+        );
+        // 入れ替えに使った一時ディレクトリは残らない
+        assert_eq!(std::fs::read_dir(scope.staging_root()).unwrap().count(), 0);
+        scope.remove_staging_root();
+        assert!(!scope.staging_root().exists());
+    }
+
+    #[test]
+    fn test_output_naming_owns_only_its_own_files() {
+        let plain = OutputNaming::new("main", None);
+        assert_eq!(plain.page_template("png"), "{0p}.png");
+        assert!(plain.owns("main.pdf"));
+        assert!(plain.owns("03.png"));
+        assert!(!plain.owns("main-anon.pdf"));
+        assert!(!plain.owns("main-anon-1.png"));
+
+        let anon = OutputNaming::new("main", Some("-anon"));
+        assert_eq!(anon.page_template("svg"), "main-anon-{0p}.svg");
+        assert!(anon.owns("main-anon.pdf"));
+        assert!(anon.owns("main-anon-1.svg"));
+        assert!(!anon.owns("main.pdf"));
+        assert!(!anon.owns("1.png"));
+    }
 }
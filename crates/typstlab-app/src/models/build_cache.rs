@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use typstlab_base::persistence::Persistence;
+
+/// 差分ビルドのために前回ビルド時の入力を記録するマニフェスト
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct BuildCacheManifest {
+    #[serde(default)]
+    pub papers: BTreeMap<String, BuildCacheEntry>,
+}
+
+/// 論文ごとの前回ビルドの指紋
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildCacheEntry {
+    /// 論文ディレクトリ・共有入力・コンパイル引数のハッシュ
+    pub inputs_hash: String,
+    pub typst_version: String,
+    pub formats: Vec<String>,
+}
+
+/// `.typstlab/build-manifest.json` に置かれるビルドキャッシュ
+pub struct BuildCache {
+    pub path: PathBuf,
+    pub manifest: BuildCacheManifest,
+}
+
+typstlab_proto::impl_entity! {
+    BuildCache {
+        fn path(&self) -> PathBuf {
+            self.path.clone()
+        }
+    }
+}
+
+impl BuildCache {
+    /// マニフェストを読み込む。存在しない・壊れている場合は空のキャッシュとして扱う。
+    pub fn load(path: PathBuf) -> Self {
+        let manifest = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self { path, manifest }
+    }
+
+    pub fn is_fresh(&self, paper_id: &str, entry: &BuildCacheEntry) -> bool {
+        self.manifest.papers.get(paper_id) == Some(entry)
+    }
+
+    pub fn record(&mut self, paper_id: &str, entry: BuildCacheEntry) {
+        self.manifest.papers.insert(paper_id.to_string(), entry);
+    }
+
+    pub fn forget(&mut self, paper_id: &str) {
+        self.manifest.papers.remove(paper_id);
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let content = serde_json::to_vec_pretty(&self.manifest)?;
+        Persistence::write_file(&self.path, &content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BuildCache, BuildCacheEntry};
+    use tempfile::TempDir;
+
+    fn entry(hash: &str) -> BuildCacheEntry {
+        BuildCacheEntry {
+            inputs_hash: hash.to_string(),
+            typst_version: "0.14.2".to_string(),
+            formats: vec!["pdf".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_round_trip_and_freshness() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(".typstlab").join("build-manifest.json");
+
+        let mut cache = BuildCache::load(path.clone());
+        assert!(!cache.is_fresh("p01", &entry("a")));
+
+        cache.record("p01", entry("a"));
+        cache.save().unwrap();
+
+        let cache = BuildCache::load(path);
+        assert!(cache.is_fresh("p01", &entry("a")));
+        assert!(!cache.is_fresh("p01", &entry("b")));
+    }
+
+    #[test]
+    fn test_corrupt_manifest_is_treated_as_empty() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("build-manifest.json");
+        std::fs::write(&path, "{not json").unwrap();
+
+        let cache = BuildCache::load(path);
+        assert!(cache.manifest.papers.is_empty());
+    }
+}
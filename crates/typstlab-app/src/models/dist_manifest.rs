@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use typstlab_base::digest::sha256_file;
+use typstlab_base::persistence::Persistence;
+
+/// `dist/manifest.json` の中身。下流のスクリプトが dist を走査せずに成果物を知るためのもの。
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DistManifest {
+    /// 論文 ID ごとの最新のビルド結果（今回ビルドしなかった論文は前回の記録が残る）
+    #[serde(default)]
+    pub papers: BTreeMap<String, PaperManifest>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaperManifest {
+    pub success: bool,
+    pub typst_version: Option<String>,
+    /// RFC 3339 形式のビルド時刻
+    pub built_at: String,
+    pub formats: Vec<FormatManifest>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FormatManifest {
+    pub format: String,
+    pub status: FormatStatus,
+    /// コンパイルにかかった時間（スキップした場合は無し）
+    pub duration_ms: Option<u64>,
+    pub page_count: Option<usize>,
+    /// 失敗時は dist に残っている前回の成果物を列挙する
+    pub files: Vec<ManifestFile>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FormatStatus {
+    Built,
+    UpToDate,
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestFile {
+    /// マニフェストのあるディレクトリからの `/` 区切りの相対パス
+    pub path: String,
+    pub sha256: String,
+    pub size: u64,
+}
+
+impl ManifestFile {
+    pub fn read(root: &Path, path: &Path) -> std::io::Result<Self> {
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        let path_str = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        Ok(Self {
+            path: path_str,
+            sha256: sha256_file(path)?,
+            size: std::fs::metadata(path)?.len(),
+        })
+    }
+}
+
+/// dist ルートに置かれるマニフェストファイル
+pub struct DistManifestFile {
+    pub path: PathBuf,
+    pub manifest: DistManifest,
+}
+
+typstlab_proto::impl_entity! {
+    DistManifestFile {
+        fn path(&self) -> PathBuf {
+            self.path.clone()
+        }
+    }
+}
+
+impl DistManifestFile {
+    /// 既存のマニフェストを読み込む。存在しない・壊れている場合は空として扱う。
+    pub fn load(path: PathBuf) -> Self {
+        let manifest = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self { path, manifest }
+    }
+
+    pub fn record(&mut self, paper_id: &str, paper: PaperManifest) {
+        self.manifest.papers.insert(paper_id.to_string(), paper);
+    }
+
+    /// 論文の記録を取り除く
+    pub fn forget(&mut self, paper_id: &str) {
+        self.manifest.papers.remove(paper_id);
+    }
+
+    /// 論文の特定フォーマットの記録だけを取り除く
+    pub fn forget_format(&mut self, paper_id: &str, format: &str) {
+        if let Some(paper) = self.manifest.papers.get_mut(paper_id) {
+            paper.formats.retain(|entry| entry.format != format);
+        }
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let content = serde_json::to_vec_pretty(&self.manifest)?;
+        Persistence::write_file(&self.path, &content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DistManifestFile, FormatStatus, ManifestFile, PaperManifest};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_manifest_round_trip_keeps_other_papers() {
+        let temp = TempDir::new().unwrap();
+        let dist = temp.path().join("dist");
+        std::fs::create_dir_all(dist.join("p01")).unwrap();
+        std::fs::write(dist.join("p01").join("main.pdf"), b"pdf").unwrap();
+        let path = dist.join("manifest.json");
+
+        let paper = |success: bool| PaperManifest {
+            success,
+            typst_version: Some("0.14.2".to_string()),
+            built_at: "2026-04-23T00:00:00Z".to_string(),
+            formats: Vec::new(),
+        };
+        let mut manifest = DistManifestFile::load(path.clone());
+        manifest.record("p01", paper(true));
+        manifest.record("p02", paper(true));
+        manifest.save().unwrap();
+
+        let mut manifest = DistManifestFile::load(path.clone());
+        manifest.record("p01", paper(false));
+        manifest.save().unwrap();
+
+        let manifest = DistManifestFile::load(path);
+        assert!(!manifest.manifest.papers["p01"].success);
+        assert!(manifest.manifest.papers["p02"].success);
+
+        let file = ManifestFile::read(&dist, &dist.join("p01").join("main.pdf")).unwrap();
+        assert_eq!(file.path, "p01/main.pdf");
+        assert_eq!(file.size, 3);
+        assert_eq!(file.sha256.len(), 64);
+        assert_eq!(
+            serde_json::to_string(&FormatStatus::UpToDate).unwrap(),
+            "\"up_to_date\""
+        );
+    }
+}
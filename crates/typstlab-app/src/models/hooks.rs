@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// `[hooks]` テーブル。typstlab.toml ではビルド全体の前後、paper.toml では論文ごとの前後に実行する。
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct HooksConfig {
+    /// コンパイル前に順に実行するコマンド（1 つでも失敗すればその対象のビルドを中止する）
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub pre_build: Vec<String>,
+    /// ビルドが成功した後に順に実行するコマンド
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub post_build: Vec<String>,
+}
+
+impl HooksConfig {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    pub fn commands(&self, stage: HookStage) -> &[String] {
+        match stage {
+            HookStage::PreBuild => &self.pre_build,
+            HookStage::PostBuild => &self.post_build,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookStage {
+    PreBuild,
+    PostBuild,
+}
+
+impl fmt::Display for HookStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HookStage::PreBuild => write!(f, "pre_build"),
+            HookStage::PostBuild => write!(f, "post_build"),
+        }
+    }
+}
+
+/// `pre_build = "make plots"` のような単一コマンドも受け付ける
+fn one_or_many<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(command) => vec![command],
+        OneOrMany::Many(commands) => commands,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HooksConfig;
+
+    #[test]
+    fn test_hooks_accept_single_command_or_list() {
+        let hooks: HooksConfig =
+            toml::from_str("pre_build = \"make plots\"\npost_build = [\"a\", \"b\"]\n").unwrap();
+
+        assert_eq!(hooks.pre_build, vec!["make plots".to_string()]);
+        assert_eq!(hooks.post_build, vec!["a".to_string(), "b".to_string()]);
+        assert!(HooksConfig::default().is_empty());
+    }
+}
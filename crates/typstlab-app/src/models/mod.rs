@@ -1,6 +1,9 @@
 pub mod build_artifact;
 pub mod build_artifact_scope;
+pub mod build_cache;
+pub mod dist_manifest;
 pub mod docs;
+pub mod hooks;
 pub mod paper;
 pub mod paper_scope;
 pub mod project;
@@ -12,13 +15,22 @@ pub mod typst;
 
 pub use build_artifact::BuildArtifact;
 pub use build_artifact_scope::BuildArtifactScope;
+pub use build_cache::{BuildCache, BuildCacheEntry};
+pub use dist_manifest::{
+    DistManifest, DistManifestFile, FormatManifest, FormatStatus, ManifestFile, PaperManifest,
+};
 pub use docs::Docs;
-pub use paper::{Paper, PaperConfig, PaperCreationArgs, PaperError, PaperHandle};
+pub use hooks::{HookStage, HooksConfig};
+pub use paper::{
+    OutputFormat, Paper, PaperBuildConfig, PaperConfig, PaperCreationArgs, PaperError, PaperHandle,
+};
 pub use paper_scope::{CollectionError, PaperScope};
 pub use project::{
-    Project, ProjectConfig, ProjectError, ProjectHandle, ProjectToolChain, ToolChoice,
+    BuildProfile, FontsConfig, Project, ProjectConfig, ProjectError, ProjectHandle,
+    ProjectToolChain, TestConfig, ToolChoice, ToolchainMirrors,
 };
 pub use store_docs::DocsStore;
 pub use store_typst::TypstStore;
 pub use template::Template;
 pub use typst::Typst;
+pub use typstlab_base::diagnostics::{BuildDiagnostic, DiagnosticSeverity};
@@ -1,4 +1,6 @@
+use crate::models::hooks::HooksConfig;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use thiserror::Error;
 use typstlab_proto::{Creatable, Loadable, Loaded, PAPER_SETTING_FILE};
@@ -23,6 +25,8 @@ impl Creatable for Paper {
                     entry_point: default_entry_point(),
                     output_name: default_output_name(),
                 },
+                build: PaperBuildConfig::default(),
+                hooks: HooksConfig::default(),
             },
         })
     }
@@ -53,6 +57,11 @@ impl Creatable for Paper {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PaperConfig {
     pub paper: PaperInfo,
+    #[serde(default, skip_serializing_if = "PaperBuildConfig::is_empty")]
+    pub build: PaperBuildConfig,
+    /// この論文のビルド前後に実行するコマンド
+    #[serde(default, skip_serializing_if = "HooksConfig::is_empty")]
+    pub hooks: HooksConfig,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -65,6 +74,46 @@ pub struct PaperInfo {
     pub output_name: String,
 }
 
+/// paper.toml の `[build]` テーブル。未指定の項目は Typst の既定に任せる。
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PaperBuildConfig {
+    /// CLI でフォーマットが指定されなかった時に出力するフォーマット
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub formats: Option<Vec<OutputFormat>>,
+    /// PNG 出力の解像度
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ppi: Option<f32>,
+    /// 出力するページ範囲 (例: `"1-3,5"`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pages: Option<String>,
+    /// `sys.inputs` に渡すキーと値
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub inputs: BTreeMap<String, String>,
+    /// 追加のフォント検索パス（論文ディレクトリからの相対パス）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub font_paths: Vec<PathBuf>,
+    /// PDF 規格 (例: `"a-2b"`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pdf_standard: Option<String>,
+}
+
+impl PaperBuildConfig {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// 論文が出力できるフォーマット
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Pdf,
+    Png,
+    Svg,
+    Html,
+}
+
 impl Default for PaperConfig {
     fn default() -> Self {
         Self {
@@ -73,6 +122,8 @@ impl Default for PaperConfig {
                 entry_point: default_entry_point(),
                 output_name: default_output_name(),
             },
+            build: PaperBuildConfig::default(),
+            hooks: HooksConfig::default(),
         }
     }
 }
@@ -135,6 +186,10 @@ pub trait PaperHandle {
     fn output_base_name(&self) -> &str;
     fn main_typ_path(&self) -> PathBuf;
     fn paper_id(&self) -> &str;
+    fn build_config(&self) -> &PaperBuildConfig;
+    /// `[build].font_paths` を論文ディレクトリ基準で解決したもの
+    fn font_paths(&self) -> Vec<PathBuf>;
+    fn hooks(&self) -> &HooksConfig;
 }
 
 impl PaperHandle for Loaded<Paper, PaperConfig> {
@@ -151,11 +206,28 @@ impl PaperHandle for Loaded<Paper, PaperConfig> {
     fn paper_id(&self) -> &str {
         &self.actual.id
     }
+
+    fn build_config(&self) -> &PaperBuildConfig {
+        &self.config.build
+    }
+
+    fn font_paths(&self) -> Vec<PathBuf> {
+        self.config
+            .build
+            .font_paths
+            .iter()
+            .map(|path| self.actual.absolute_path.join(path))
+            .collect()
+    }
+
+    fn hooks(&self) -> &HooksConfig {
+        &self.config.hooks
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Paper, PaperConfig, PaperError, PaperHandle};
+    use super::{OutputFormat, Paper, PaperBuildConfig, PaperConfig, PaperError, PaperHandle};
     use std::path::PathBuf;
     use tempfile::TempDir;
     use typstlab_proto::Loadable;
@@ -228,4 +300,50 @@ mod tests {
             paper_root.join("src").join("main.typ")
         );
     }
+
+    #[test]
+    fn test_build_table_is_optional_and_parsed() {
+        let config: PaperConfig = toml::from_str(
+            r#"
+                [paper]
+                title = "Demo"
+
+                [build]
+                formats = ["pdf", "png"]
+                ppi = 300.0
+                pages = "1-3"
+                font_paths = ["fonts"]
+                pdf_standard = "a-2b"
+
+                [build.inputs]
+                lang = "ja"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.build.formats,
+            Some(vec![OutputFormat::Pdf, OutputFormat::Png])
+        );
+        assert_eq!(config.build.ppi, Some(300.0));
+        assert_eq!(config.build.pages.as_deref(), Some("1-3"));
+        assert_eq!(config.build.inputs["lang"], "ja");
+        assert_eq!(config.build.pdf_standard.as_deref(), Some("a-2b"));
+
+        let plain: PaperConfig = toml::from_str("[paper]\ntitle = \"Demo\"\n").unwrap();
+        assert_eq!(plain.build, PaperBuildConfig::default());
+        assert!(!toml::to_string_pretty(&plain).unwrap().contains("[build]"));
+    }
+
+    #[test]
+    fn test_build_table_rejects_unknown_formats_and_keys() {
+        let unknown_format = toml::from_str::<PaperConfig>(
+            "[paper]\ntitle = \"Demo\"\n[build]\nformats = [\"docx\"]\n",
+        );
+        let unknown_key =
+            toml::from_str::<PaperConfig>("[paper]\ntitle = \"Demo\"\n[build]\ndpi = 1\n");
+
+        assert!(unknown_format.is_err());
+        assert!(unknown_key.is_err());
+    }
 }
@@ -53,6 +53,7 @@ impl Collection<Paper, CollectionError> for PaperScope {
                 papers.push(Paper::new(id.to_string(), root.clone()));
             }
         }
+        papers.sort_by(|a, b| a.id.cmp(&b.id));
         Ok(papers)
     }
 
@@ -1,12 +1,22 @@
 use crate::models::build_artifact_scope::BuildArtifactScope;
+use crate::models::build_cache::BuildCache;
+use crate::models::hooks::HooksConfig;
+use crate::models::paper::OutputFormat;
 use crate::models::paper_scope::PaperScope;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use thiserror::Error;
+use typstlab_base::path::is_path_safe;
 use typstlab_proto::{Creatable, Entity, Loadable, Loaded, PROJECT_SETTING_FILE};
 
 pub use typstlab_base::version_resolver::ProjectToolChain;
 pub use typstlab_base::version_resolver::ToolChoice;
+pub use typstlab_base::version_resolver::ToolchainMirrors;
+
+const PROJECT_CACHE_DIR: &str = ".typstlab";
+const BUILD_MANIFEST_FILE: &str = "build-manifest.json";
+const BUILD_LOCK_FILE: &str = "build.lock";
 
 #[derive(Error, Debug)]
 pub enum ProjectError {
@@ -27,6 +37,53 @@ pub struct ProjectConfig {
     pub toolchain: ProjectToolChain,
     #[serde(default)]
     pub structure: StructureConfig,
+    #[serde(default)]
+    pub fonts: FontsConfig,
+    /// `[profiles.<name>]` で定義される名前付きビルド設定
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, BuildProfile>,
+    /// ビルド全体の前後に実行するコマンド
+    #[serde(default, skip_serializing_if = "HooksConfig::is_empty")]
+    pub hooks: HooksConfig,
+    /// `typstlab test` のスナップショット比較
+    #[serde(default, skip_serializing_if = "TestConfig::is_default")]
+    pub test: TestConfig,
+}
+
+/// `typstlab build --profile <name>` で選ぶビルド設定。論文の `[build]` より優先される。
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct BuildProfile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub formats: Option<Vec<OutputFormat>>,
+    /// `sys.inputs` に渡すキーと値
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub inputs: BTreeMap<String, String>,
+    /// 成果物のベース名に付ける接尾辞 (例: `"-anon"` で `main-anon.pdf`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_suffix: Option<String>,
+    /// dist 以下の出力先サブディレクトリ (例: `"camera-ready"`)。絶対パスと `..` は読み込み時に拒否する
+    #[serde(
+        default,
+        deserialize_with = "dist_subdir",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub dist_subdir: Option<PathBuf>,
+}
+
+/// dist の外へ出力（と掃除）が及ばないよう、相対パスだけを受け付ける
+fn dist_subdir<'de, D>(deserializer: D) -> Result<Option<PathBuf>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let path = Option::<PathBuf>::deserialize(deserializer)?;
+    match path {
+        Some(path) if !is_path_safe(&path) => Err(serde::de::Error::custom(format!(
+            "dist_subdir must be a relative path inside dist without `..`: {}",
+            path.display()
+        ))),
+        path => Ok(path),
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -61,6 +118,10 @@ pub struct StructureConfig {
     pub dist_dir: PathBuf,
     #[serde(default = "default_templates_dir")]
     pub templates_dir: PathBuf,
+    /// 論文間で共有する Typst ソースの置き場（例: `lib/`）。
+    /// 設定するとプロジェクトルートを Typst のルートとしてコンパイルし、`/lib/...` で参照できる。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shared_dir: Option<PathBuf>,
 }
 
 impl Default for StructureConfig {
@@ -69,6 +130,7 @@ impl Default for StructureConfig {
             papers_dir: default_papers_dir(),
             dist_dir: default_dist_dir(),
             templates_dir: default_templates_dir(),
+            shared_dir: None,
         }
     }
 }
@@ -85,6 +147,79 @@ fn default_templates_dir() -> PathBuf {
     PathBuf::from("templates")
 }
 
+/// 全ての論文のコンパイルで使うフォント設定。環境差による出力の揺れを防ぐ。
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FontsConfig {
+    /// プロジェクトルートからのフォントディレクトリ
+    #[serde(default = "default_font_dirs")]
+    pub dirs: Vec<PathBuf>,
+    /// true ならシステムフォントを使わず、プロジェクトのフォントと Typst 同梱フォントだけを使う
+    #[serde(default)]
+    pub ignore_system_fonts: bool,
+}
+
+impl Default for FontsConfig {
+    fn default() -> Self {
+        Self {
+            dirs: default_font_dirs(),
+            ignore_system_fonts: false,
+        }
+    }
+}
+
+fn default_font_dirs() -> Vec<PathBuf> {
+    vec![PathBuf::from("fonts")]
+}
+
+/// 論文とテンプレートの PNG 出力を基準画像と比べる設定
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TestConfig {
+    /// 基準画像の置き場（プロジェクトルートから）
+    #[serde(default = "default_tests_dir")]
+    pub dir: PathBuf,
+    /// 1 ページのうち変化してもよい画素の割合 (0.0〜1.0)
+    #[serde(default, deserialize_with = "tolerance")]
+    pub tolerance: f64,
+}
+
+impl TestConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// 割合として意味のある値か（NaN や無限大、範囲外は比較が常に成功・失敗してしまう）
+    pub fn is_valid_tolerance(value: f64) -> bool {
+        (0.0..=1.0).contains(&value)
+    }
+}
+
+fn tolerance<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = f64::deserialize(deserializer)?;
+    if !TestConfig::is_valid_tolerance(value) {
+        return Err(serde::de::Error::custom(format!(
+            "tolerance must be between 0.0 and 1.0: {}",
+            value
+        )));
+    }
+    Ok(value)
+}
+
+impl Default for TestConfig {
+    fn default() -> Self {
+        Self {
+            dir: default_tests_dir(),
+            tolerance: 0.0,
+        }
+    }
+}
+
+fn default_tests_dir() -> PathBuf {
+    PathBuf::from("tests")
+}
+
 pub struct Project {
     pub root: PathBuf,
 }
@@ -137,6 +272,10 @@ impl Creatable for Project {
                 },
                 toolchain: ProjectToolChain::default(),
                 structure: StructureConfig::default(),
+                fonts: FontsConfig::default(),
+                profiles: BTreeMap::new(),
+                hooks: HooksConfig::default(),
+                test: TestConfig::default(),
             },
         })
     }
@@ -160,8 +299,29 @@ pub trait ProjectHandle {
     fn papers_scope(&self) -> PaperScope;
     fn templates_scope(&self) -> crate::models::template_scope::TemplateScope;
     fn build_artifact_scope(&self) -> BuildArtifactScope;
+    /// 複数の論文から共有される入力（テンプレート等）のディレクトリ
+    fn shared_source_dirs(&self) -> Vec<PathBuf>;
+    /// `[structure] shared_dir` の絶対パス
+    fn shared_dir(&self) -> Option<PathBuf>;
+    /// Typst に `--root` として渡すディレクトリ。共有ディレクトリがある場合のみプロジェクトルート。
+    fn compile_root(&self) -> Option<PathBuf>;
+    /// プロジェクトローカルのキャッシュ置き場 (`.typstlab/`)
+    fn cache_dir(&self) -> PathBuf;
+    /// `.typstlab/.tmp`（プロジェクトドキュメント同期などの作業場所）
+    fn tmp_dir(&self) -> PathBuf;
+    fn build_cache(&self) -> BuildCache;
+    /// 同じプロジェクトのビルドを直列化するためのロックファイル
+    fn build_lock_path(&self) -> PathBuf;
+    /// `[fonts].dirs` をプロジェクトルート基準で解決したもの
+    fn font_dirs(&self) -> Vec<PathBuf>;
+    fn fonts(&self) -> &FontsConfig;
     fn name(&self) -> &str;
     fn toolchain(&self) -> &ProjectToolChain;
+    fn profile(&self, name: &str) -> Option<&BuildProfile>;
+    fn hooks(&self) -> &HooksConfig;
+    fn test_config(&self) -> &TestConfig;
+    /// `[test].dir` をプロジェクトルート基準で解決したもの
+    fn snapshots_dir(&self) -> PathBuf;
 }
 
 impl ProjectHandle for Loaded<Project, ProjectConfig> {
@@ -186,6 +346,58 @@ impl ProjectHandle for Loaded<Project, ProjectConfig> {
         )
     }
 
+    fn shared_source_dirs(&self) -> Vec<PathBuf> {
+        let mut dirs = vec![self.templates_scope().path()];
+        dirs.extend(self.shared_dir());
+        dirs.extend(self.font_dirs());
+        dirs
+    }
+
+    fn shared_dir(&self) -> Option<PathBuf> {
+        self.config
+            .structure
+            .shared_dir
+            .as_ref()
+            .map(|dir| self.actual.root.join(dir))
+    }
+
+    fn compile_root(&self) -> Option<PathBuf> {
+        self.config
+            .structure
+            .shared_dir
+            .is_some()
+            .then(|| self.actual.root.clone())
+    }
+
+    fn font_dirs(&self) -> Vec<PathBuf> {
+        self.config
+            .fonts
+            .dirs
+            .iter()
+            .map(|dir| self.actual.root.join(dir))
+            .collect()
+    }
+
+    fn fonts(&self) -> &FontsConfig {
+        &self.config.fonts
+    }
+
+    fn cache_dir(&self) -> PathBuf {
+        self.actual.root.join(PROJECT_CACHE_DIR)
+    }
+
+    fn tmp_dir(&self) -> PathBuf {
+        typstlab_base::project_docs::project_staging_root(&self.actual.root)
+    }
+
+    fn build_cache(&self) -> BuildCache {
+        BuildCache::load(self.cache_dir().join(BUILD_MANIFEST_FILE))
+    }
+
+    fn build_lock_path(&self) -> PathBuf {
+        self.cache_dir().join(BUILD_LOCK_FILE)
+    }
+
     fn name(&self) -> &str {
         &self.config.project.name
     }
@@ -193,14 +405,31 @@ impl ProjectHandle for Loaded<Project, ProjectConfig> {
     fn toolchain(&self) -> &ProjectToolChain {
         &self.config.toolchain
     }
+
+    fn profile(&self, name: &str) -> Option<&BuildProfile> {
+        self.config.profiles.get(name)
+    }
+
+    fn hooks(&self) -> &HooksConfig {
+        &self.config.hooks
+    }
+
+    fn test_config(&self) -> &TestConfig {
+        &self.config.test
+    }
+
+    fn snapshots_dir(&self) -> PathBuf {
+        self.actual.root.join(&self.config.test.dir)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{
-        Project, ProjectConfig, ProjectHandle, ProjectInfo, ProjectToolChain, StructureConfig,
-        ToolChoice,
+        BuildProfile, FontsConfig, Project, ProjectConfig, ProjectHandle, ProjectInfo,
+        ProjectToolChain, StructureConfig, ToolChoice, ToolchainMirrors,
     };
+    use crate::models::OutputFormat;
     use std::path::PathBuf;
     use typstlab_base::get_latest_typst;
     use typstlab_proto::{Entity, Loaded};
@@ -217,12 +446,15 @@ mod tests {
                     typst: "0.14.2".to_string(),
                     typst_docs: ToolChoice::Auto,
                     typstyle: ToolChoice::None,
+                    mirrors: ToolchainMirrors::default(),
                 },
                 structure: StructureConfig {
                     papers_dir: PathBuf::from("content").join("papers"),
                     dist_dir: PathBuf::from("out").join("dist"),
                     templates_dir: PathBuf::from("assets").join("templates"),
+                    shared_dir: Some(PathBuf::from("lib")),
                 },
+                ..Default::default()
             },
         }
     }
@@ -253,6 +485,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_shared_dir_makes_project_root_the_compile_root() {
+        let mut project = loaded_project("/project-root");
+
+        assert_eq!(
+            project.shared_dir(),
+            Some(PathBuf::from("/project-root").join("lib"))
+        );
+        assert_eq!(project.compile_root(), Some(PathBuf::from("/project-root")));
+        assert!(
+            project
+                .shared_source_dirs()
+                .contains(&PathBuf::from("/project-root").join("lib"))
+        );
+
+        project.config.structure.shared_dir = None;
+        assert_eq!(project.shared_dir(), None);
+        assert_eq!(project.compile_root(), None);
+    }
+
     #[test]
     fn test_config_deserializes_structure_paths_as_pathbuf() {
         let config: ProjectConfig = toml::from_str(
@@ -305,6 +557,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_config_deserializes_toolchain_mirrors() {
+        let config: ProjectConfig = toml::from_str(
+            r#"
+                [project]
+                name = "demo"
+                init_date = "2026-04-23"
+
+                [toolchain]
+                typst = "0.14.2"
+
+                [toolchain.mirrors]
+                typst = "https://artifacts.example.com/typst/v{version}/{asset}"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.toolchain.mirrors.typst.as_deref(),
+            Some("https://artifacts.example.com/typst/v{version}/{asset}")
+        );
+        assert_eq!(config.toolchain.mirrors.docs, None);
+
+        let error = toml::from_str::<ProjectConfig>(
+            "[project]\nname = \"demo\"\n\n[toolchain.mirrors]\ndocs = \"https://artifacts.example.com/docs.json\"\n",
+        )
+        .unwrap_err();
+        assert!(
+            error.to_string().contains("must contain {asset}"),
+            "{}",
+            error
+        );
+    }
+
     #[test]
     fn test_project_config_defaults_toolchain() {
         let config = ProjectConfig::default();
@@ -313,4 +599,88 @@ mod tests {
         assert!(matches!(config.toolchain.typst_docs, ToolChoice::Auto));
         assert!(matches!(config.toolchain.typstyle, ToolChoice::None));
     }
+
+    #[test]
+    fn test_config_deserializes_named_profiles() {
+        let config: ProjectConfig = toml::from_str(
+            r#"
+                [project]
+                name = "demo"
+
+                [profiles.anonymous]
+                output_suffix = "-anon"
+                dist_subdir = "submission"
+
+                [profiles.anonymous.inputs]
+                anonymous = "true"
+
+                [profiles.camera-ready]
+                formats = ["pdf", "html"]
+            "#,
+        )
+        .unwrap();
+        let project = Loaded {
+            actual: Project::new(PathBuf::from("/project-root")),
+            config,
+        };
+
+        let anonymous = project.profile("anonymous").unwrap();
+        assert_eq!(anonymous.output_suffix.as_deref(), Some("-anon"));
+        assert_eq!(anonymous.dist_subdir, Some(PathBuf::from("submission")));
+        assert_eq!(anonymous.inputs["anonymous"], "true");
+        assert_eq!(
+            project.profile("camera-ready"),
+            Some(&BuildProfile {
+                formats: Some(vec![OutputFormat::Pdf, OutputFormat::Html]),
+                ..Default::default()
+            })
+        );
+        assert!(project.profile("missing").is_none());
+    }
+
+    #[test]
+    fn test_config_rejects_tolerance_outside_unit_range() {
+        for tolerance in ["1.5", "-0.1", "nan", "inf"] {
+            let error = toml::from_str::<ProjectConfig>(&format!(
+                "[project]\nname = \"demo\"\n\n[test]\ntolerance = {}\n",
+                tolerance
+            ))
+            .unwrap_err();
+            assert!(
+                error.to_string().contains("between 0.0 and 1.0"),
+                "{}",
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn test_config_rejects_dist_subdir_outside_dist() {
+        for subdir in ["../outside", "/tmp/outside", "a/../../b"] {
+            let result = toml::from_str::<ProjectConfig>(&format!(
+                "[project]\nname = \"demo\"\n\n[profiles.bad]\ndist_subdir = \"{}\"\n",
+                subdir
+            ));
+
+            assert!(result.is_err(), "{subdir}");
+        }
+    }
+
+    #[test]
+    fn test_fonts_default_to_project_fonts_dir_and_are_shared_inputs() {
+        let config: ProjectConfig = toml::from_str("[project]\nname = \"demo\"\n").unwrap();
+        assert_eq!(config.fonts, FontsConfig::default());
+        assert!(!config.fonts.ignore_system_fonts);
+
+        let project = loaded_project("/project-root");
+        assert_eq!(
+            project.font_dirs(),
+            vec![PathBuf::from("/project-root").join("fonts")]
+        );
+        assert!(
+            project
+                .shared_source_dirs()
+                .contains(&PathBuf::from("/project-root").join("fonts"))
+        );
+    }
 }
@@ -2,6 +2,7 @@ use crate::actions::resolve_typst::StoreError;
 use crate::models::Docs;
 use std::path::PathBuf;
 use tempfile::TempDir;
+use typstlab_base::lock::FileLock;
 use typstlab_base::persistence::Persistence;
 use typstlab_proto::{Collection, Store};
 
@@ -15,13 +16,29 @@ impl DocsStore {
         Self { root }
     }
 
-    fn staging_root(&self) -> PathBuf {
+    /// 中断されたインストール等の作業場所が置かれるディレクトリ
+    pub fn staging_root(&self) -> PathBuf {
         self.root.join(".tmp")
     }
 
     pub fn docs_path(&self, version: &str) -> PathBuf {
         self.root.join(version)
     }
+
+    /// 同じバージョンのインストールをプロセス間で直列化するロックファイル
+    pub fn lock_path(&self, version: &str) -> PathBuf {
+        self.root.join(".locks").join(format!("{}.lock", version))
+    }
+
+    /// ロックを取得できるまで待つ
+    pub fn lock(&self, version: &str) -> Result<FileLock, StoreError> {
+        Ok(FileLock::acquire(self.lock_path(version))?)
+    }
+
+    /// 他のプロセスが docs をインストール中なら待たずに `None` を返す
+    pub fn try_lock(&self, version: &str) -> Result<Option<FileLock>, StoreError> {
+        Ok(FileLock::try_acquire(self.lock_path(version))?)
+    }
 }
 
 typstlab_proto::impl_entity! {
@@ -2,6 +2,7 @@ use crate::actions::resolve_typst::StoreError;
 use crate::models::Typst;
 use std::path::PathBuf;
 use tempfile::TempDir;
+use typstlab_base::lock::FileLock;
 use typstlab_base::persistence::Persistence;
 use typstlab_proto::{Collection, Store, TYPST_BINARY_NAME};
 
@@ -15,7 +16,8 @@ impl TypstStore {
         Self { root }
     }
 
-    fn staging_root(&self) -> PathBuf {
+    /// 中断されたインストール等の作業場所が置かれるディレクトリ
+    pub fn staging_root(&self) -> PathBuf {
         self.root.join(".tmp")
     }
 
@@ -23,6 +25,21 @@ impl TypstStore {
         self.root.join(version)
     }
 
+    /// 同じバージョンのインストールをプロセス間で直列化するロックファイル
+    pub fn lock_path(&self, version: &str) -> PathBuf {
+        self.root.join(".locks").join(format!("{}.lock", version))
+    }
+
+    /// ロックを取得できるまで待つ
+    pub fn lock(&self, version: &str) -> Result<FileLock, StoreError> {
+        Ok(FileLock::acquire(self.lock_path(version))?)
+    }
+
+    /// 他のプロセスが Typst をインストール中なら待たずに `None` を返す
+    pub fn try_lock(&self, version: &str) -> Result<Option<FileLock>, StoreError> {
+        Ok(FileLock::try_acquire(self.lock_path(version))?)
+    }
+
     pub fn binary_path(&self, version: &str) -> PathBuf {
         self.typst_path(version).join(TYPST_BINARY_NAME)
     }
@@ -0,0 +1,107 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tempfile::Builder;
+use thiserror::Error;
+use zip::ZipWriter;
+use zip::write::SimpleFileOptions;
+
+#[derive(Debug, Error)]
+pub enum ArchiveError {
+    #[error("failed to read '{path}': {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to write archive: {0}")]
+    Write(#[from] std::io::Error),
+    #[error("failed to write archive: {0}")]
+    Zip(#[from] zip::result::ZipError),
+    #[error("failed to move archive into place: {0}")]
+    Persist(#[from] tempfile::PersistError),
+}
+
+/// アーカイブに入れる 1 ファイル分の中身
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArchiveSource {
+    File(PathBuf),
+    Bytes(Vec<u8>),
+}
+
+/// zip アーカイブをアトミックに書き出す。`entries` の名前は `/` 区切りの相対パス。
+/// 書き出したアーカイブのサイズを返す。
+pub fn write_zip(dest: &Path, entries: &[(String, ArchiveSource)]) -> Result<u64, ArchiveError> {
+    let parent = match dest.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    std::fs::create_dir_all(parent)?;
+
+    let tmp = Builder::new()
+        .prefix(".typstlab-tmp-")
+        .tempfile_in(parent)?;
+    let mut zip = ZipWriter::new(tmp.reopen()?);
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for (name, source) in entries {
+        zip.start_file(name.as_str(), options)?;
+        match source {
+            ArchiveSource::File(path) => {
+                let mut file = std::fs::File::open(path).map_err(|source| ArchiveError::Read {
+                    path: path.clone(),
+                    source,
+                })?;
+                std::io::copy(&mut file, &mut zip).map_err(|source| ArchiveError::Read {
+                    path: path.clone(),
+                    source,
+                })?;
+            }
+            ArchiveSource::Bytes(bytes) => zip.write_all(bytes)?,
+        }
+    }
+    let file = zip.finish()?;
+    file.sync_all()?;
+    let size = file.metadata()?.len();
+
+    tmp.persist(dest)?;
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArchiveSource, write_zip};
+    use std::io::Read;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_write_zip_stores_files_and_bytes() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("main.typ");
+        std::fs::write(&source, "= Hello").unwrap();
+        let dest = temp.path().join("out").join("bundle.zip");
+
+        let size = write_zip(
+            &dest,
+            &[
+                (
+                    "papers/p01/main.typ".to_string(),
+                    ArchiveSource::File(source),
+                ),
+                (
+                    "README.md".to_string(),
+                    ArchiveSource::Bytes(b"readme".to_vec()),
+                ),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(size, std::fs::metadata(&dest).unwrap().len());
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&dest).unwrap()).unwrap();
+        let mut content = String::new();
+        archive
+            .by_name("papers/p01/main.typ")
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "= Hello");
+        assert!(archive.by_name("README.md").is_ok());
+    }
+}
@@ -0,0 +1,170 @@
+use crate::source_refs::string_literals;
+use std::path::Path;
+
+/// ソース中の `bibliography(...)` に渡されている文献ファイルのパスを列挙する。
+/// `style:` の CSL など、BibTeX / Hayagriva 以外の拡張子のリテラルは含めない。
+pub fn bibliography_paths(source: &str) -> Vec<String> {
+    const CALL: &str = "bibliography(";
+    let mut paths = Vec::new();
+    let mut search_from = 0;
+
+    while let Some(offset) = source[search_from..].find(CALL) {
+        let start = search_from + offset;
+        let args_start = start + CALL.len();
+        search_from = args_start;
+
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        if source[line_start..start].contains("//") {
+            continue;
+        }
+        let args = &source[args_start..];
+        let end = matching_paren(args).unwrap_or(args.len());
+        for literal in string_literals(&args[..end]) {
+            if is_bibliography_file(Path::new(&literal)) && !paths.contains(&literal) {
+                paths.push(literal);
+            }
+        }
+    }
+
+    paths
+}
+
+/// 開き括弧の直後から見て、対応する閉じ括弧の位置
+fn matching_paren(text: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (index, c) in text.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(index),
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn is_bibliography_file(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("bib" | "yml" | "yaml")
+    )
+}
+
+/// 文献ファイルに定義されているエントリのキー。拡張子で BibTeX / Hayagriva YAML を判別する。
+pub fn bibliography_keys(path: &Path, content: &str) -> Vec<String> {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("bib") => bibtex_keys(content),
+        Some("yml" | "yaml") => hayagriva_keys(content),
+        _ => Vec::new(),
+    }
+}
+
+/// `@type{key, ...}` のキー。`@string` などエントリでないものは除く。
+fn bibtex_keys(content: &str) -> Vec<String> {
+    let mut keys = Vec::new();
+
+    for (index, _) in content.match_indices('@') {
+        let rest = &content[index + 1..];
+        let kind_len = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        let kind = &rest[..kind_len];
+        if kind.is_empty()
+            || ["comment", "string", "preamble"]
+                .iter()
+                .any(|skip| kind.eq_ignore_ascii_case(skip))
+        {
+            continue;
+        }
+        let Some(body) = rest[kind_len..].trim_start().strip_prefix(['{', '(']) else {
+            continue;
+        };
+        let Some(end) = body.find(',') else {
+            continue;
+        };
+        let key = body[..end].trim();
+        if !key.is_empty() && !key.contains(char::is_whitespace) {
+            keys.push(key.to_string());
+        }
+    }
+
+    keys
+}
+
+/// Hayagriva 形式ではトップレベルのマッピングのキーがエントリのキー
+fn hayagriva_keys(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter(|line| !line.starts_with([' ', '\t', '#', '-']))
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, _)| key.trim().trim_matches(['"', '\'']).to_string())
+        .filter(|key| !key.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bibliography_keys, bibliography_paths};
+    use std::path::Path;
+
+    #[test]
+    fn test_bibliography_paths_reads_call_arguments_only() {
+        let source = r#"
+#let data = yaml("data.yml")
+// #bibliography("old.bib")
+#bibliography(("refs.bib", "extra.yml"), style: "custom.csl")
+#bibliography("refs.bib")
+"#;
+
+        assert_eq!(bibliography_paths(source), vec!["refs.bib", "extra.yml"]);
+    }
+
+    #[test]
+    fn test_bibtex_keys_skip_non_entries_and_emails() {
+        let content = r#"
+@string{ acm = "ACM" }
+@Article{knuth1984,
+  author = {Donald Knuth},
+  note = {mail: knuth@example.com},
+}
+@book( lamport94 , title = {LaTeX})
+@comment{ignored, entry}
+"#;
+
+        assert_eq!(
+            bibliography_keys(Path::new("refs.bib"), content),
+            vec!["knuth1984", "lamport94"]
+        );
+    }
+
+    #[test]
+    fn test_hayagriva_keys_are_top_level_mappings() {
+        let content = "\
+# comment
+harry:
+  type: Book
+  title: Harry Potter
+\"quoted-key\":
+  type: Article
+";
+
+        assert_eq!(
+            bibliography_keys(Path::new("refs.yml"), content),
+            vec!["harry", "quoted-key"]
+        );
+        assert!(bibliography_keys(Path::new("refs.txt"), content).is_empty());
+    }
+}
@@ -0,0 +1,326 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// コンパイラ診断の重大度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticSeverity {
+    Error,
+    Warning,
+}
+
+/// Typst が出力した 1 件の診断（エラー・警告）
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BuildDiagnostic {
+    pub severity: DiagnosticSeverity,
+    pub file: Option<PathBuf>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+    /// `hint:` 注記や、エラーに至った呼び出し経路 (`help:`)
+    pub hints: Vec<String>,
+}
+
+impl BuildDiagnostic {
+    /// `file:line:column` 形式の位置表記（位置情報がなければ None）
+    pub fn location(&self) -> Option<String> {
+        let file = self.file.as_ref()?.display();
+        Some(match (self.line, self.column) {
+            (Some(line), Some(column)) => format!("{}:{}:{}", file, line, column),
+            (Some(line), None) => format!("{}:{}", file, line),
+            _ => file.to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HeaderKind {
+    Diagnostic(DiagnosticSeverity),
+    Help,
+}
+
+struct Location {
+    file: PathBuf,
+    line: usize,
+    column: usize,
+}
+
+/// Typst の stderr から診断を取り出す。
+/// 既定の human 形式と `--diagnostic-format short` の 1 行形式の両方を受け付ける
+/// （`= hint:` 注記は human 形式にしか出ない）。
+/// `help:` で始まるトレース診断は直前の診断のヒントとして畳み込む。
+pub fn parse_diagnostics(stderr: &str) -> Vec<BuildDiagnostic> {
+    let mut diagnostics: Vec<BuildDiagnostic> = Vec::new();
+    // 直前のヘッダが help だった場合、その位置行を help 側に結び付けるための状態
+    let mut in_help = false;
+
+    for raw_line in stderr.lines() {
+        let line = raw_line.trim_end();
+
+        if let Some((kind, location, message)) = parse_header(line) {
+            match kind {
+                HeaderKind::Diagnostic(severity) => {
+                    in_help = false;
+                    diagnostics.push(BuildDiagnostic {
+                        severity,
+                        file: location.as_ref().map(|l| l.file.clone()),
+                        line: location.as_ref().map(|l| l.line),
+                        column: location.as_ref().map(|l| l.column),
+                        message,
+                        hints: Vec::new(),
+                    });
+                }
+                HeaderKind::Help => {
+                    in_help = true;
+                    if let Some(last) = diagnostics.last_mut() {
+                        last.hints.push(with_location(message, location.as_ref()));
+                    }
+                }
+            }
+            continue;
+        }
+
+        let trimmed = line.trim_start();
+        if let Some(rest) = trimmed
+            .strip_prefix("┌─")
+            .or_else(|| trimmed.strip_prefix(",-"))
+        {
+            let Some(location) = parse_location(rest.trim()) else {
+                continue;
+            };
+            let Some(last) = diagnostics.last_mut() else {
+                continue;
+            };
+            if in_help {
+                if let Some(hint) = last.hints.pop() {
+                    last.hints.push(with_location(hint, Some(&location)));
+                }
+            } else if last.file.is_none() {
+                last.file = Some(location.file);
+                last.line = Some(location.line);
+                last.column = Some(location.column);
+            }
+        } else if let Some(note) = trimmed.strip_prefix("= ")
+            && let Some(last) = diagnostics.last_mut()
+        {
+            let note = note.strip_prefix("hint: ").unwrap_or(note);
+            last.hints.push(note.to_string());
+        }
+    }
+
+    diagnostics
+}
+
+fn parse_header(line: &str) -> Option<(HeaderKind, Option<Location>, String)> {
+    const KINDS: [(&str, HeaderKind); 3] = [
+        ("error", HeaderKind::Diagnostic(DiagnosticSeverity::Error)),
+        (
+            "warning",
+            HeaderKind::Diagnostic(DiagnosticSeverity::Warning),
+        ),
+        ("help", HeaderKind::Help),
+    ];
+
+    for (label, kind) in KINDS {
+        // 位置なし: `error: message`
+        if let Some(rest) = line.strip_prefix(label)
+            && let Some(message) = strip_code_and_colon(rest)
+        {
+            return Some((kind, None, message.to_string()));
+        }
+
+        // 位置あり: `file:line:col: error: message`
+        let marker = format!(": {}", label);
+        let mut search_from = 0;
+        while let Some(offset) = line[search_from..].find(&marker) {
+            let at = search_from + offset;
+            if let Some(location) = parse_location(&line[..at])
+                && let Some(message) = strip_code_and_colon(&line[at + marker.len()..])
+            {
+                return Some((kind, Some(location), message.to_string()));
+            }
+            search_from = at + marker.len();
+        }
+    }
+    None
+}
+
+/// `[E0001]: message` や `: message` の先頭を剥がして本文を返す
+fn strip_code_and_colon(rest: &str) -> Option<&str> {
+    let rest = if rest.starts_with('[') {
+        &rest[rest.find(']')? + 1..]
+    } else {
+        rest
+    };
+    rest.strip_prefix(": ")
+}
+
+fn parse_location(text: &str) -> Option<Location> {
+    let mut parts = text.rsplitn(3, ':');
+    let column = parts.next()?.trim().parse().ok()?;
+    let line = parts.next()?.trim().parse().ok()?;
+    let file = parts.next()?.trim();
+    if file.is_empty() {
+        return None;
+    }
+    Some(Location {
+        file: PathBuf::from(file),
+        line,
+        column,
+    })
+}
+
+fn with_location(message: String, location: Option<&Location>) -> String {
+    match location {
+        Some(l) => format!("{} ({}:{}:{})", message, l.file.display(), l.line, l.column),
+        None => message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_short_format() {
+        let stderr = "\
+main.typ:3:2: error: unknown variable: foo
+chapters/intro.typ:10:1: warning: unknown font family: nope
+";
+        let diagnostics = parse_diagnostics(stderr);
+
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].severity, DiagnosticSeverity::Error);
+        assert_eq!(diagnostics[0].file, Some(PathBuf::from("main.typ")));
+        assert_eq!(diagnostics[0].line, Some(3));
+        assert_eq!(diagnostics[0].column, Some(2));
+        assert_eq!(diagnostics[0].message, "unknown variable: foo");
+        assert_eq!(diagnostics[1].severity, DiagnosticSeverity::Warning);
+        assert_eq!(
+            diagnostics[1].location().as_deref(),
+            Some("chapters/intro.typ:10:1")
+        );
+    }
+
+    #[test]
+    fn test_parse_human_format_with_hints() {
+        let stderr = "\
+error: unknown variable: a-b
+  ┌─ main.typ:1:2
+  │
+1 │ #a-b
+  │  ^^^
+  │
+  = hint: use spaces around the minus sign
+  = hint: or use `a - b`
+
+warning: no text within stars
+  ┌─ main.typ:2:1
+  │
+2 │ **
+  │ ^^
+";
+        let diagnostics = parse_diagnostics(stderr);
+
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].location().as_deref(), Some("main.typ:1:2"));
+        assert_eq!(
+            diagnostics[0].hints,
+            vec!["use spaces around the minus sign", "or use `a - b`"]
+        );
+        assert_eq!(diagnostics[1].severity, DiagnosticSeverity::Warning);
+        assert_eq!(diagnostics[1].line, Some(2));
+    }
+
+    #[test]
+    fn test_preferred_format_keeps_hints() {
+        use crate::driver::DiagnosticFormat;
+
+        let version = semver::Version::new(0, 14, 2);
+        assert_eq!(
+            DiagnosticFormat::preferred_for(&version),
+            Some(DiagnosticFormat::Human)
+        );
+
+        let stderr = "\
+error: unknown variable: a-b
+  ┌─ papers/p01/main.typ:3:2
+  │
+3 │ #a-b
+  │  ^^^
+  │
+  = hint: if you meant to use subtraction, try adding spaces around the minus sign: `a - b`
+
+";
+        let diagnostics = parse_diagnostics(stderr);
+        assert_eq!(
+            diagnostics[0].location().as_deref(),
+            Some("papers/p01/main.typ:3:2")
+        );
+        assert_eq!(
+            diagnostics[0].hints,
+            vec![
+                "if you meant to use subtraction, try adding spaces around the minus sign: `a - b`"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_help_trace_is_folded_into_previous_diagnostic() {
+        let short = "\
+lib.typ:5:3: error: expected integer, found string
+main.typ:2:1: help: error occurred in this call of function `f`
+";
+        let diagnostics = parse_diagnostics(short);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].hints,
+            vec!["error occurred in this call of function `f` (main.typ:2:1)"]
+        );
+
+        let human = "\
+error: expected integer, found string
+  ┌─ lib.typ:5:3
+
+help: error occurred in this call of function `f`
+  ┌─ main.typ:2:1
+";
+        let diagnostics = parse_diagnostics(human);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].location().as_deref(), Some("lib.typ:5:3"));
+        assert_eq!(
+            diagnostics[0].hints,
+            vec!["error occurred in this call of function `f` (main.typ:2:1)"]
+        );
+    }
+
+    #[test]
+    fn test_unlocated_diagnostic_and_noise() {
+        let stderr = "\
+error: file not found (searched at /tmp/missing.typ)
+some unrelated output
+";
+        let diagnostics = parse_diagnostics(stderr);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].file, None);
+        assert_eq!(diagnostics[0].location(), None);
+        assert_eq!(
+            diagnostics[0].message,
+            "file not found (searched at /tmp/missing.typ)"
+        );
+    }
+
+    #[test]
+    fn test_windows_style_path_keeps_drive_letter() {
+        let diagnostics = parse_diagnostics("C:\\work\\main.typ:4:7: error: oops\n");
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].file,
+            Some(PathBuf::from("C:\\work\\main.typ"))
+        );
+        assert_eq!(diagnostics[0].line, Some(4));
+        assert_eq!(diagnostics[0].column, Some(7));
+    }
+}
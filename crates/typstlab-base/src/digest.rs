@@ -0,0 +1,219 @@
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// バイト列の SHA-256 を 16 進文字列で返す。
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    to_hex(&Sha256::digest(bytes))
+}
+
+/// ファイル内容の SHA-256 を 16 進文字列で返す。
+pub fn sha256_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(to_hex(&hasher.finalize()))
+}
+
+/// 複数の入力（文字列やディレクトリツリー）をまとめて 1 つの指紋にする。
+/// ツリーは相対パス順に走査するため、走査順に依存しない値になる。
+#[derive(Default)]
+pub struct ContentDigest {
+    hasher: Sha256,
+}
+
+impl ContentDigest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update_str(&mut self, value: &str) {
+        self.update_field(value.as_bytes());
+    }
+
+    /// ファイルまたはディレクトリ以下の全ファイルを取り込む。存在しないパスは無視する。
+    pub fn update_path(&mut self, path: &Path) -> std::io::Result<()> {
+        self.update_path_excluding(path, &[])
+    }
+
+    /// [`update_path`](Self::update_path) と同じだが、`excluded` 以下は取り込まない
+    pub fn update_path_excluding(
+        &mut self,
+        path: &Path,
+        excluded: &[PathBuf],
+    ) -> std::io::Result<()> {
+        if path.is_file() {
+            self.update_field(b"file");
+            self.update_field(&std::fs::read(path)?);
+        } else if path.is_dir() {
+            self.update_tree(path, path, excluded)?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> String {
+        to_hex(&self.hasher.finalize())
+    }
+
+    fn update_tree(
+        &mut self,
+        root: &Path,
+        dir: &Path,
+        excluded: &[PathBuf],
+    ) -> std::io::Result<()> {
+        let mut entries = std::fs::read_dir(dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        entries.sort();
+
+        for path in entries {
+            if excluded.contains(&path) {
+                continue;
+            }
+            if path.is_dir() {
+                self.update_tree(root, &path, excluded)?;
+            } else if path.is_file() {
+                let relative = path.strip_prefix(root).unwrap_or(&path);
+                self.update_field(relative.to_string_lossy().as_bytes());
+                self.update_field(&std::fs::read(&path)?);
+            }
+        }
+        Ok(())
+    }
+
+    /// 長さを前置して区切りの曖昧さをなくす
+    fn update_field(&mut self, bytes: &[u8]) {
+        self.hasher.update((bytes.len() as u64).to_le_bytes());
+        self.hasher.update(bytes);
+    }
+}
+
+/// 読み進めた分の SHA-256 を計算しながら中身をそのまま渡すリーダー
+pub struct Sha256Reader<R: Read> {
+    inner: R,
+    hasher: Sha256,
+}
+
+impl<R: Read> Sha256Reader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// 残りを読み捨ててから、全体の SHA-256 を 16 進文字列で返す。
+    pub fn finish(mut self) -> std::io::Result<String> {
+        std::io::copy(&mut self, &mut std::io::sink())?;
+        Ok(to_hex(&self.hasher.finalize()))
+    }
+}
+
+impl<R: Read> Read for Sha256Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_sha256_hex_known_value() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_sha256_reader_hashes_unread_tail_on_finish() {
+        let mut reader = Sha256Reader::new(&b"abc"[..]);
+        let mut first = [0u8; 1];
+        reader.read_exact(&mut first).unwrap();
+
+        assert_eq!(&first, b"a");
+        assert_eq!(reader.finish().unwrap(), sha256_hex(b"abc"));
+    }
+
+    #[test]
+    fn test_sha256_file_matches_bytes() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("a.txt");
+        std::fs::write(&path, b"abc").unwrap();
+
+        assert_eq!(sha256_file(&path).unwrap(), sha256_hex(b"abc"));
+    }
+
+    #[test]
+    fn test_tree_digest_tracks_content_and_names() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("paper");
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::write(root.join("main.typ"), "= A").unwrap();
+        std::fs::write(root.join("sub").join("b.typ"), "b").unwrap();
+
+        let digest_of = |root: &Path| {
+            let mut digest = ContentDigest::new();
+            digest.update_path(root).unwrap();
+            digest.finish()
+        };
+
+        let first = digest_of(&root);
+        assert_eq!(first, digest_of(&root));
+
+        std::fs::write(root.join("sub").join("b.typ"), "changed").unwrap();
+        let second = digest_of(&root);
+        assert_ne!(first, second);
+
+        std::fs::rename(
+            root.join("sub").join("b.typ"),
+            root.join("sub").join("c.typ"),
+        )
+        .unwrap();
+        assert_ne!(second, digest_of(&root));
+    }
+
+    #[test]
+    fn test_tree_digest_skips_excluded_paths() {
+        let temp = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp.path().join("dist")).unwrap();
+        std::fs::write(temp.path().join("main.typ"), "= A").unwrap();
+        let excluded = vec![temp.path().join("dist")];
+        let digest_of = |root: &Path| {
+            let mut digest = ContentDigest::new();
+            digest.update_path_excluding(root, &excluded).unwrap();
+            digest.finish()
+        };
+
+        let first = digest_of(temp.path());
+        std::fs::write(temp.path().join("dist").join("main.pdf"), "pdf").unwrap();
+        assert_eq!(first, digest_of(temp.path()));
+        std::fs::write(temp.path().join("data.csv"), "1,2").unwrap();
+        assert_ne!(first, digest_of(temp.path()));
+    }
+
+    #[test]
+    fn test_missing_path_is_ignored() {
+        let temp = TempDir::new().unwrap();
+        let mut digest = ContentDigest::new();
+        digest.update_path(&temp.path().join("missing")).unwrap();
+
+        assert_eq!(digest.finish(), ContentDigest::new().finish());
+    }
+}
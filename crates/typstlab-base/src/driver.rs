@@ -1,23 +1,192 @@
 use anyhow::{Result, anyhow};
 use semver::{Version, VersionReq};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::process::Command;
 
+/// Typst の診断出力形式 (`--diagnostic-format`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticFormat {
+    /// 位置を `┌─` 行で示し、`= hint:` 注記も出す形式
+    Human,
+    /// `file:line:col: severity: message` の 1 行形式（`= hint:` 注記は出ない）
+    Short,
+}
+
+impl DiagnosticFormat {
+    /// `--diagnostic-format` を渡せるバージョン条件
+    pub fn required_version() -> Result<VersionReq> {
+        VersionReq::parse(">=0.12.0")
+            .map_err(|error| anyhow!("invalid diagnostic format version requirement: {}", error))
+    }
+
+    /// 診断を読み取るときに指定する形式（非対応なら None で Typst の既定に任せる）。
+    /// ヒントを失わないよう、[`parse_diagnostics`](crate::diagnostics::parse_diagnostics) が
+    /// 位置もヒントも読める human 形式を明示する
+    pub fn preferred_for(version: &Version) -> Option<Self> {
+        Self::required_version()
+            .is_ok_and(|requirement| requirement.matches(version))
+            .then_some(DiagnosticFormat::Human)
+    }
+
+    fn as_arg(self) -> &'static str {
+        match self {
+            DiagnosticFormat::Human => "human",
+            DiagnosticFormat::Short => "short",
+        }
+    }
+}
+
+/// `typst query` の出力形式 (`--format`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QueryFormat {
+    #[default]
+    Json,
+    Yaml,
+}
+
+impl QueryFormat {
+    fn as_arg(self) -> &'static str {
+        match self {
+            QueryFormat::Json => "json",
+            QueryFormat::Yaml => "yaml",
+        }
+    }
+}
+
+/// `typst compile` に渡す追加オプション（論文ごとの設定や CLI 指定から組み立てる）
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CompileOptions {
+    /// プロジェクトのルート (`--root`)。None なら入力ファイルのディレクトリ
+    pub root: Option<PathBuf>,
+    /// PNG 出力の解像度 (`--ppi`)
+    pub ppi: Option<f32>,
+    /// 出力するページ範囲 (`--pages`、例: `1-3,5`)
+    pub pages: Option<String>,
+    /// `sys.inputs` に渡すキーと値 (`--input key=value`)
+    pub inputs: BTreeMap<String, String>,
+    /// 追加のフォント検索パス (`--font-path`)
+    pub font_paths: Vec<PathBuf>,
+    /// システムフォントを使わない (`--ignore-system-fonts`)
+    pub ignore_system_fonts: bool,
+    /// PDF 規格 (`--pdf-standard`、例: `a-2b`)
+    pub pdf_standard: Option<String>,
+    /// ローカルパッケージの置き場 (`--package-path`)
+    pub package_path: Option<PathBuf>,
+    /// タイミングトレースの出力先 (`--timings`)
+    pub timings: Option<PathBuf>,
+}
+
+impl CompileOptions {
+    /// これらのオプションを渡せるバージョン条件
+    pub fn required_version(&self) -> Result<VersionReq> {
+        let requirement = if self.pages.is_some() || self.pdf_standard.is_some() {
+            ">=0.12.0"
+        } else if self.timings.is_some() {
+            ">=0.11.0"
+        } else {
+            self.world_required_version().unwrap_or(">=0.1.0")
+        };
+        VersionReq::parse(requirement)
+            .map_err(|error| anyhow!("invalid compile option version requirement: {}", error))
+    }
+
+    /// `typst query` でも使えるオプションだけのバージョン条件
+    fn world_required_version(&self) -> Option<&'static str> {
+        if self.ignore_system_fonts || self.package_path.is_some() {
+            Some(">=0.11.0")
+        } else if !self.inputs.is_empty() {
+            Some(">=0.7.0")
+        } else {
+            None
+        }
+    }
+
+    /// ソースの読み込み環境に関わる引数（ルート、パッケージ、`sys.inputs`、フォント）
+    fn push_world_args(&self, args: &mut Vec<String>) {
+        if let Some(root) = &self.root {
+            args.push("--root".to_string());
+            args.push(root.to_string_lossy().to_string());
+        }
+        if let Some(package_path) = &self.package_path {
+            args.push("--package-path".to_string());
+            args.push(package_path.to_string_lossy().to_string());
+        }
+        for (key, value) in &self.inputs {
+            args.push("--input".to_string());
+            args.push(format!("{}={}", key, value));
+        }
+        push_font_args(args, &self.font_paths, self.ignore_system_fonts);
+    }
+
+    fn push_args(&self, args: &mut Vec<String>) {
+        self.push_world_args(args);
+        if let Some(ppi) = self.ppi {
+            args.push("--ppi".to_string());
+            args.push(ppi.to_string());
+        }
+        if let Some(pages) = &self.pages {
+            args.push("--pages".to_string());
+            args.push(pages.clone());
+        }
+        if let Some(standard) = &self.pdf_standard {
+            args.push("--pdf-standard".to_string());
+            args.push(standard.clone());
+        }
+        if let Some(timings) = &self.timings {
+            // 値が省略可能なフラグなので、出力パスと取り違えられないよう `=` でつなぐ
+            args.push(format!("--timings={}", timings.to_string_lossy()));
+        }
+    }
+
+    /// `--timings` を渡せるバージョン条件
+    pub fn timings_required_version() -> Result<VersionReq> {
+        VersionReq::parse(">=0.11.0")
+            .map_err(|error| anyhow!("invalid timings version requirement: {}", error))
+    }
+}
+
+fn push_font_args(args: &mut Vec<String>, font_paths: &[PathBuf], ignore_system_fonts: bool) {
+    for path in font_paths {
+        args.push("--font-path".to_string());
+        args.push(path.to_string_lossy().to_string());
+    }
+    if ignore_system_fonts {
+        args.push("--ignore-system-fonts".to_string());
+    }
+}
+
 /// Typst の主要なコマンドを型定義
+#[derive(Debug, Clone)]
 pub enum TypstCommand {
     Compile {
         source: PathBuf,
         output: Option<PathBuf>,
         features: Vec<String>,
+        /// None なら Typst の既定 (human) に任せる
+        diagnostic_format: Option<DiagnosticFormat>,
+        options: Box<CompileOptions>,
     },
     Query {
         source: PathBuf,
         selector: String,
+        /// 要素全体ではなくこのフィールドだけを取り出す (`--field`)
+        field: Option<String>,
+        /// ちょうど 1 件であることを要求し、配列ではなく単体で出力する (`--one`)
+        one: bool,
+        format: QueryFormat,
+        /// ルート・入力・フォントだけが使われ、出力に関わるオプションは無視される
+        options: Box<CompileOptions>,
     },
     Init {
         template: String,
         output: Option<PathBuf>,
     },
+    /// Typst から見えるフォントファミリーの一覧
+    Fonts {
+        font_paths: Vec<PathBuf>,
+        ignore_system_fonts: bool,
+    },
     Update,
     Version,
     /// 生の引数を直接渡す実行（バージョンガード付き）
@@ -31,12 +200,24 @@ impl TypstCommand {
     /// そのコマンドを実行するために必要な最低限のバージョン条件
     pub fn required_version(&self) -> Result<VersionReq> {
         match self {
-            TypstCommand::Compile { .. } => VersionReq::parse(">=0.1.0")
-                .map_err(|error| anyhow!("invalid compile version requirement: {}", error)),
-            TypstCommand::Query { .. } => VersionReq::parse(">=0.5.0")
-                .map_err(|error| anyhow!("invalid query version requirement: {}", error)),
+            TypstCommand::Compile {
+                diagnostic_format: Some(_),
+                ..
+            } => DiagnosticFormat::required_version(),
+            TypstCommand::Compile { options, .. } => options.required_version(),
+            TypstCommand::Query { options, .. } => {
+                VersionReq::parse(options.world_required_version().unwrap_or(">=0.5.0"))
+                    .map_err(|error| anyhow!("invalid query version requirement: {}", error))
+            }
             TypstCommand::Init { .. } => VersionReq::parse(">=0.11.0")
                 .map_err(|error| anyhow!("invalid init version requirement: {}", error)),
+            TypstCommand::Fonts {
+                ignore_system_fonts: true,
+                ..
+            } => VersionReq::parse(">=0.11.0")
+                .map_err(|error| anyhow!("invalid fonts version requirement: {}", error)),
+            TypstCommand::Fonts { .. } => VersionReq::parse(">=0.1.0")
+                .map_err(|error| anyhow!("invalid fonts version requirement: {}", error)),
             TypstCommand::Update => VersionReq::parse(">=0.11.0")
                 .map_err(|error| anyhow!("invalid update version requirement: {}", error)),
             TypstCommand::Version => VersionReq::parse("*")
@@ -51,23 +232,46 @@ impl TypstCommand {
                 source,
                 output,
                 features,
+                diagnostic_format,
+                options,
             } => {
                 let mut args = vec!["compile".to_string(), source.to_string_lossy().to_string()];
                 if !features.is_empty() {
                     args.push("--features".to_string());
                     args.push(features.join(","));
                 }
+                options.push_args(&mut args);
+                if let Some(format) = diagnostic_format {
+                    args.push("--diagnostic-format".to_string());
+                    args.push(format.as_arg().to_string());
+                }
                 if let Some(out) = output {
                     args.push(out.to_string_lossy().to_string());
                 }
                 args
             }
-            TypstCommand::Query { source, selector } => {
-                vec![
-                    "query".to_string(),
-                    source.to_string_lossy().to_string(),
-                    selector.clone(),
-                ]
+            TypstCommand::Query {
+                source,
+                selector,
+                field,
+                one,
+                format,
+                options,
+            } => {
+                let mut args = vec!["query".to_string()];
+                options.push_world_args(&mut args);
+                if let Some(field) = field {
+                    args.push("--field".to_string());
+                    args.push(field.clone());
+                }
+                if *one {
+                    args.push("--one".to_string());
+                }
+                args.push("--format".to_string());
+                args.push(format.as_arg().to_string());
+                args.push(source.to_string_lossy().to_string());
+                args.push(selector.clone());
+                args
             }
             TypstCommand::Init { template, output } => {
                 let mut args = vec!["init".to_string(), template.clone()];
@@ -76,6 +280,14 @@ impl TypstCommand {
                 }
                 args
             }
+            TypstCommand::Fonts {
+                font_paths,
+                ignore_system_fonts,
+            } => {
+                let mut args = vec!["fonts".to_string()];
+                push_font_args(&mut args, font_paths, *ignore_system_fonts);
+                args
+            }
             TypstCommand::Update => vec!["update".to_string()],
             TypstCommand::Version => vec!["--version".to_string()],
             TypstCommand::Raw { args, .. } => args.clone(),
@@ -83,6 +295,7 @@ impl TypstCommand {
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct TypstDriver {
     pub binary_path: PathBuf,
 }
@@ -0,0 +1,105 @@
+use std::path::Path;
+use std::process::Command;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum GitError {
+    #[error("Failed to run git: {0}")]
+    Spawn(#[source] std::io::Error),
+    #[error("git {command} failed: {stderr}")]
+    Failed { command: String, stderr: String },
+    #[error("Failed to unpack git archive: {0}")]
+    Unpack(#[source] std::io::Error),
+}
+
+/// `dir` を含むリポジトリの `rev` 時点の内容のうち、`dir` 以下を `dest` に展開する。
+/// 作業ツリーやインデックスには触れない。
+pub fn export_git_tree(dir: &Path, rev: &str, dest: &Path) -> Result<(), GitError> {
+    // リポジトリのルートから見た `dir` の位置（末尾 `/` 付き、ルートなら空）
+    let prefix = git(dir, &["rev-parse", "--show-prefix"])?;
+    let prefix = String::from_utf8_lossy(&prefix).trim().to_string();
+    // サブディレクトリで実行すると archive がさらにそこへ絞り込むので、ルートから実行する
+    let toplevel = git(dir, &["rev-parse", "--show-toplevel"])?;
+    let toplevel = String::from_utf8_lossy(&toplevel).trim().to_string();
+    // `-` で始まる rev をオプションとして読ませないよう、先にツリーのオブジェクト名へ解決する
+    let tree = git(
+        dir,
+        &[
+            "rev-parse",
+            "--verify",
+            "--end-of-options",
+            &format!("{}:{}", rev, prefix),
+        ],
+    )?;
+    let tree = String::from_utf8_lossy(&tree).trim().to_string();
+    let tar = git(Path::new(&toplevel), &["archive", "--format=tar", &tree])?;
+
+    tar::Archive::new(tar.as_slice())
+        .unpack(dest)
+        .map_err(GitError::Unpack)
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<Vec<u8>, GitError> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(GitError::Spawn)?;
+    if !output.status.success() {
+        return Err(GitError::Failed {
+            command: args.join(" "),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn run(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?}", args);
+    }
+
+    #[test]
+    fn test_export_git_tree_extracts_subdirectory_at_revision() {
+        let temp = TempDir::new().unwrap();
+        let repo = temp.path().join("repo");
+        let project = repo.join("project");
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::write(project.join("main.typ"), "old").unwrap();
+        run(&repo, &["init", "-q"]);
+        run(&repo, &["add", "-A"]);
+        run(&repo, &["commit", "-q", "-m", "init"]);
+        std::fs::write(project.join("main.typ"), "new").unwrap();
+        let dest = temp.path().join("dest");
+
+        export_git_tree(&project, "HEAD", &dest).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(dest.join("main.typ")).unwrap(),
+            "old"
+        );
+        assert!(matches!(
+            export_git_tree(&project, "no-such-rev", &temp.path().join("other")),
+            Err(GitError::Failed { .. })
+        ));
+        let injected = temp.path().join("injected");
+        assert!(matches!(
+            export_git_tree(&project, &format!("--output={}", injected.display()), &dest),
+            Err(GitError::Failed { .. })
+        ));
+        assert!(!injected.exists());
+    }
+}
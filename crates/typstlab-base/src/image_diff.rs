@@ -0,0 +1,173 @@
+use std::path::Path;
+use thiserror::Error;
+
+/// 差分として塗る色
+const HIGHLIGHT: [u8; 4] = [0xE5, 0x39, 0x35, 0xFF];
+/// 変化していない画素を薄く残すときの元画像の割合 (0-255)
+const FADE: u16 = 64;
+
+#[derive(Error, Debug)]
+pub enum ImageDiffError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to decode PNG: {0}")]
+    Decode(#[from] png::DecodingError),
+    #[error("Failed to encode PNG: {0}")]
+    Encode(#[from] png::EncodingError),
+}
+
+/// 8bit RGBA の画像
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl RgbaImage {
+    fn pixel(&self, x: u32, y: u32) -> Option<&[u8]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let index = ((y * self.width + x) * 4) as usize;
+        Some(&self.pixels[index..index + 4])
+    }
+}
+
+/// 2 枚の画像を画素単位で比べた結果
+#[derive(Debug, Clone)]
+pub struct PixelDiff {
+    pub changed_pixels: u64,
+    pub total_pixels: u64,
+    /// 変化した画素を塗り、それ以外を薄くした画像
+    pub image: RgbaImage,
+}
+
+/// PNG を読み、色形式を RGBA 8bit に揃える
+pub fn read_png(path: &Path) -> Result<RgbaImage, ImageDiffError> {
+    let mut decoder = png::Decoder::new(std::io::BufReader::new(std::fs::File::open(path)?));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+    buffer.truncate(info.buffer_size());
+
+    let pixels = match info.color_type {
+        png::ColorType::Rgba => buffer,
+        png::ColorType::Rgb => buffer
+            .chunks_exact(3)
+            .flat_map(|c| [c[0], c[1], c[2], 0xFF])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => buffer
+            .chunks_exact(2)
+            .flat_map(|c| [c[0], c[0], c[0], c[1]])
+            .collect(),
+        // パレットは normalize_to_color8 で RGB(A) に展開済み
+        png::ColorType::Grayscale | png::ColorType::Indexed => {
+            buffer.iter().flat_map(|&v| [v, v, v, 0xFF]).collect()
+        }
+    };
+
+    Ok(RgbaImage {
+        width: info.width,
+        height: info.height,
+        pixels,
+    })
+}
+
+pub fn write_png(path: &Path, image: &RgbaImage) -> Result<(), ImageDiffError> {
+    let mut bytes = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut bytes, image.width, image.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&image.pixels)?;
+    }
+    std::fs::write(path, bytes)?;
+    Ok(())
+}
+
+/// `current` と `reference` を比べる。大きさが違う場合ははみ出した部分を変化とみなす。
+pub fn diff_images(current: &RgbaImage, reference: &RgbaImage) -> PixelDiff {
+    let width = current.width.max(reference.width);
+    let height = current.height.max(reference.height);
+    let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+    let mut changed_pixels = 0;
+
+    for y in 0..height {
+        for x in 0..width {
+            let a = current.pixel(x, y);
+            let b = reference.pixel(x, y);
+            if a == b {
+                pixels.extend(faded(a.unwrap_or(&[0xFF; 4])));
+            } else {
+                changed_pixels += 1;
+                pixels.extend(HIGHLIGHT);
+            }
+        }
+    }
+
+    PixelDiff {
+        changed_pixels,
+        total_pixels: u64::from(width) * u64::from(height),
+        image: RgbaImage {
+            width,
+            height,
+            pixels,
+        },
+    }
+}
+
+/// 白背景に合成してから白へ寄せる
+fn faded(pixel: &[u8]) -> [u8; 4] {
+    let alpha = u16::from(pixel[3]);
+    let mut out = [0xFF; 4];
+    for (channel, &value) in out.iter_mut().zip(&pixel[..3]) {
+        let composed = (u16::from(value) * alpha + 0xFF * (0xFF - alpha)) / 0xFF;
+        *channel = ((composed * FADE + 0xFF * (0xFF - FADE)) / 0xFF) as u8;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn solid(width: u32, height: u32, rgba: [u8; 4]) -> RgbaImage {
+        RgbaImage {
+            width,
+            height,
+            pixels: rgba.repeat((width * height) as usize),
+        }
+    }
+
+    #[test]
+    fn test_diff_counts_changed_and_out_of_bounds_pixels() {
+        let reference = solid(2, 2, [0, 0, 0, 0xFF]);
+        let mut current = solid(2, 3, [0, 0, 0, 0xFF]);
+        current.pixels[0..4].copy_from_slice(&[0xFF, 0xFF, 0xFF, 0xFF]);
+
+        let diff = diff_images(&current, &reference);
+
+        // 左上の 1 画素と、reference に無い最下行の 2 画素
+        assert_eq!(diff.changed_pixels, 3);
+        assert_eq!(diff.total_pixels, 6);
+        assert_eq!((diff.image.width, diff.image.height), (2, 3));
+        assert_eq!(&diff.image.pixels[0..4], &HIGHLIGHT);
+        assert_ne!(&diff.image.pixels[4..8], &HIGHLIGHT);
+        assert!(diff_images(&reference, &reference).changed_pixels == 0);
+    }
+
+    #[test]
+    fn test_png_round_trip() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("page.png");
+        let image = solid(3, 2, [10, 20, 30, 0xFF]);
+
+        write_png(&path, &image).unwrap();
+
+        assert_eq!(read_png(&path).unwrap(), image);
+    }
+}
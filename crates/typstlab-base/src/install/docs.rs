@@ -2,6 +2,7 @@ use std::io::{self, copy};
 use std::path::PathBuf;
 use thiserror::Error;
 
+use crate::digest::Sha256Reader;
 use crate::install::{InstallProvider, ProgressReader};
 use typstlab_proto::{Installer, SourceFormat};
 
@@ -34,15 +35,32 @@ pub enum DocsInstallError {
 
     #[error("General I/O error during docs install: {0}")]
     Io(#[from] io::Error),
+
+    #[error("Checksum mismatch for '{url}': expected sha256 {expected}, got {actual}")]
+    ChecksumMismatch {
+        url: String,
+        expected: String,
+        actual: String,
+    },
 }
 
 pub struct DocsInstaller<P: InstallProvider> {
     provider: P,
+    /// 指定があれば、書き出したファイルを返す前に突き合わせる
+    sha256: Option<String>,
 }
 
 impl<P: InstallProvider> DocsInstaller<P> {
     pub fn new(provider: P) -> Self {
-        Self { provider }
+        Self {
+            provider,
+            sha256: None,
+        }
+    }
+
+    pub fn with_sha256(mut self, sha256: Option<String>) -> Self {
+        self.sha256 = sha256;
+        self
     }
 }
 
@@ -98,7 +116,8 @@ impl<P: InstallProvider> DocsInstaller<P> {
             .fetch(url)
             .map_err(|e| DocsInstallError::SourceAccessFailed(Box::new(e)))?;
 
-        let mut progress_reader = ProgressReader::new(reader, total_size, on_progress);
+        let mut progress_reader =
+            ProgressReader::new(Sha256Reader::new(reader), total_size, on_progress);
         let mut raw_file = std::fs::File::create(&raw_path).map_err(|e| {
             DocsInstallError::RawFileCreationFailed {
                 path: raw_path.clone(),
@@ -113,6 +132,17 @@ impl<P: InstallProvider> DocsInstaller<P> {
             }
         })?;
 
+        if let Some(expected) = &self.sha256 {
+            let actual = progress_reader.into_inner().finish()?;
+            if actual != *expected {
+                return Err(DocsInstallError::ChecksumMismatch {
+                    url: url.to_string(),
+                    expected: expected.clone(),
+                    actual,
+                });
+            }
+        }
+
         Ok(installation)
     }
 }
@@ -193,6 +223,32 @@ mod tests {
         assert_eq!(h.last().unwrap().1, total_size);
     }
 
+    #[test]
+    fn test_checksum_mismatch_rejects_downloaded_docs() {
+        let data = b"[]".to_vec();
+        let installer = DocsInstaller::new(MockProvider {
+            data: Ok(data.clone()),
+            chunk_size: 1,
+        })
+        .with_sha256(Some(crate::digest::sha256_hex(&data)));
+        assert!(
+            installer
+                .install("url", SourceFormat::Raw, |_, _| {})
+                .is_ok()
+        );
+
+        let installer = DocsInstaller::new(MockProvider {
+            data: Ok(data),
+            chunk_size: 1,
+        })
+        .with_sha256(Some("0".repeat(64)));
+        let res = installer.install("url", SourceFormat::Raw, |_, _| {});
+        assert!(matches!(
+            res,
+            Err(DocsInstallError::ChecksumMismatch { .. })
+        ));
+    }
+
     #[test]
     fn test_err_source_access_failed() {
         let provider = MockProvider {
@@ -18,6 +18,10 @@ impl<R: Read, F: FnMut(u64, u64) + Send + 'static> ProgressReader<R, F> {
             on_progress,
         }
     }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
 }
 
 impl<R: Read, F: FnMut(u64, u64) + Send + 'static> Read for ProgressReader<R, F> {
@@ -56,7 +60,8 @@ impl InstallProvider for HttpProvider {
     type Error = reqwest::Error;
 
     fn fetch(&self, url: &str) -> Result<(Box<dyn Read + Send>, u64), Self::Error> {
-        let resp: Response = self.client.get(url).send()?;
+        // ミラーの設定ミスなどで返る 404 を展開エラーにしない
+        let resp: Response = self.client.get(url).send()?.error_for_status()?;
         let size = resp.content_length().unwrap_or(0);
         Ok((Box::new(resp), size))
     }
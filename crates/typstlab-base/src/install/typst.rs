@@ -11,6 +11,7 @@ use tar::Archive as TarArchive;
 use xz2::read::XzDecoder;
 use zip::ZipArchive;
 
+use crate::digest::Sha256Reader;
 use crate::install::{InstallProvider, ProgressReader};
 use typstlab_proto::{Installer, SourceFormat};
 
@@ -62,10 +63,19 @@ pub enum TypstInstallError {
 
     #[error("Malicious path detected in archive: {0}")]
     SecurityError(String),
+
+    #[error("Checksum mismatch for '{url}': expected sha256 {expected}, got {actual}")]
+    ChecksumMismatch {
+        url: String,
+        expected: String,
+        actual: String,
+    },
 }
 
 pub struct TypstInstaller<P: InstallProvider> {
     provider: P,
+    /// 指定があれば、展開したアーカイブを返す前に突き合わせる
+    sha256: Option<String>,
 }
 
 struct XzReadTracker<R: Read> {
@@ -74,6 +84,10 @@ struct XzReadTracker<R: Read> {
 }
 
 impl<R: Read> XzReadTracker<R> {
+    fn into_inner(self) -> R {
+        self.inner.into_inner()
+    }
+
     fn new(reader: R) -> (Self, Arc<AtomicBool>) {
         let failed = Arc::new(AtomicBool::new(false));
         (
@@ -108,7 +122,31 @@ fn tar_or_xz_error(error: io::Error, xz_failed: &AtomicBool) -> TypstInstallErro
 
 impl<P: InstallProvider> TypstInstaller<P> {
     pub fn new(provider: P) -> Self {
-        Self { provider }
+        Self {
+            provider,
+            sha256: None,
+        }
+    }
+
+    pub fn with_sha256(mut self, sha256: Option<String>) -> Self {
+        self.sha256 = sha256;
+        self
+    }
+
+    fn verify(&self, url: &str, hashed: Sha256Reader<impl Read>) -> Result<(), TypstInstallError> {
+        let Some(expected) = &self.sha256 else {
+            return Ok(());
+        };
+        let actual = hashed.finish().map_err(TypstInstallError::Io)?;
+        if actual == *expected {
+            Ok(())
+        } else {
+            Err(TypstInstallError::ChecksumMismatch {
+                url: url.to_string(),
+                expected: expected.clone(),
+                actual,
+            })
+        }
     }
 }
 
@@ -174,7 +212,8 @@ impl<P: InstallProvider> TypstInstaller<P> {
             .fetch(url)
             .map_err(|e| TypstInstallError::SourceAccessFailed(Box::new(e)))?;
 
-        let mut progress_reader = ProgressReader::new(reader, total_size, on_progress);
+        let mut progress_reader =
+            ProgressReader::new(Sha256Reader::new(reader), total_size, on_progress);
         let dest = installation.path();
 
         match format {
@@ -251,12 +290,15 @@ impl<P: InstallProvider> TypstInstaller<P> {
                         }
                     }
                 }
+                // 展開先はまだ一時ディレクトリなので、不一致ならそのまま捨てられる
+                self.verify(url, archive.into_inner().into_inner().into_inner())?;
                 Ok(installation)
             }
             SourceFormat::Zip { strip_components } => {
                 let mut tmp_file =
                     create_zip_staging().map_err(TypstInstallError::ZipStagingFailed)?;
                 copy(&mut progress_reader, &mut tmp_file).map_err(TypstInstallError::Io)?;
+                self.verify(url, progress_reader.into_inner())?;
 
                 let mut archive = ZipArchive::new(tmp_file)?;
 
@@ -514,6 +556,54 @@ mod tests {
         assert_eq!(h.last().unwrap().1, total_size);
     }
 
+    // --- Checksum ---
+
+    #[test]
+    fn test_checksum_accepts_matching_archives_and_rejects_others() {
+        let xz_data = create_tar_xz_raw(vec![("typst", b"bin", None)]);
+        let zip_data = create_zip(vec![("typst", b"bin")]);
+        let cases = [
+            (
+                xz_data,
+                SourceFormat::TarXz {
+                    strip_components: 0,
+                },
+            ),
+            (
+                zip_data,
+                SourceFormat::Zip {
+                    strip_components: 0,
+                },
+            ),
+        ];
+
+        for (data, format) in cases {
+            let digest = crate::digest::sha256_hex(&data);
+            let installer = TypstInstaller::new(MockProvider {
+                data: Ok(data.clone()),
+                chunk_size: 7,
+            })
+            .with_sha256(Some(digest.clone()));
+            let installation = installer.install("url", format.clone(), |_, _| {}).unwrap();
+            assert!(installation.path().join("typst").exists());
+
+            let installer = TypstInstaller::new(MockProvider {
+                data: Ok(data),
+                chunk_size: 7,
+            })
+            .with_sha256(Some("0".repeat(64)));
+            match installer.install("url", format, |_, _| {}) {
+                Err(TypstInstallError::ChecksumMismatch {
+                    expected, actual, ..
+                }) => {
+                    assert_eq!(expected, "0".repeat(64));
+                    assert_eq!(actual, digest);
+                }
+                other => panic!("expected checksum mismatch, got {:?}", other.map(|_| ())),
+            }
+        }
+    }
+
     // --- 2. Security ---
 
     #[test]
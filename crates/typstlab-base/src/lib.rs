@@ -1,23 +1,47 @@
+pub mod archive;
+pub mod bibliography;
+pub mod diagnostics;
+pub mod digest;
 pub mod docs_parser;
 pub mod driver;
+pub mod git;
+pub mod image_diff;
 pub mod install;
 pub mod link_resolver;
+pub mod lock;
 pub mod path;
+pub mod pdf;
 pub mod persistence;
 pub mod platform;
 pub mod project_docs;
+pub mod shell;
+pub mod source_refs;
+pub mod text_stats;
+pub mod timings;
 pub mod version_resolver;
 
-pub use driver::{ExecutionResult, TypstCommand, TypstDriver};
+pub use archive::{ArchiveError, ArchiveSource, write_zip};
+pub use bibliography::{bibliography_keys, bibliography_paths};
+pub use diagnostics::{BuildDiagnostic, DiagnosticSeverity, parse_diagnostics};
+pub use driver::{
+    CompileOptions, DiagnosticFormat, ExecutionResult, QueryFormat, TypstCommand, TypstDriver,
+};
+pub use git::{GitError, export_git_tree};
+pub use image_diff::{ImageDiffError, PixelDiff, RgbaImage, diff_images, read_png, write_png};
 pub use install::{
     DocsInstallError, DocsInstaller, RAW_DOCS_FILENAME, TypstInstallError, TypstInstaller,
 };
+pub use lock::FileLock;
+pub use pdf::pdf_page_count;
 pub use persistence::Persistence;
 pub use platform::{Arch, Os, Platform};
 pub use project_docs::{
     ProjectDocs, ProjectDocsCommitError, ProjectDocsSyncError, sync_project_docs,
 };
+pub use text_stats::{count_words, html_word_count};
+pub use timings::{TimingPhase, summarize_trace};
 pub use version_resolver::{
-    ProjectToolChain, ResolvedToolChain, ToolChoice, VersionResolveError, get_latest_typst,
-    resolve_toolchain,
+    ProjectToolChain, ResolvedToolChain, ToolChoice, ToolchainMirrors, VersionResolveError,
+    get_latest_typst, known_docs_versions, known_typst_versions, resolve_toolchain,
+    resolve_typst_version,
 };
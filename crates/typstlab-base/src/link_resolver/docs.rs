@@ -1,19 +1,31 @@
-use super::{ResolvedLink, Version};
+use super::{LinkResolveError, ResolvedLink, Version, expand_url_template};
+use crate::version_resolver::docs_asset_sha256;
 use typstlab_proto::SourceFormat;
 
+/// docs の配布物のファイル名（チェックサム表のキー）
+pub const DOCS_ASSET: &str = "docs.json";
+
+/// 既定の配布元（typst-community/dev-builds の Releases）
+pub const DOCS_URL_TEMPLATE: &str =
+    "https://github.com/typst-community/dev-builds/releases/download/docs-v{version}/{asset}";
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DocsLinkRequest<'a> {
     pub version: Version<'a>,
+    /// `[toolchain.mirrors] docs`。None なら [`DOCS_URL_TEMPLATE`]
+    pub mirror: Option<&'a str>,
 }
 
-pub fn resolve_docs_link(request: DocsLinkRequest<'_>) -> ResolvedLink {
-    ResolvedLink {
-        url: format!(
-            "https://github.com/typst-community/dev-builds/releases/download/docs-v{}/docs.json",
-            request.version.as_str()
-        ),
+pub fn resolve_docs_link(request: DocsLinkRequest<'_>) -> Result<ResolvedLink, LinkResolveError> {
+    Ok(ResolvedLink {
+        url: expand_url_template(
+            request.mirror.unwrap_or(DOCS_URL_TEMPLATE),
+            request.version,
+            DOCS_ASSET,
+        )?,
         format: SourceFormat::Raw,
-    }
+        sha256: docs_asset_sha256(request.version.as_str(), DOCS_ASSET)?,
+    })
 }
 
 #[cfg(test)]
@@ -24,7 +36,9 @@ mod tests {
     fn test_resolve_docs_link_uses_docs_version_and_raw_format() {
         let link = resolve_docs_link(DocsLinkRequest {
             version: Version::new("0.14.2"),
-        });
+            mirror: None,
+        })
+        .unwrap();
 
         assert_eq!(
             link.url,
@@ -32,4 +46,15 @@ mod tests {
         );
         assert_eq!(link.format, SourceFormat::Raw);
     }
+
+    #[test]
+    fn test_resolve_docs_link_uses_mirror_template() {
+        let link = resolve_docs_link(DocsLinkRequest {
+            version: Version::new("0.14.2"),
+            mirror: Some("http://127.0.0.1:8080/docs/{version}/{asset}"),
+        })
+        .unwrap();
+
+        assert_eq!(link.url, "http://127.0.0.1:8080/docs/0.14.2/docs.json");
+    }
 }
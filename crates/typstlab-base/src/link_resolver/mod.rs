@@ -1,9 +1,11 @@
 mod docs;
 mod typst;
 
-pub use docs::{DocsLinkRequest, resolve_docs_link};
-pub use typst::{LinkResolveError, TypstLinkRequest, resolve_typst_link};
+pub use docs::{DOCS_ASSET, DOCS_URL_TEMPLATE, DocsLinkRequest, resolve_docs_link};
+pub use typst::{TYPST_URL_TEMPLATE, TypstLinkRequest, resolve_typst_link, typst_release_assets};
 
+use crate::version_resolver::VersionResolveError;
+use thiserror::Error;
 use typstlab_proto::SourceFormat;
 
 /// Release version used for link resolution.
@@ -32,4 +34,70 @@ impl AsRef<str> for Version<'_> {
 pub struct ResolvedLink {
     pub url: String,
     pub format: SourceFormat,
+    /// 公開されている配布物の SHA-256（表に載っていなければ None）
+    pub sha256: Option<String>,
+}
+
+/// ミラーの URL テンプレートがバージョンと配布物を区別できるかを確かめる。
+/// 配布物名（[`typst_release_assets`], [`DOCS_ASSET`]）はバージョンを含まないため、
+/// `{asset}` と `{version}` の両方が必要
+pub fn validate_url_template(template: &str) -> Result<(), LinkResolveError> {
+    for placeholder in ["{asset}", "{version}"] {
+        if !template.contains(placeholder) {
+            return Err(LinkResolveError::InvalidUrlTemplate {
+                template: template.to_string(),
+                placeholder,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// ミラーの URL テンプレートに `{version}` と `{asset}` を埋め込む
+fn expand_url_template(
+    template: &str,
+    version: Version<'_>,
+    asset: &str,
+) -> Result<String, LinkResolveError> {
+    validate_url_template(template)?;
+    Ok(template
+        .replace("{version}", version.as_str())
+        .replace("{asset}", asset))
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum LinkResolveError {
+    #[error("unsupported platform for typst download: {platform:?}")]
+    UnsupportedTypstPlatform { platform: crate::platform::Platform },
+    #[error("failed to look up published checksums: {0}")]
+    Checksum(#[from] VersionResolveError),
+    #[error("mirror URL template '{template}' must contain {placeholder}")]
+    InvalidUrlTemplate {
+        template: String,
+        placeholder: &'static str,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_url_template_requires_asset_and_version() {
+        assert!(validate_url_template("https://mirror.example/{version}/{asset}").is_ok());
+        assert_eq!(
+            validate_url_template("https://mirror.example/{version}/typst.tar.xz"),
+            Err(LinkResolveError::InvalidUrlTemplate {
+                template: "https://mirror.example/{version}/typst.tar.xz".to_string(),
+                placeholder: "{asset}",
+            })
+        );
+        assert!(matches!(
+            validate_url_template("https://mirror.example/latest/{asset}"),
+            Err(LinkResolveError::InvalidUrlTemplate {
+                placeholder: "{version}",
+                ..
+            })
+        ));
+    }
 }
@@ -1,8 +1,12 @@
-use super::{ResolvedLink, Version};
+use super::{LinkResolveError, ResolvedLink, Version, expand_url_template};
 use crate::platform::{Arch, Os, Platform};
-use thiserror::Error;
+use crate::version_resolver::typst_asset_sha256;
 use typstlab_proto::SourceFormat;
 
+/// 既定の配布元（GitHub Releases）
+pub const TYPST_URL_TEMPLATE: &str =
+    "https://github.com/typst/typst/releases/download/v{version}/{asset}";
+
 const TYPST_TAR_XZ_FORMAT: SourceFormat = SourceFormat::TarXz {
     strip_components: 1,
 };
@@ -14,27 +18,48 @@ const TYPST_ZIP_FORMAT: SourceFormat = SourceFormat::Zip {
 pub struct TypstLinkRequest<'a> {
     pub platform: Platform,
     pub version: Version<'a>,
-}
-
-#[derive(Error, Debug, Clone, PartialEq, Eq)]
-pub enum LinkResolveError {
-    #[error("unsupported platform for typst download: {platform:?}")]
-    UnsupportedTypstPlatform { platform: Platform },
+    /// `[toolchain.mirrors] typst`。None なら [`TYPST_URL_TEMPLATE`]
+    pub mirror: Option<&'a str>,
 }
 
 pub fn resolve_typst_link(request: TypstLinkRequest<'_>) -> Result<ResolvedLink, LinkResolveError> {
     let (target, format) = typst_target_and_format(request.platform)?;
+    let asset = typst_asset_name(target, request.platform.os);
     Ok(ResolvedLink {
-        url: format!(
-            "https://github.com/typst/typst/releases/download/v{}/typst-{}.{}",
-            request.version.as_str(),
-            target,
-            typst_archive_extension(request.platform.os)
-        ),
+        url: expand_url_template(
+            request.mirror.unwrap_or(TYPST_URL_TEMPLATE),
+            request.version,
+            &asset,
+        )?,
         format,
+        sha256: typst_asset_sha256(request.version.as_str(), &asset)?,
     })
 }
 
+/// 配布されているプラットフォームとターゲット名
+const TYPST_TARGETS: &[(Os, Arch, &str)] = &[
+    (Os::MacOS, Arch::X86_64, "x86_64-apple-darwin"),
+    (Os::MacOS, Arch::Aarch64, "aarch64-apple-darwin"),
+    (Os::Linux, Arch::X86_64, "x86_64-unknown-linux-musl"),
+    (Os::Linux, Arch::Aarch64, "aarch64-unknown-linux-musl"),
+    (Os::Linux, Arch::Armv7, "armv7-unknown-linux-musleabi"),
+    (Os::Linux, Arch::Riscv64, "riscv64gc-unknown-linux-gnu"),
+    (Os::Windows, Arch::X86_64, "x86_64-pc-windows-msvc"),
+    (Os::Windows, Arch::Aarch64, "aarch64-pc-windows-msvc"),
+];
+
+/// 全プラットフォームの配布物のファイル名（チェックサム表のキー）
+pub fn typst_release_assets() -> Vec<String> {
+    TYPST_TARGETS
+        .iter()
+        .map(|(os, _, target)| typst_asset_name(target, *os))
+        .collect()
+}
+
+fn typst_asset_name(target: &str, os: Os) -> String {
+    format!("typst-{}.{}", target, typst_archive_extension(os))
+}
+
 fn typst_target_and_format(
     platform: Platform,
 ) -> Result<(&'static str, SourceFormat), LinkResolveError> {
@@ -43,17 +68,11 @@ fn typst_target_and_format(
         Os::MacOS | Os::Linux => TYPST_TAR_XZ_FORMAT,
     };
 
-    let target = match (platform.os, platform.arch) {
-        (Os::MacOS, Arch::X86_64) => "x86_64-apple-darwin",
-        (Os::MacOS, Arch::Aarch64) => "aarch64-apple-darwin",
-        (Os::Linux, Arch::X86_64) => "x86_64-unknown-linux-musl",
-        (Os::Linux, Arch::Aarch64) => "aarch64-unknown-linux-musl",
-        (Os::Linux, Arch::Armv7) => "armv7-unknown-linux-musleabi",
-        (Os::Linux, Arch::Riscv64) => "riscv64gc-unknown-linux-gnu",
-        (Os::Windows, Arch::X86_64) => "x86_64-pc-windows-msvc",
-        (Os::Windows, Arch::Aarch64) => "aarch64-pc-windows-msvc",
-        _ => return Err(LinkResolveError::UnsupportedTypstPlatform { platform }),
-    };
+    let target = TYPST_TARGETS
+        .iter()
+        .find(|(os, arch, _)| *os == platform.os && *arch == platform.arch)
+        .map(|(_, _, target)| *target)
+        .ok_or(LinkResolveError::UnsupportedTypstPlatform { platform })?;
 
     Ok((target, format))
 }
@@ -73,6 +92,7 @@ mod tests {
         TypstLinkRequest {
             platform: Platform { os, arch },
             version: Version::new("0.14.2"),
+            mirror: None,
         }
     }
 
@@ -0,0 +1,71 @@
+use fs2::FileExt;
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+
+/// プロセスをまたいで有効な排他ロック。drop 時に解放される。
+#[derive(Debug)]
+pub struct FileLock {
+    file: File,
+    path: PathBuf,
+}
+
+impl FileLock {
+    /// ロックを取得できるまで待つ
+    pub fn acquire<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let (file, path) = open_lock_file(path.as_ref())?;
+        FileExt::lock_exclusive(&file)?;
+        Ok(Self { file, path })
+    }
+
+    /// 他のプロセスが保持していれば待たずに `None` を返す
+    pub fn try_acquire<P: AsRef<Path>>(path: P) -> std::io::Result<Option<Self>> {
+        let (file, path) = open_lock_file(path.as_ref())?;
+        match FileExt::try_lock_exclusive(&file) {
+            Ok(()) => Ok(Some(Self { file, path })),
+            Err(error) if error.kind() == fs2::lock_contended_error().kind() => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = FileExt::unlock(&self.file);
+    }
+}
+
+fn open_lock_file(path: &Path) -> std::io::Result<(File, PathBuf)> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)?;
+    Ok((file, path.to_path_buf()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FileLock;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_lock_is_exclusive_until_dropped() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(".typstlab").join("build.lock");
+
+        let held = FileLock::acquire(&path).unwrap();
+        assert_eq!(held.path(), path.as_path());
+        assert!(FileLock::try_acquire(&path).unwrap().is_none());
+
+        drop(held);
+        assert!(FileLock::try_acquire(&path).unwrap().is_some());
+    }
+}
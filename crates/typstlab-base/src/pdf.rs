@@ -0,0 +1,163 @@
+use flate2::read::ZlibDecoder;
+use std::io::Read;
+
+/// PDF のページ数。ルートのページツリー（`/Parent` を持たない `/Type /Pages`）の `/Count` を読み、
+/// 見つからなければ `/Type /Page` オブジェクトの数で代える。
+/// 圧縮されたオブジェクトストリーム（`/Type /ObjStm`）の中も探す。
+pub fn pdf_page_count(bytes: &[u8]) -> Option<usize> {
+    let objects = objects(bytes);
+
+    let root_count = objects
+        .iter()
+        .map(|object| dictionary(object))
+        .filter(|dict| has_type(dict, b"Pages") && find(dict, b"/Parent").is_none())
+        .find_map(|dict| integer_after(dict, b"/Count"));
+    if let Some(count) = root_count.filter(|count| *count > 0) {
+        return Some(count);
+    }
+
+    let count = objects
+        .iter()
+        .filter(|object| has_type(dictionary(object), b"Page"))
+        .count();
+    (count > 0).then_some(count)
+}
+
+/// 間接オブジェクトの中身（`N G obj` から `endobj` まで）を、オブジェクトストリームを展開しつつ集める
+fn objects(bytes: &[u8]) -> Vec<Vec<u8>> {
+    let mut objects = Vec::new();
+    let mut rest = bytes;
+    while let Some(start) = find(rest, b"obj") {
+        let body = &rest[start + b"obj".len()..];
+        let end = find(body, b"endobj").unwrap_or(body.len());
+        let object = &body[..end];
+        if has_type(dictionary(object), b"ObjStm") {
+            objects.extend(object_stream(object).unwrap_or_default());
+        }
+        objects.push(object.to_vec());
+        rest = &body[(end + b"endobj".len()).min(body.len())..];
+    }
+    objects
+}
+
+/// `/Type /ObjStm` の中に並んだオブジェクト（FlateDecode 以外の圧縮は扱わない）
+fn object_stream(object: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let dict = dictionary(object);
+    find(dict, b"/FlateDecode")?;
+    let first = integer_after(dict, b"/First")?;
+
+    let data = &object[dict.len() + b"stream".len()..];
+    let data = data
+        .strip_prefix(b"\r\n".as_slice())
+        .or_else(|| data.strip_prefix(b"\n".as_slice()))
+        .unwrap_or(data);
+    let data = &data[..find(data, b"endstream").unwrap_or(data.len())];
+    let mut decoded = Vec::new();
+    ZlibDecoder::new(data).read_to_end(&mut decoded).ok()?;
+
+    // 先頭は「オブジェクト番号 オフセット」の組の並び
+    let header = std::str::from_utf8(decoded.get(..first)?).ok()?;
+    let offsets: Vec<usize> = header
+        .split_ascii_whitespace()
+        .skip(1)
+        .step_by(2)
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .ok()?;
+    let body = &decoded[first..];
+    let mut objects = Vec::new();
+    for (index, &offset) in offsets.iter().enumerate() {
+        let end = offsets.get(index + 1).copied().unwrap_or(body.len());
+        objects.push(body.get(offset..end)?.to_vec());
+    }
+    Some(objects)
+}
+
+/// ストリームを持つオブジェクトの場合は辞書の部分だけ
+fn dictionary(object: &[u8]) -> &[u8] {
+    &object[..find(object, b"stream").unwrap_or(object.len())]
+}
+
+fn has_type(dict: &[u8], name: &[u8]) -> bool {
+    let mut rest = dict;
+    while let Some(index) = find(rest, b"/Type") {
+        rest = &rest[index + b"/Type".len()..];
+        if let Some(after) = trim_start(rest)
+            .strip_prefix(b"/".as_slice())
+            .and_then(|value| value.strip_prefix(name))
+            && !after.first().is_some_and(|b| b.is_ascii_alphanumeric())
+        {
+            return true;
+        }
+    }
+    false
+}
+
+fn integer_after(dict: &[u8], key: &[u8]) -> Option<usize> {
+    let value = trim_start(&dict[find(dict, key)? + key.len()..]);
+    let digits = value.iter().take_while(|b| b.is_ascii_digit()).count();
+    std::str::from_utf8(&value[..digits]).ok()?.parse().ok()
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn trim_start(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::pdf_page_count;
+    use flate2::Compression;
+    use flate2::write::ZlibEncoder;
+    use std::io::Write;
+
+    #[test]
+    fn test_pdf_page_count_reads_root_pages_count() {
+        let pdf = b"1 0 obj << /Type /Pages /Kids [4 0 R 5 0 R] /Count 3 >> endobj\n\
+                    4 0 obj << /Type /Pages /Parent 1 0 R /Count 2 >> endobj\n\
+                    2 0 obj << /Type /Page /Parent 4 0 R >> endobj\n\
+                    3 0 obj <</Type/Page/Parent 4 0 R>> endobj\n";
+
+        assert_eq!(pdf_page_count(pdf), Some(3));
+        assert_eq!(pdf_page_count(b"%PDF-1.7 compressed"), None);
+    }
+
+    #[test]
+    fn test_pdf_page_count_falls_back_to_page_objects() {
+        let pdf = b"2 0 obj << /Type /Page /Parent 1 0 R >> endobj\n\
+                    3 0 obj <</Type/Page/Parent 1 0 R>> endobj\n";
+
+        assert_eq!(pdf_page_count(pdf), Some(2));
+    }
+
+    #[test]
+    fn test_pdf_page_count_looks_inside_object_streams() {
+        let objects =
+            b"<< /Type /Pages /Kids [2 0 R 3 0 R] /Count 2 >><< /Type /Page >><< /Type /Page >>";
+        let header = b"1 0 2 47 3 64 ";
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(header).unwrap();
+        encoder.write_all(objects).unwrap();
+        let stream = encoder.finish().unwrap();
+
+        let mut pdf = format!(
+            "%PDF-1.7\n5 0 obj\n<< /Type /ObjStm /N 3 /First {} /Filter /FlateDecode /Length {} >>\nstream\n",
+            header.len(),
+            stream.len()
+        )
+        .into_bytes();
+        pdf.extend_from_slice(&stream);
+        pdf.extend_from_slice(b"\nendstream\nendobj\n");
+
+        assert_eq!(pdf_page_count(&pdf), Some(2));
+    }
+}
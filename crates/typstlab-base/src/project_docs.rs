@@ -79,12 +79,17 @@ pub fn project_docs_path(project_root: &Path, docs: ProjectDocs) -> PathBuf {
     project_root.join(PROJECT_CACHE_DIR).join(docs.path_name())
 }
 
+/// 同期途中の作業場所が置かれるディレクトリ
+pub fn project_staging_root(project_root: &Path) -> PathBuf {
+    project_root.join(PROJECT_CACHE_DIR).join(PROJECT_TMP_DIR)
+}
+
 fn create_staging_area(
     project_root: &Path,
     docs: ProjectDocs,
 ) -> Result<TempDir, ProjectDocsSyncError> {
     Persistence::create_temp_dir(
-        project_root.join(PROJECT_CACHE_DIR).join(PROJECT_TMP_DIR),
+        project_staging_root(project_root),
         &format!("staging-{}-", docs.path_name()),
     )
     .map_err(|error| ProjectDocsSyncError::Staging(std::io::Error::other(error)))
@@ -0,0 +1,58 @@
+use crate::driver::ExecutionResult;
+use std::path::Path;
+use std::process::Command;
+use std::time::Instant;
+
+/// ユーザーが設定したコマンド行をシェル経由で実行する（出力は取り込む）
+pub fn run_shell(
+    command: &str,
+    cwd: &Path,
+    env: &[(String, String)],
+) -> std::io::Result<ExecutionResult> {
+    let start = Instant::now();
+    let mut process = if cfg!(windows) {
+        let mut process = Command::new("cmd");
+        process.arg("/C").arg(command);
+        process
+    } else {
+        let mut process = Command::new("sh");
+        process.arg("-c").arg(command);
+        process
+    };
+    let output = process
+        .current_dir(cwd)
+        .envs(env.iter().map(|(key, value)| (key, value)))
+        .output()?;
+
+    Ok(ExecutionResult {
+        exit_code: output.status.code().unwrap_or(-1),
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        duration_ms: start.elapsed().as_millis() as u64,
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::run_shell;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_run_shell_passes_env_and_cwd() {
+        let temp = TempDir::new().unwrap();
+
+        let result = run_shell(
+            "echo \"$GREETING\" > out.txt; echo oops >&2; exit 3",
+            temp.path(),
+            &[("GREETING".to_string(), "hello".to_string())],
+        )
+        .unwrap();
+
+        assert_eq!(result.exit_code, 3);
+        assert_eq!(result.stderr, "oops\n");
+        assert_eq!(
+            std::fs::read_to_string(temp.path().join("out.txt")).unwrap(),
+            "hello\n"
+        );
+    }
+}
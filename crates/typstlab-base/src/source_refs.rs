@@ -0,0 +1,157 @@
+use std::fmt;
+use std::path::PathBuf;
+
+/// `@namespace/name:version` 形式のパッケージ指定
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PackageSpec {
+    pub namespace: String,
+    pub name: String,
+    pub version: String,
+}
+
+impl PackageSpec {
+    pub fn parse(raw: &str) -> Option<Self> {
+        let rest = raw.strip_prefix('@')?;
+        let (namespace, rest) = rest.split_once('/')?;
+        let (name, version) = rest.split_once(':')?;
+        let valid = |part: &str| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+        };
+        if !valid(namespace) || !valid(name) || !valid(version) {
+            return None;
+        }
+        if !version.split('.').all(|part| part.parse::<u64>().is_ok()) {
+            return None;
+        }
+
+        Some(Self {
+            namespace: namespace.to_string(),
+            name: name.to_string(),
+            version: version.to_string(),
+        })
+    }
+
+    /// パッケージディレクトリ内の配置 (`namespace/name/version`)
+    pub fn relative_dir(&self) -> PathBuf {
+        PathBuf::from(&self.namespace)
+            .join(&self.name)
+            .join(&self.version)
+    }
+}
+
+impl fmt::Display for PackageSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "@{}/{}:{}", self.namespace, self.name, self.version)
+    }
+}
+
+/// Typst ソース中の文字列リテラルを順に取り出す。コメント内のものは無視する。
+/// import / include / image / read などの参照先はここから拾う。
+pub fn string_literals(source: &str) -> Vec<String> {
+    let mut literals = Vec::new();
+    let mut chars = source.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = '\0';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            '\\' => {
+                chars.next();
+            }
+            '"' => {
+                let mut literal = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' | '\n' => break,
+                        '\\' => {
+                            if let Some(escaped) = chars.next() {
+                                literal.push(escaped);
+                            }
+                        }
+                        _ => literal.push(c),
+                    }
+                }
+                literals.push(literal);
+            }
+            _ => {}
+        }
+    }
+
+    literals
+}
+
+/// ソースが参照しているパッケージを重複なく列挙する
+pub fn package_imports(source: &str) -> Vec<PackageSpec> {
+    let mut specs: Vec<PackageSpec> = string_literals(source)
+        .iter()
+        .filter_map(|literal| PackageSpec::parse(literal))
+        .collect();
+    specs.sort();
+    specs.dedup();
+    specs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PackageSpec, package_imports, string_literals};
+
+    #[test]
+    fn test_string_literals_skip_comments_and_unescape() {
+        let source = r#"
+#import "../../templates/ieee/lib.typ": *
+// #import "commented.typ"
+/* "block" */
+#image("fig \"1\".png")
+"#;
+
+        assert_eq!(
+            string_literals(source),
+            vec![
+                "../../templates/ieee/lib.typ".to_string(),
+                "fig \"1\".png".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_package_imports_parse_specs() {
+        let source = r#"
+#import "@preview/cetz:0.3.4": canvas
+#import "@preview/cetz:0.3.4"
+#import "@local/mine:1.0.0"
+Email me at "@someone/else"
+"#;
+
+        assert_eq!(
+            package_imports(source),
+            vec![
+                PackageSpec::parse("@local/mine:1.0.0").unwrap(),
+                PackageSpec::parse("@preview/cetz:0.3.4").unwrap(),
+            ]
+        );
+        assert_eq!(
+            PackageSpec::parse("@preview/cetz:0.3.4")
+                .unwrap()
+                .relative_dir(),
+            std::path::PathBuf::from("preview/cetz/0.3.4")
+        );
+    }
+}
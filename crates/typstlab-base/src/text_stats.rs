@@ -0,0 +1,101 @@
+use crate::docs_parser::html::decode_entities;
+use html5gum::Tokenizer;
+use html5gum::emitters::default::Token;
+
+/// 本文として数えない要素
+const SKIPPED_TAGS: [&str; 4] = ["head", "script", "style", "template"];
+
+/// HTML 出力の本文の語数
+pub fn html_word_count(html: &str) -> usize {
+    count_words(&html_text(html))
+}
+
+/// HTML からタグを除いた本文（`head` やスクリプトの中身は含めない）
+pub fn html_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut skip_depth = 0usize;
+
+    for token in Tokenizer::new(html).flatten() {
+        match token {
+            Token::StartTag(tag) => {
+                let name = String::from_utf8_lossy(tag.name.as_slice()).to_ascii_lowercase();
+                if SKIPPED_TAGS.contains(&name.as_str()) && !tag.self_closing {
+                    skip_depth += 1;
+                }
+                // ブロック境界で語がつながらないようにする
+                text.push(' ');
+            }
+            Token::EndTag(tag) => {
+                let name = String::from_utf8_lossy(tag.name.as_slice()).to_ascii_lowercase();
+                if SKIPPED_TAGS.contains(&name.as_str()) {
+                    skip_depth = skip_depth.saturating_sub(1);
+                }
+                text.push(' ');
+            }
+            Token::String(value) if skip_depth == 0 => {
+                text.push_str(&decode_entities(&String::from_utf8_lossy(
+                    value.value.as_slice(),
+                )));
+            }
+            _ => {}
+        }
+    }
+
+    text
+}
+
+/// 空白区切りの語を数える。漢字・かな・ハングルは 1 文字を 1 語とみなす。
+pub fn count_words(text: &str) -> usize {
+    let mut count = 0;
+    let mut in_word = false;
+
+    for c in text.chars() {
+        if is_cjk(c) {
+            count += 1;
+            in_word = false;
+        } else if c.is_alphanumeric() {
+            if !in_word {
+                count += 1;
+                in_word = true;
+            }
+        } else if c.is_whitespace() {
+            in_word = false;
+        }
+        // それ以外の記号（`don't` の `'` や `well-known` の `-`）は語を区切らない
+    }
+
+    count
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(
+        c,
+        '\u{3040}'..='\u{30FF}' // ひらがな・カタカナ
+            | '\u{3400}'..='\u{4DBF}' // CJK 統合漢字拡張 A
+            | '\u{4E00}'..='\u{9FFF}' // CJK 統合漢字
+            | '\u{AC00}'..='\u{D7AF}' // ハングル
+            | '\u{F900}'..='\u{FAFF}' // CJK 互換漢字
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{count_words, html_word_count};
+
+    #[test]
+    fn test_count_words_mixes_latin_and_cjk() {
+        assert_eq!(count_words("Don't panic, it's well-known."), 4);
+        assert_eq!(count_words("Typstで論文を書く"), 7);
+        assert_eq!(count_words("  \n"), 0);
+    }
+
+    #[test]
+    fn test_html_word_count_skips_head_and_scripts() {
+        let html = "<!DOCTYPE html><html><head><title>Ignored title</title>\
+                    <style>p { color: red }</style></head>\
+                    <body><h1>Intro</h1><p>Hello&nbsp;world &amp; more</p>\
+                    <script>let x = 1;</script><p>end</p></body></html>";
+
+        assert_eq!(html_word_count(html), 5);
+    }
+}
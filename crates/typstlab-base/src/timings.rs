@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// タイミングトレースの 1 フェーズ分の集計
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimingPhase {
+    pub name: String,
+    /// 入れ子になった同名区間は二重に数えない
+    pub total_ms: f64,
+    pub calls: usize,
+}
+
+/// Chrome trace 形式のイベント（Typst の `--timings` が出力する）
+#[derive(Debug, Deserialize)]
+struct TraceEvent {
+    name: String,
+    ph: String,
+    /// マイクロ秒
+    ts: f64,
+    #[serde(default)]
+    dur: Option<f64>,
+    #[serde(default)]
+    tid: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum TraceFile {
+    Events(Vec<TraceEvent>),
+    Object {
+        #[serde(rename = "traceEvents")]
+        trace_events: Vec<TraceEvent>,
+    },
+}
+
+fn add(totals: &mut HashMap<String, (f64, usize)>, name: &str, micros: f64) {
+    let entry = totals.entry(name.to_string()).or_default();
+    entry.0 += micros;
+    entry.1 += 1;
+}
+
+/// トレースをフェーズ名ごとに集計し、時間のかかった順に返す
+pub fn summarize_trace(json: &str) -> serde_json::Result<Vec<TimingPhase>> {
+    let events = match serde_json::from_str(json)? {
+        TraceFile::Events(events) => events,
+        TraceFile::Object { trace_events } => trace_events,
+    };
+
+    let mut totals: HashMap<String, (f64, usize)> = HashMap::new();
+    let mut stacks: HashMap<String, Vec<(String, f64)>> = HashMap::new();
+
+    for event in events {
+        let stack = stacks.entry(event.tid.to_string()).or_default();
+        match event.ph.as_str() {
+            "B" => stack.push((event.name, event.ts)),
+            "E" => {
+                let Some(index) = stack.iter().rposition(|(name, _)| *name == event.name) else {
+                    continue;
+                };
+                let (name, start) = stack.remove(index);
+                if !stack.iter().any(|(open, _)| *open == name) {
+                    add(&mut totals, &name, event.ts - start);
+                }
+            }
+            "X" if !stack.iter().any(|(open, _)| *open == event.name) => {
+                add(&mut totals, &event.name, event.dur.unwrap_or_default());
+            }
+            _ => {}
+        }
+    }
+
+    let mut phases: Vec<TimingPhase> = totals
+        .into_iter()
+        .map(|(name, (micros, calls))| TimingPhase {
+            name,
+            total_ms: micros / 1000.0,
+            calls,
+        })
+        .collect();
+    phases.sort_by(|a, b| {
+        b.total_ms
+            .total_cmp(&a.total_ms)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    Ok(phases)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::summarize_trace;
+
+    #[test]
+    fn test_summarize_trace_sorts_by_total_time_without_double_counting() {
+        let trace = r#"[
+            {"name":"typeset","ph":"B","ts":0,"pid":1,"tid":1},
+            {"name":"layout","ph":"B","ts":100,"pid":1,"tid":1},
+            {"name":"layout","ph":"B","ts":200,"pid":1,"tid":1},
+            {"name":"layout","ph":"E","ts":300,"pid":1,"tid":1},
+            {"name":"layout","ph":"E","ts":2100,"pid":1,"tid":1},
+            {"name":"typeset","ph":"E","ts":5000,"pid":1,"tid":1},
+            {"name":"export","ph":"X","ts":5000,"dur":500,"pid":1,"tid":1}
+        ]"#;
+
+        let phases = summarize_trace(trace).unwrap();
+
+        let names: Vec<_> = phases.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["typeset", "layout", "export"]);
+        assert_eq!(phases[0].total_ms, 5.0);
+        assert_eq!(phases[1].total_ms, 2.0);
+        assert_eq!(phases[1].calls, 1);
+    }
+
+    #[test]
+    fn test_summarize_trace_accepts_trace_event_object() {
+        let trace = r#"{"traceEvents":[{"name":"eval","ph":"X","ts":0,"dur":1500}]}"#;
+
+        let phases = summarize_trace(trace).unwrap();
+
+        assert_eq!(phases.len(), 1);
+        assert_eq!(phases[0].total_ms, 1.5);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::link_resolver::validate_url_template;
 use semver::Version as SemverVersion;
 use serde::de::{Error as DeError, Visitor};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
@@ -106,6 +107,49 @@ pub struct ProjectToolChain {
     pub typst_docs: ToolChoice,
     #[serde(default = "default_typstyle_choice")]
     pub typstyle: ToolChoice,
+    #[serde(default, skip_serializing_if = "ToolchainMirrors::is_empty")]
+    pub mirrors: ToolchainMirrors,
+}
+
+/// `[toolchain.mirrors]`。ダウンロード URL のテンプレートで、
+/// `{version}` と `{asset}` が置き換えられる。None なら GitHub Releases
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolchainMirrors {
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "url_template"
+    )]
+    pub typst: Option<String>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "url_template"
+    )]
+    pub docs: Option<String>,
+}
+
+fn url_template<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let template = String::deserialize(deserializer)?;
+    validate_url_template(&template).map_err(DeError::custom)?;
+    Ok(Some(template))
+}
+
+impl ToolchainMirrors {
+    pub fn is_empty(&self) -> bool {
+        self.typst.is_none() && self.docs.is_none()
+    }
+
+    /// 指定の無い項目だけを `fallback` で埋める
+    pub fn or(self, fallback: ToolchainMirrors) -> Self {
+        Self {
+            typst: self.typst.or(fallback.typst),
+            docs: self.docs.or(fallback.docs),
+        }
+    }
 }
 
 impl Default for ProjectToolChain {
@@ -114,6 +158,7 @@ impl Default for ProjectToolChain {
             typst: default_typst_version(),
             typst_docs: default_typst_docs_choice(),
             typstyle: default_typstyle_choice(),
+            mirrors: ToolchainMirrors::default(),
         }
     }
 }
@@ -151,8 +196,18 @@ pub enum VersionResolveError {
 
     #[error("invalid semantic version '{version}' in {tool} resolver JSON")]
     InvalidVersion { tool: &'static str, version: String },
+
+    #[error("no published checksum for {tool} {version} asset '{asset}'")]
+    MissingChecksum {
+        tool: &'static str,
+        version: String,
+        asset: String,
+    },
 }
 
+/// 配布物の SHA-256 を載せる鍵。`{ "<version>": { "<asset file name>": "<hex>" } }`
+const CHECKSUMS_KEY: &str = "sha256";
+
 pub fn resolve_toolchain(
     toolchain: &ProjectToolChain,
 ) -> Result<ResolvedToolChain, VersionResolveError> {
@@ -166,6 +221,65 @@ pub fn resolve_toolchain(
     })
 }
 
+/// 互換表に載っている Typst のバージョン（新しい順）
+pub fn known_typst_versions() -> Result<Vec<String>, VersionResolveError> {
+    newest_first(
+        TYPST_RESOLVER.name,
+        TYPST_RESOLVER.table()?.versions_by_typst.keys(),
+    )
+}
+
+/// 互換表に載っている docs のバージョン（新しい順）
+pub fn known_docs_versions() -> Result<Vec<String>, VersionResolveError> {
+    newest_first(
+        TYPST_DOCS_RESOLVER.name,
+        TYPST_DOCS_RESOLVER.table()?.all_versions.iter(),
+    )
+}
+
+fn newest_first<'a>(
+    tool: &'static str,
+    versions: impl Iterator<Item = &'a String>,
+) -> Result<Vec<String>, VersionResolveError> {
+    let mut versions = versions
+        .map(|version| {
+            validate_version(tool, version)?;
+            let parsed =
+                SemverVersion::parse(version).expect("version was validated immediately above");
+            Ok((parsed, version.clone()))
+        })
+        .collect::<Result<Vec<_>, VersionResolveError>>()?;
+    versions.sort_by(|(left, _), (right, _)| right.cmp(left));
+    Ok(versions.into_iter().map(|(_, version)| version).collect())
+}
+
+/// 公開されている Typst の配布物 `asset` の SHA-256。
+///
+/// 表にバージョンごと無ければ None を返し、呼び出し側は検証できないことを警告して
+/// ダウンロードを続ける。表に載っているバージョンで配布物が欠けていれば
+/// [`VersionResolveError::MissingChecksum`]（表は `cargo xtask update_checksums` で埋める）。
+pub fn typst_asset_sha256(
+    version: &str,
+    asset: &str,
+) -> Result<Option<String>, VersionResolveError> {
+    TYPST_RESOLVER.asset_sha256(version, asset)
+}
+
+/// 公開されている docs の配布物 `asset` の SHA-256。表に無い場合は [`typst_asset_sha256`] と同じ扱い
+pub fn docs_asset_sha256(
+    version: &str,
+    asset: &str,
+) -> Result<Option<String>, VersionResolveError> {
+    TYPST_DOCS_RESOLVER.asset_sha256(version, asset)
+}
+
+/// 先頭の `v` を外し、互換表に載っている Typst のバージョンであることを確かめる
+pub fn resolve_typst_version(version: &str) -> Result<String, VersionResolveError> {
+    let version = normalize_version(version);
+    TYPST_RESOLVER.ensure_typst_version_exists(&version)?;
+    Ok(version)
+}
+
 trait VersionResolver {
     fn tool_name(&self) -> &'static str;
     fn table(&self) -> Result<&CompatibilityTable, VersionResolveError>;
@@ -222,6 +336,25 @@ impl JsonToolResolver {
             })
         }
     }
+
+    fn asset_sha256(
+        &self,
+        version: &str,
+        asset: &str,
+    ) -> Result<Option<String>, VersionResolveError> {
+        let version = normalize_version(version);
+        let Some(assets) = self.table()?.checksums.get(&version) else {
+            return Ok(None);
+        };
+        match assets.get(asset) {
+            Some(digest) => Ok(Some(digest.clone())),
+            None => Err(VersionResolveError::MissingChecksum {
+                tool: self.name,
+                version,
+                asset: asset.to_string(),
+            }),
+        }
+    }
 }
 
 impl VersionResolver for JsonToolResolver {
@@ -241,6 +374,8 @@ impl VersionResolver for JsonToolResolver {
 struct CompatibilityTable {
     versions_by_typst: BTreeMap<String, Vec<String>>,
     all_versions: BTreeSet<String>,
+    /// version -> 配布物のファイル名 -> SHA-256
+    checksums: BTreeMap<String, BTreeMap<String, String>>,
 }
 
 impl CompatibilityTable {
@@ -295,9 +430,15 @@ impl CompatibilityTable {
             versions_by_typst.insert(typst_version, compatible_versions);
         }
 
+        let checksums = match object.get(CHECKSUMS_KEY) {
+            Some(value) => parse_checksums(tool, value)?,
+            None => BTreeMap::new(),
+        };
+
         Ok(Self {
             versions_by_typst,
             all_versions,
+            checksums,
         })
     }
 
@@ -362,10 +503,54 @@ impl CompatibilityTable {
     }
 }
 
-fn normalize_version(version: &str) -> String {
+/// 先頭の `v` を外したバージョン文字列
+pub fn normalize_version(version: &str) -> String {
     version.strip_prefix('v').unwrap_or(version).to_string()
 }
 
+fn parse_checksums(
+    tool: &'static str,
+    value: &Value,
+) -> Result<BTreeMap<String, BTreeMap<String, String>>, VersionResolveError> {
+    let invalid = |message: String| VersionResolveError::InvalidEmbeddedJson { tool, message };
+    let versions = value
+        .as_object()
+        .ok_or_else(|| invalid(format!("expected object for '{CHECKSUMS_KEY}'")))?;
+
+    let mut checksums = BTreeMap::new();
+    for (version, assets) in versions {
+        let version = normalize_version(version);
+        validate_version(tool, &version)?;
+        let assets = assets.as_object().ok_or_else(|| {
+            invalid(format!(
+                "expected object for '{CHECKSUMS_KEY}' of version '{version}'"
+            ))
+        })?;
+
+        let mut by_asset = BTreeMap::new();
+        for (asset, digest) in assets {
+            let digest = digest
+                .as_str()
+                .filter(|digest| is_sha256_hex(digest))
+                .ok_or_else(|| {
+                    invalid(format!(
+                        "expected lowercase hex SHA-256 for '{asset}' of version '{version}'"
+                    ))
+                })?;
+            by_asset.insert(asset.clone(), digest.to_string());
+        }
+        checksums.insert(version, by_asset);
+    }
+    Ok(checksums)
+}
+
+fn is_sha256_hex(value: &str) -> bool {
+    value.len() == 64
+        && value
+            .bytes()
+            .all(|byte| byte.is_ascii_digit() || (b'a'..=b'f').contains(&byte))
+}
+
 fn is_version_key(key: &str) -> bool {
     SemverVersion::parse(key.strip_prefix('v').unwrap_or(key)).is_ok()
 }
@@ -396,6 +581,7 @@ mod tests {
                 typst: "0.14.2".to_string(),
                 typst_docs: ToolChoice::Auto,
                 typstyle: ToolChoice::None,
+                mirrors: ToolchainMirrors::default(),
             }
         );
     }
@@ -420,6 +606,7 @@ mod tests {
             typst: "0.14.2".to_string(),
             typst_docs: ToolChoice::Auto,
             typstyle: ToolChoice::Auto,
+            mirrors: ToolchainMirrors::default(),
         })
         .unwrap();
 
@@ -439,6 +626,7 @@ mod tests {
             typst: "0.14.2".to_string(),
             typst_docs: ToolChoice::None,
             typstyle: ToolChoice::None,
+            mirrors: ToolchainMirrors::default(),
         })
         .unwrap();
 
@@ -453,6 +641,7 @@ mod tests {
             typst: "0.14.2".to_string(),
             typst_docs: ToolChoice::Version("0.14.2".to_string()),
             typstyle: ToolChoice::None,
+            mirrors: ToolchainMirrors::default(),
         })
         .unwrap();
 
@@ -465,6 +654,7 @@ mod tests {
             typst: "9.9.9".to_string(),
             typst_docs: ToolChoice::None,
             typstyle: ToolChoice::None,
+            mirrors: ToolchainMirrors::default(),
         })
         .unwrap_err();
 
@@ -474,6 +664,22 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_known_typst_versions_are_newest_first() {
+        let versions = known_typst_versions().unwrap();
+
+        assert_eq!(
+            versions.first().map(String::as_str),
+            Some(get_latest_typst())
+        );
+        assert!(versions.iter().any(|version| version == "0.12.0"));
+        assert_eq!(resolve_typst_version("v0.14.2").unwrap(), "0.14.2");
+        assert!(matches!(
+            resolve_typst_version("9.9.9"),
+            Err(VersionResolveError::TypstVersionNotFound { .. })
+        ));
+    }
+
     #[test]
     fn test_latest_compatible_uses_semver_order() {
         let resolver = resolver_with_json(
@@ -554,6 +760,120 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_checksums_are_looked_up_by_version_and_asset() {
+        let digest = "ab".repeat(32);
+        let table = CompatibilityTable::from_json_str(
+            "test_tool",
+            &format!(
+                r#"{{
+                    "0.14.2": ["0.14.2"],
+                    "sha256": {{ "v0.14.2": {{ "tool-linux.tar.xz": "{digest}" }} }}
+                }}"#
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(
+            table.checksums["0.14.2"].get("tool-linux.tar.xz"),
+            Some(&digest)
+        );
+        assert!(!table.has_typst_version("sha256"));
+        assert_eq!(typst_asset_sha256("0.14.2", "no-such-asset").unwrap(), None);
+    }
+
+    #[test]
+    fn test_missing_asset_of_listed_version_is_an_error() {
+        static CACHE: OnceLock<Result<CompatibilityTable, VersionResolveError>> = OnceLock::new();
+        let resolver = JsonToolResolver {
+            name: "test_tool",
+            json: r#"{
+                "0.14.2": ["0.14.2"],
+                "sha256": { "0.14.2": { "tool-linux.tar.xz": "abababababababababababababababababababababababababababababababab" } }
+            }"#,
+            cache: &CACHE,
+        };
+
+        assert_eq!(
+            resolver
+                .asset_sha256("v0.14.2", "tool-linux.tar.xz")
+                .unwrap(),
+            Some("abababababababababababababababababababababababababababababababab".to_string())
+        );
+        assert_eq!(
+            resolver.asset_sha256("0.14.2", "tool-macos.tar.xz"),
+            Err(VersionResolveError::MissingChecksum {
+                tool: "test_tool",
+                version: "0.14.2".to_string(),
+                asset: "tool-macos.tar.xz".to_string(),
+            })
+        );
+        assert_eq!(
+            resolver.asset_sha256("0.13.1", "tool-linux.tar.xz"),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn test_listed_checksum_versions_cover_every_release_asset() {
+        let mut missing = Vec::new();
+        for version in known_typst_versions().unwrap() {
+            for asset in crate::link_resolver::typst_release_assets() {
+                if let Err(error) = typst_asset_sha256(&version, &asset) {
+                    missing.push(error.to_string());
+                }
+            }
+        }
+        for version in known_docs_versions().unwrap() {
+            if let Err(error) = docs_asset_sha256(&version, crate::link_resolver::DOCS_ASSET) {
+                missing.push(error.to_string());
+            }
+        }
+
+        assert!(missing.is_empty(), "missing checksums: {missing:#?}");
+    }
+
+    #[test]
+    #[ignore = "upstream digests are not in the tables yet; fill them with `cargo xtask update_checksums`"]
+    fn test_checksum_tables_cover_every_release_asset() {
+        let mut missing = Vec::new();
+        for version in known_typst_versions().unwrap() {
+            for asset in crate::link_resolver::typst_release_assets() {
+                if !matches!(typst_asset_sha256(&version, &asset), Ok(Some(_))) {
+                    missing.push(format!("typst {version} {asset}"));
+                }
+            }
+        }
+        for version in known_docs_versions().unwrap() {
+            let asset = crate::link_resolver::DOCS_ASSET;
+            if !matches!(docs_asset_sha256(&version, asset), Ok(Some(_))) {
+                missing.push(format!("docs {version} {asset}"));
+            }
+        }
+
+        assert!(missing.is_empty(), "missing checksums: {missing:#?}");
+    }
+
+    #[test]
+    fn test_checksums_reject_malformed_digest() {
+        let error = CompatibilityTable::from_json_str(
+            "test_tool",
+            r#"{
+                "0.14.2": ["0.14.2"],
+                "sha256": { "0.14.2": { "tool-linux.tar.xz": "ABC" } }
+            }"#,
+        )
+        .unwrap_err();
+
+        assert!(matches!(
+            error,
+            VersionResolveError::InvalidEmbeddedJson {
+                tool: "test_tool",
+                ..
+            }
+        ));
+    }
+
     struct TestResolver {
         name: &'static str,
         table: CompatibilityTable,
@@ -1,20 +1,43 @@
 use anyhow::{Result, anyhow};
 use colored::Colorize;
 use typstlab_app::{
-    AppContext, BuildAction, BuildError, BuildEvent, BuildFormat, BuildWarning, DistObject,
+    AppContext, BuildAction, BuildDiagnostic, BuildError, BuildEvent, BuildFormat, BuildOverrides,
+    BuildWarning, DiagnosticSeverity, DistObject, WatchAction, WatchError, WatchEvent,
+    WatchWarning,
 };
 use typstlab_proto::{Action, AppEvent, Artifact, CliSpeaker, Entity};
 
+/// `--timings` の集計で表示するフェーズ数
+const TIMINGS_SHOWN: usize = 5;
+
+/// ビルド方法に関する CLI オプション
+pub struct BuildOptions {
+    pub jobs: usize,
+    pub force: bool,
+    /// Typst のタイミングトレースを出力して集計を表示する
+    pub timings: bool,
+    /// paper.toml の `[build]` より優先される設定
+    pub overrides: BuildOverrides,
+    /// typstlab.toml の `[profiles]` から選ぶプロファイル名
+    pub profile: Option<String>,
+}
+
 /// build コマンドのエントリポイント
 pub fn run(
     ctx: AppContext,
     inputs: Option<Vec<String>>,
-    format: BuildFormat,
+    format: Option<BuildFormat>,
+    options: BuildOptions,
     verbose: bool,
 ) -> Result<()> {
     use typstlab_base::driver::TypstDriver;
     let driver = TypstDriver::new(ctx.toolchain.typst.path());
-    let action = BuildAction::new(ctx.loaded_project, driver, inputs, format);
+    let action = BuildAction::new(ctx.loaded_project, driver, inputs, format)
+        .with_overrides(options.overrides)
+        .with_profile(options.profile)
+        .with_jobs(options.jobs)
+        .with_force(options.force)
+        .with_timings(options.timings);
     let presenter = BuildPresenter;
     let mut warning_seen = false;
 
@@ -25,7 +48,9 @@ pub fn run(
             }
         },
         &mut |warning| {
-            warning_seen = true;
+            if matches!(warning, BuildWarning::NoTargetsFound) {
+                warning_seen = true;
+            }
             presenter.render_warning(warning);
         },
     ) {
@@ -44,6 +69,42 @@ pub fn run(
     }
 }
 
+/// `build --watch` のエントリポイント。中断されるまで戻らない。
+pub fn run_watch(
+    ctx: AppContext,
+    inputs: Option<Vec<String>>,
+    format: Option<BuildFormat>,
+    options: BuildOptions,
+    verbose: bool,
+) -> Result<()> {
+    use typstlab_base::driver::TypstDriver;
+    let driver = TypstDriver::new(ctx.toolchain.typst.path());
+    let action = WatchAction::new(ctx.loaded_project, driver, inputs, format)
+        .with_overrides(options.overrides)
+        .with_profile(options.profile)
+        .with_jobs(options.jobs)
+        .with_force(options.force)
+        .with_timings(options.timings);
+    let presenter = WatchPresenter;
+
+    match action.run(
+        &mut |event| {
+            if event.visible_in_cli(verbose) {
+                presenter.render_event(event);
+            }
+        },
+        &mut |warning| presenter.render_warning(warning),
+    ) {
+        Ok(()) => Ok(()),
+        Err(errors) => {
+            for err in &errors {
+                presenter.render_error(err);
+            }
+            Err(anyhow!("Watch failed"))
+        }
+    }
+}
+
 struct BuildPresenter;
 
 impl CliSpeaker for BuildPresenter {
@@ -64,6 +125,35 @@ impl CliSpeaker for BuildPresenter {
             BuildEvent::Starting { paper_id } => {
                 println!("{} Building {}...", "🔨".cyan(), paper_id.bold());
             }
+            BuildEvent::WaitingForLock { path } => {
+                println!(
+                    "{} Another build of this project is running, waiting for it to finish... {}",
+                    "⏳".yellow(),
+                    path.display().to_string().dimmed()
+                );
+            }
+            BuildEvent::RunningHook {
+                paper_id,
+                stage,
+                command,
+            } => {
+                let target = paper_id.unwrap_or_else(|| "project".to_string());
+                println!(
+                    "{} Running {} hook for {}: {}",
+                    "🪝".cyan(),
+                    stage,
+                    target.bold(),
+                    command.dimmed()
+                );
+            }
+            BuildEvent::UpToDate { paper_id } => {
+                println!(
+                    "{} {} is up to date {}",
+                    "✔".green(),
+                    paper_id.bold(),
+                    "(use --force to rebuild)".dimmed()
+                );
+            }
             BuildEvent::Finished {
                 artifact,
                 duration_ms,
@@ -76,6 +166,28 @@ impl CliSpeaker for BuildPresenter {
                     artifact.path().display().to_string().dimmed()
                 );
             }
+            BuildEvent::Timings {
+                paper_id,
+                format,
+                trace,
+                phases,
+            } => {
+                println!(
+                    "{} Slowest phases for {} ({}) {}",
+                    "⏱".cyan(),
+                    paper_id.bold(),
+                    format,
+                    trace.display().to_string().dimmed()
+                );
+                for phase in phases.iter().take(TIMINGS_SHOWN) {
+                    println!(
+                        "    {:>10} {} {}",
+                        format!("{:.1}ms", phase.total_ms).yellow(),
+                        phase.name,
+                        format!("×{}", phase.calls).dimmed()
+                    );
+                }
+            }
             _ => {}
         }
     }
@@ -85,6 +197,42 @@ impl CliSpeaker for BuildPresenter {
             BuildWarning::NoTargetsFound => {
                 eprintln!("{} No papers found to build.", "⚠ WARNING:".yellow().bold());
             }
+            BuildWarning::CompilerWarning {
+                paper_id,
+                diagnostic,
+            } => {
+                eprintln!("{} {}:", "⚠ WARNING:".yellow().bold(), paper_id.bold());
+                render_diagnostic(&diagnostic);
+            }
+            BuildWarning::ManifestWriteFailed(reason) => {
+                eprintln!(
+                    "{} Failed to update dist/manifest.json: {}",
+                    "⚠ WARNING:".yellow().bold(),
+                    reason
+                );
+            }
+            BuildWarning::TimingsUnsupported { version } => {
+                eprintln!(
+                    "{} Typst {} cannot write timing traces (requires 0.11 or newer); building without them.",
+                    "⚠ WARNING:".yellow().bold(),
+                    version
+                );
+            }
+            BuildWarning::TimingsUnreadable { path, reason } => {
+                eprintln!(
+                    "{} Could not read timing trace {}: {}",
+                    "⚠ WARNING:".yellow().bold(),
+                    path.display(),
+                    reason
+                );
+            }
+            BuildWarning::CacheWriteFailed(reason) => {
+                eprintln!(
+                    "{} Failed to update the build cache: {}",
+                    "⚠ WARNING:".yellow().bold(),
+                    reason
+                );
+            }
         }
     }
 
@@ -96,6 +244,17 @@ impl CliSpeaker for BuildPresenter {
                     eprintln!("  {} {}", "•".red(), err);
                 }
             }
+            BuildError::PaperBuildError(artifact) if !artifact.diagnostics.is_empty() => {
+                eprintln!(
+                    "{} {} failed:",
+                    "❌".red(),
+                    artifact.root().display().to_string().bold()
+                );
+                for diagnostic in &artifact.diagnostics {
+                    render_diagnostic(diagnostic);
+                }
+                eprintln!();
+            }
             BuildError::PaperBuildError(artifact) => {
                 eprintln!(
                     "{} {} failed:",
@@ -139,3 +298,105 @@ impl CliSpeaker for BuildPresenter {
         }
     }
 }
+
+/// 診断 1 件を `severity: message` + 位置 + ヒントの形で表示する
+pub(super) fn render_diagnostic(diagnostic: &BuildDiagnostic) {
+    let severity = match diagnostic.severity {
+        DiagnosticSeverity::Error => "error".red().bold(),
+        DiagnosticSeverity::Warning => "warning".yellow().bold(),
+    };
+    eprintln!("   {}: {}", severity, diagnostic.message);
+    if let Some(location) = diagnostic.location() {
+        eprintln!("     {} {}", "-->".blue(), location.dimmed());
+    }
+    for hint in &diagnostic.hints {
+        eprintln!("     {} {}", "= hint:".cyan(), hint);
+    }
+}
+
+struct WatchPresenter;
+
+impl CliSpeaker for WatchPresenter {
+    type Event = WatchEvent;
+    type Warning = WatchWarning;
+    type Error = WatchError;
+    type Output = ();
+
+    fn render_event(&self, event: AppEvent<WatchEvent>) {
+        let AppEvent {
+            scope,
+            level,
+            presentation,
+            audience,
+            payload,
+        } = event;
+
+        match payload {
+            WatchEvent::Watching { paths } => {
+                println!(
+                    "\n{} Watching {} path(s) for changes. Press Ctrl-C to stop.",
+                    "👀".cyan(),
+                    paths.len()
+                );
+            }
+            WatchEvent::ChangeDetected { paper_ids } => {
+                println!(
+                    "\n{} Change detected, rebuilding {}...",
+                    "🔄".yellow(),
+                    paper_ids.join(", ").bold()
+                );
+            }
+            WatchEvent::Build(build_event) => {
+                BuildPresenter.render_event(AppEvent {
+                    scope,
+                    level,
+                    presentation,
+                    audience,
+                    payload: build_event,
+                });
+            }
+        }
+    }
+
+    fn render_warning(&self, warning: WatchWarning) {
+        match warning {
+            WatchWarning::Build(build_warning) => BuildPresenter.render_warning(build_warning),
+            WatchWarning::BuildFailed(errors) => {
+                for err in &errors {
+                    BuildPresenter.render_error(err);
+                }
+            }
+            WatchWarning::ProjectReloadFailed(error) => {
+                eprintln!(
+                    "{} Failed to reload project settings, keeping previous ones: {}",
+                    "⚠ WARNING:".yellow().bold(),
+                    error
+                );
+            }
+            WatchWarning::ScanFailed { path, source } => {
+                eprintln!(
+                    "{} Failed to scan {}: {}",
+                    "⚠ WARNING:".yellow().bold(),
+                    path.display(),
+                    source
+                );
+            }
+        }
+    }
+
+    fn render_error(&self, error: &WatchError) {
+        match error {
+            WatchError::Discovery(errs) => {
+                eprintln!("\n{}", "Failed to resolve some targets:".red().bold());
+                for err in errs {
+                    eprintln!("  {} {}", "•".red(), err);
+                }
+            }
+            WatchError::UnknownProfile(_) => {
+                eprintln!("{} {}", "❌ ERROR:".red().bold(), error);
+            }
+        }
+    }
+
+    fn render_result(&self, _output: &()) {}
+}
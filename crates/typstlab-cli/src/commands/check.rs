@@ -0,0 +1,265 @@
+use anyhow::{Result, anyhow};
+use colored::Colorize;
+use typstlab_app::{
+    AppContext, BuildFormat, CheckAction, CheckError, CheckEvent, CheckOutput, CheckRefsAction,
+    CheckRefsError, CheckRefsEvent, CheckRefsOutput, CheckRefsWarning, CheckWarning, RefIssueKind,
+};
+use typstlab_base::diagnostics::DiagnosticSeverity;
+use typstlab_base::driver::TypstDriver;
+use typstlab_proto::{Action, AppEvent, CliSpeaker, Entity};
+
+use super::build::render_diagnostic;
+
+pub struct CheckOptions {
+    /// None なら論文ごとの `[build].formats` に従う
+    pub format: Option<BuildFormat>,
+    pub deny_warnings: bool,
+    pub json: bool,
+}
+
+pub fn run(
+    ctx: AppContext,
+    inputs: Option<Vec<String>>,
+    options: CheckOptions,
+    verbose: bool,
+) -> Result<()> {
+    let driver = TypstDriver::new(ctx.toolchain.typst.path());
+    let action = CheckAction::new(ctx.loaded_project, driver, inputs)
+        .with_format(options.format)
+        .with_deny_warnings(options.deny_warnings);
+    let presenter = CheckPresenter;
+
+    match action.run(
+        &mut |event| {
+            if !options.json && event.visible_in_cli(verbose) {
+                presenter.render_event(event);
+            }
+        },
+        &mut |warning| presenter.render_warning(warning),
+    ) {
+        Ok(output) => {
+            if options.json {
+                println!("{}", serde_json::to_string_pretty(&output)?);
+            } else {
+                presenter.render_result(&output);
+            }
+            if output.passed() {
+                Ok(())
+            } else {
+                Err(anyhow!(
+                    "Check failed with {} error(s), {} warning(s)",
+                    output.count(DiagnosticSeverity::Error),
+                    output.count(DiagnosticSeverity::Warning)
+                ))
+            }
+        }
+        Err(errors) => {
+            for error in &errors {
+                presenter.render_error(error);
+            }
+            Err(anyhow!("Check failed"))
+        }
+    }
+}
+
+struct CheckPresenter;
+
+impl CliSpeaker for CheckPresenter {
+    type Event = CheckEvent;
+    type Warning = CheckWarning;
+    type Error = CheckError;
+    type Output = CheckOutput;
+
+    fn render_event(&self, event: AppEvent<Self::Event>) {
+        match event.payload {
+            CheckEvent::Checking { paper_id } => {
+                println!("{} Checking {}", "🔎".cyan(), paper_id.bold());
+            }
+        }
+    }
+
+    fn render_warning(&self, warning: Self::Warning) {
+        match warning {
+            CheckWarning::NoTargetsFound => {
+                eprintln!("{} {}", "⚠".yellow(), "no papers to check".yellow());
+            }
+        }
+    }
+
+    fn render_error(&self, error: &Self::Error) {
+        eprintln!("{} {}", "❌ Check failed:".red().bold(), error);
+    }
+
+    fn render_result(&self, output: &Self::Output) {
+        for paper in &output.papers {
+            let errors = paper.count(DiagnosticSeverity::Error);
+            let warnings = paper.count(DiagnosticSeverity::Warning);
+            let mark = if !paper.compiled {
+                "❌".red()
+            } else if warnings > 0 {
+                "⚠".yellow()
+            } else {
+                "✅".green()
+            };
+            println!(
+                "{} {} {}",
+                mark,
+                paper.paper_id.bold(),
+                format!(
+                    "[{}] {} error(s), {} warning(s)",
+                    paper.formats.join(", "),
+                    errors,
+                    warnings
+                )
+                .dimmed()
+            );
+            for diagnostic in &paper.diagnostics {
+                render_diagnostic(diagnostic);
+            }
+        }
+
+        let failed = output.papers.iter().filter(|paper| !paper.compiled).count();
+        let summary = format!(
+            "{} paper(s) checked, {} failed, {} warning(s)",
+            output.papers.len(),
+            failed,
+            output.count(DiagnosticSeverity::Warning)
+        );
+        if output.passed() {
+            println!("{} {}", "✅".green(), summary);
+        } else {
+            println!("{} {}", "❌".red(), summary.bold());
+            if failed == 0 && output.deny_warnings {
+                println!("   {}", "warnings are denied (--deny-warnings)".dimmed());
+            }
+        }
+    }
+}
+
+pub fn run_refs(
+    ctx: AppContext,
+    inputs: Option<Vec<String>>,
+    json: bool,
+    verbose: bool,
+) -> Result<()> {
+    let driver = TypstDriver::new(ctx.toolchain.typst.path());
+    let action = CheckRefsAction::new(ctx.loaded_project, driver, inputs);
+    let presenter = CheckRefsPresenter;
+
+    match action.run(
+        &mut |event| {
+            if !json && event.visible_in_cli(verbose) {
+                presenter.render_event(event);
+            }
+        },
+        &mut |warning| presenter.render_warning(warning),
+    ) {
+        Ok(output) => {
+            if json {
+                println!("{}", serde_json::to_string_pretty(&output)?);
+            } else {
+                presenter.render_result(&output);
+            }
+            if output.has_errors() {
+                Err(anyhow!(
+                    "Reference check found {} error(s)",
+                    output.count(DiagnosticSeverity::Error)
+                ))
+            } else {
+                Ok(())
+            }
+        }
+        Err(errors) => {
+            for error in &errors {
+                presenter.render_error(error);
+            }
+            Err(anyhow!("Reference check failed"))
+        }
+    }
+}
+
+struct CheckRefsPresenter;
+
+impl CliSpeaker for CheckRefsPresenter {
+    type Event = CheckRefsEvent;
+    type Warning = CheckRefsWarning;
+    type Error = CheckRefsError;
+    type Output = CheckRefsOutput;
+
+    fn render_event(&self, event: AppEvent<Self::Event>) {
+        match event.payload {
+            CheckRefsEvent::CheckingPaper { paper_id } => {
+                println!("{} Checking references in {}", "🔎".cyan(), paper_id.bold());
+            }
+        }
+    }
+
+    fn render_warning(&self, warning: Self::Warning) {
+        match warning {
+            CheckRefsWarning::NoTargetsFound => {
+                eprintln!("{} {}", "⚠".yellow(), "no papers to check".yellow());
+            }
+            CheckRefsWarning::UnusedEntriesSkipped { paper_id } => {
+                eprintln!(
+                    "{} {}: {} {}",
+                    "⚠".yellow(),
+                    "unused entries not checked".yellow(),
+                    paper_id.bold(),
+                    "(the document does not compile yet)".dimmed()
+                );
+            }
+        }
+    }
+
+    fn render_error(&self, error: &Self::Error) {
+        eprintln!("{} {}", "❌ Reference check failed:".red().bold(), error);
+    }
+
+    fn render_result(&self, output: &Self::Output) {
+        for paper in &output.papers {
+            println!(
+                "{} {} {}",
+                "📚".blue(),
+                paper.paper_id.bold(),
+                format!(
+                    "{} citation(s), {} reference(s), {} bibliography file(s)",
+                    paper.citations.len(),
+                    paper.references.len(),
+                    paper.bibliography.len()
+                )
+                .dimmed()
+            );
+            for issue in &paper.issues {
+                let (mark, description) = match issue.kind {
+                    RefIssueKind::MissingCitation => ("✖".red(), "missing citation".red()),
+                    RefIssueKind::UndefinedLabel => ("✖".red(), "undefined label".red()),
+                    RefIssueKind::UnusedEntry => ("⚠".yellow(), "unused entry".yellow()),
+                };
+                println!(
+                    "  {} {} {} {}",
+                    mark,
+                    description,
+                    issue.key.bold(),
+                    issue.location.as_deref().unwrap_or_default().bright_black()
+                );
+            }
+        }
+
+        let errors = output.count(DiagnosticSeverity::Error);
+        let warnings = output.count(DiagnosticSeverity::Warning);
+        if errors == 0 && warnings == 0 {
+            println!("{} References OK", "✅".green());
+        } else {
+            println!(
+                "{} {} error(s), {} warning(s)",
+                if errors > 0 {
+                    "❌".red()
+                } else {
+                    "⚠".yellow()
+                },
+                errors,
+                warnings
+            );
+        }
+    }
+}
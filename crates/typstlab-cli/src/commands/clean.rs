@@ -0,0 +1,122 @@
+use anyhow::{Result, anyhow};
+use colored::Colorize;
+use typstlab_app::{
+    AppContext, CleanAction, CleanError, CleanKind, CleanOutput, CleanWarning, DistSelection,
+};
+use typstlab_proto::{Action, CliSpeaker};
+
+pub fn run(
+    ctx: AppContext,
+    dist: Option<DistSelection>,
+    cache: bool,
+    dry_run: bool,
+    _verbose: bool,
+) -> Result<()> {
+    let mut action = CleanAction::new(ctx.loaded_project, ctx.typst_store, ctx.docs_store)
+        .with_cache(cache)
+        .with_dry_run(dry_run);
+    if let Some(selection) = dist {
+        action = action.with_dist(selection);
+    }
+    let presenter = CleanPresenter;
+
+    match action.run(&mut |_| {}, &mut |warning| {
+        presenter.render_warning(warning)
+    }) {
+        Ok(output) => {
+            presenter.render_result(&output);
+            Ok(())
+        }
+        Err(errors) => {
+            for error in &errors {
+                presenter.render_error(error);
+            }
+            Err(anyhow!("Clean failed"))
+        }
+    }
+}
+
+struct CleanPresenter;
+
+impl CliSpeaker for CleanPresenter {
+    type Event = ();
+    type Warning = CleanWarning;
+    type Error = CleanError;
+    type Output = CleanOutput;
+
+    fn render_event(&self, _event: typstlab_proto::AppEvent<Self::Event>) {}
+
+    fn render_warning(&self, warning: Self::Warning) {
+        match warning {
+            CleanWarning::NothingToClean(id) => {
+                eprintln!(
+                    "{} {}: {}",
+                    "⚠".yellow(),
+                    "no outputs in dist".yellow(),
+                    id.bold()
+                );
+            }
+            CleanWarning::ManifestWriteFailed(reason) => {
+                eprintln!(
+                    "{} {}: {}",
+                    "⚠".yellow(),
+                    "could not update dist/manifest.json".yellow(),
+                    reason.dimmed()
+                );
+            }
+        }
+    }
+
+    fn render_error(&self, error: &Self::Error) {
+        eprintln!("{} {}", "❌ Clean failed:".red().bold(), error);
+    }
+
+    fn render_result(&self, output: &Self::Output) {
+        if output.entries.is_empty() {
+            println!("{} Nothing to clean", "✨".green());
+            return;
+        }
+
+        let verb = if output.dry_run {
+            "Would remove"
+        } else {
+            "Removed"
+        };
+        for entry in &output.entries {
+            let kind = match entry.kind {
+                CleanKind::Dist => "dist",
+                CleanKind::Staging => "staging",
+            };
+            println!(
+                "  {:<8} {} {}",
+                kind.bright_black(),
+                entry.path.display(),
+                format_bytes(entry.bytes).dimmed()
+            );
+        }
+
+        let total: u64 = output.entries.iter().map(|entry| entry.bytes).sum();
+        println!(
+            "{} {} {} item(s), {}",
+            "🧹".cyan(),
+            verb.bold(),
+            output.entries.len(),
+            format_bytes(total).bold()
+        );
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
@@ -0,0 +1,126 @@
+use anyhow::{Result, anyhow};
+use colored::Colorize;
+use typstlab_app::{AppContext, DiffAction, DiffError, DiffEvent, DiffOutput, PageStatus};
+use typstlab_base::driver::TypstDriver;
+use typstlab_proto::{Action, AppEvent, CliSpeaker, Entity};
+
+use super::build::render_diagnostic;
+
+pub fn run(ctx: AppContext, paper: String, against: Option<String>, verbose: bool) -> Result<()> {
+    let driver = TypstDriver::new(ctx.toolchain.typst.path());
+    let action = DiffAction::new(ctx.loaded_project, driver, paper).with_against(against);
+    let presenter = DiffPresenter;
+
+    match action.run(
+        &mut |event| {
+            if event.visible_in_cli(verbose) {
+                presenter.render_event(event);
+            }
+        },
+        &mut |_| {},
+    ) {
+        Ok(output) => {
+            presenter.render_result(&output);
+            Ok(())
+        }
+        Err(errors) => {
+            for error in &errors {
+                presenter.render_error(error);
+            }
+            Err(anyhow!("Diff failed"))
+        }
+    }
+}
+
+struct DiffPresenter;
+
+impl CliSpeaker for DiffPresenter {
+    type Event = DiffEvent;
+    type Warning = ();
+    type Error = DiffError;
+    type Output = DiffOutput;
+
+    fn render_event(&self, event: AppEvent<Self::Event>) {
+        match event.payload {
+            DiffEvent::Rendering { paper_id, source } => {
+                println!(
+                    "{} Rendering {} {}",
+                    "🖼".cyan(),
+                    paper_id.bold(),
+                    format!("({})", source).dimmed()
+                );
+            }
+            DiffEvent::Comparing { pages } => {
+                println!("   Comparing {} page(s)", pages);
+            }
+        }
+    }
+
+    fn render_warning(&self, _warning: Self::Warning) {}
+
+    fn render_error(&self, error: &Self::Error) {
+        eprintln!("{} {}", "❌ Diff failed:".red().bold(), error);
+        if let DiffError::Render {
+            diagnostics,
+            stderr,
+            ..
+        } = error
+        {
+            if diagnostics.is_empty() {
+                eprintln!("{}", stderr.trim_end());
+            }
+            for diagnostic in diagnostics {
+                render_diagnostic(diagnostic);
+            }
+        }
+    }
+
+    fn render_result(&self, output: &Self::Output) {
+        for page in &output.pages {
+            let (mark, label) = match page.status {
+                PageStatus::Unchanged => continue,
+                PageStatus::Changed => ("~".yellow(), "changed".yellow()),
+                PageStatus::Added => ("+".green(), "added".green()),
+                PageStatus::Removed => ("-".red(), "removed".red()),
+            };
+            let detail = match &page.diff_image {
+                Some(path) => format!(
+                    "{:.2}% of pixels  {}",
+                    page.changed_pixels as f64 * 100.0 / page.total_pixels.max(1) as f64,
+                    path.display()
+                ),
+                None => String::new(),
+            };
+            println!(
+                "  {} page {:<4} {:<8} {}",
+                mark,
+                page.page,
+                label,
+                detail.bright_black()
+            );
+        }
+
+        let changed = output.changed_pages();
+        if changed.is_empty() {
+            println!(
+                "{} {} is identical to {} ({} page(s))",
+                "✅".green(),
+                output.paper_id.bold(),
+                output.against,
+                output.pages.len()
+            );
+        } else {
+            println!(
+                "{} {} of {} page(s) differ from {}",
+                "🔍".yellow(),
+                changed.len().to_string().bold(),
+                output.pages.len(),
+                output.against
+            );
+            println!(
+                "   {}",
+                format!("diff images: {}", output.out_dir.display()).dimmed()
+            );
+        }
+    }
+}
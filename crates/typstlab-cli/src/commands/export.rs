@@ -0,0 +1,95 @@
+use anyhow::{Result, anyhow};
+use colored::Colorize;
+use std::path::PathBuf;
+use typstlab_app::{AppContext, ExportAction, ExportError, ExportOutput, ExportWarning};
+use typstlab_proto::{Action, CliSpeaker};
+
+pub fn run(ctx: AppContext, paper: String, out: PathBuf, _verbose: bool) -> Result<()> {
+    let out = std::path::absolute(&out)?;
+    // Typst と同じ順序（ローカルパッケージ、ダウンロード済みキャッシュ）で探す
+    let package_dirs = [dirs::data_dir(), dirs::cache_dir()]
+        .into_iter()
+        .flatten()
+        .map(|dir| dir.join("typst").join("packages"))
+        .collect();
+    let action = ExportAction::new(ctx.loaded_project, paper, out).with_package_dirs(package_dirs);
+    let presenter = ExportPresenter;
+
+    match action.run(&mut |_| {}, &mut |warning| {
+        presenter.render_warning(warning)
+    }) {
+        Ok(output) => {
+            presenter.render_result(&output);
+            Ok(())
+        }
+        Err(errors) => {
+            for error in &errors {
+                presenter.render_error(error);
+            }
+            Err(anyhow!("Export failed"))
+        }
+    }
+}
+
+struct ExportPresenter;
+
+impl CliSpeaker for ExportPresenter {
+    type Event = ();
+    type Warning = ExportWarning;
+    type Error = ExportError;
+    type Output = ExportOutput;
+
+    fn render_event(&self, _event: typstlab_proto::AppEvent<Self::Event>) {}
+
+    fn render_warning(&self, warning: Self::Warning) {
+        match warning {
+            ExportWarning::PackageNotFound(spec) => {
+                eprintln!(
+                    "{} {}: {} {}",
+                    "⚠".yellow(),
+                    "package not vendored".yellow(),
+                    spec.bold(),
+                    "(build the paper once to download it)".dimmed()
+                );
+            }
+        }
+    }
+
+    fn render_error(&self, error: &Self::Error) {
+        eprintln!("{} {}", "❌ Export failed:".red().bold(), error);
+    }
+
+    fn render_result(&self, output: &Self::Output) {
+        println!(
+            "{} Exported {} to {}",
+            "📦".cyan(),
+            output.paper_id.bold(),
+            output.path.display().to_string().green()
+        );
+        println!(
+            "  {:<10} {}",
+            "typst".bright_black(),
+            output.typst_version.bold()
+        );
+        println!(
+            "  {:<10} {} ({} bytes)",
+            "files".bright_black(),
+            output.files,
+            output.bytes
+        );
+        if !output.templates.is_empty() {
+            println!(
+                "  {:<10} {}",
+                "templates".bright_black(),
+                output.templates.join(", ")
+            );
+        }
+        if !output.packages.is_empty() {
+            println!(
+                "  {:<10} {}",
+                "packages".bright_black(),
+                output.packages.join(", ")
+            );
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use anyhow::{Result, anyhow};
+use colored::Colorize;
+use typstlab_app::{AppContext, FontList, FontsAction, FontsError, FontsWarning};
+use typstlab_proto::{Action, CliSpeaker, Entity};
+
+pub fn run(ctx: AppContext, _verbose: bool) -> Result<()> {
+    use typstlab_base::driver::TypstDriver;
+    let driver = TypstDriver::new(ctx.toolchain.typst.path());
+    let action = FontsAction::new(ctx.loaded_project, driver);
+    let presenter = FontsPresenter;
+    let mut warnings = Vec::new();
+
+    match action.run(&mut |_| {}, &mut |warning| warnings.push(warning)) {
+        Ok(output) => {
+            presenter.render_result(&output);
+            for warning in warnings {
+                presenter.render_warning(warning);
+            }
+            Ok(())
+        }
+        Err(errors) => {
+            for error in &errors {
+                presenter.render_error(error);
+            }
+            Err(anyhow!("Listing fonts failed"))
+        }
+    }
+}
+
+struct FontsPresenter;
+
+impl CliSpeaker for FontsPresenter {
+    type Event = ();
+    type Warning = FontsWarning;
+    type Error = FontsError;
+    type Output = FontList;
+
+    fn render_event(&self, _event: typstlab_proto::AppEvent<Self::Event>) {}
+
+    fn render_warning(&self, warning: Self::Warning) {
+        match warning {
+            FontsWarning::FontDirNotFound(path) => {
+                eprintln!(
+                    "{} {}: {}",
+                    "⚠".yellow(),
+                    "font directory missing".yellow(),
+                    path.display().to_string().dimmed()
+                );
+            }
+        }
+    }
+
+    fn render_error(&self, error: &Self::Error) {
+        eprintln!("{} {}", "❌ Fonts failed:".red().bold(), error);
+    }
+
+    fn render_result(&self, output: &Self::Output) {
+        let count = format!("{} famil(ies)", output.families.len());
+        println!("{} {}", "Fonts".bright_blue().bold(), count.green().bold());
+        for family in &output.families {
+            println!("  {}", family);
+        }
+        println!();
+
+        for dir in &output.font_dirs {
+            println!(
+                "  {:<8} {}",
+                "dir".bright_black(),
+                dir.display().to_string().bright_black()
+            );
+        }
+        let system = if output.ignore_system_fonts {
+            "ignored"
+        } else {
+            "included"
+        };
+        println!("  {:<8} {}", "system".bright_black(), system.bold());
+    }
+}
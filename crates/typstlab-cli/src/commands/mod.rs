@@ -1,6 +1,15 @@
 pub mod build;
+pub mod check;
+pub mod clean;
+pub mod diff;
+pub mod export;
+pub mod fonts;
 pub mod gen_paper;
 pub mod gen_template;
 pub mod mcp;
 pub mod new;
+pub mod query;
+pub mod stats;
 pub mod status;
+pub mod test;
+pub mod toolchain;
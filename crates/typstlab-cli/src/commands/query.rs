@@ -0,0 +1,76 @@
+use anyhow::{Result, anyhow};
+use colored::Colorize;
+use typstlab_app::{AppContext, QueryAction, QueryError, QueryOutput};
+use typstlab_base::driver::{QueryFormat, TypstDriver};
+use typstlab_proto::{Action, CliSpeaker, Entity};
+
+use super::build::render_diagnostic;
+
+pub struct QueryOptions {
+    pub field: Option<String>,
+    pub one: bool,
+    pub format: QueryFormat,
+}
+
+pub fn run(
+    ctx: AppContext,
+    paper: String,
+    selector: String,
+    options: QueryOptions,
+    _verbose: bool,
+) -> Result<()> {
+    let driver = TypstDriver::new(ctx.toolchain.typst.path());
+    let action = QueryAction::new(ctx.loaded_project, driver, paper, selector)
+        .with_field(options.field)
+        .with_one(options.one)
+        .with_format(options.format);
+    let presenter = QueryPresenter;
+
+    match action.run(&mut |_| {}, &mut |_| {}) {
+        Ok(output) => {
+            presenter.render_result(&output);
+            Ok(())
+        }
+        Err(errors) => {
+            for error in &errors {
+                presenter.render_error(error);
+            }
+            Err(anyhow!("Query failed"))
+        }
+    }
+}
+
+struct QueryPresenter;
+
+impl CliSpeaker for QueryPresenter {
+    type Event = ();
+    type Warning = ();
+    type Error = QueryError;
+    type Output = QueryOutput;
+
+    fn render_event(&self, _event: typstlab_proto::AppEvent<Self::Event>) {}
+
+    fn render_warning(&self, _warning: Self::Warning) {}
+
+    fn render_error(&self, error: &Self::Error) {
+        eprintln!("{} {}", "❌ Query failed:".red().bold(), error);
+        if let QueryError::Typst {
+            diagnostics,
+            stderr,
+            ..
+        } = error
+        {
+            if diagnostics.is_empty() {
+                eprintln!("{}", stderr.trim_end());
+            }
+            for diagnostic in diagnostics {
+                render_diagnostic(diagnostic);
+            }
+        }
+    }
+
+    /// スクリプトから読まれるので、Typst の出力をそのまま標準出力へ流す
+    fn render_result(&self, output: &Self::Output) {
+        print!("{}", output.content);
+    }
+}
@@ -0,0 +1,127 @@
+use anyhow::{Result, anyhow};
+use colored::Colorize;
+use typstlab_app::{AppContext, StatsAction, StatsError, StatsEvent, StatsOutput, StatsWarning};
+use typstlab_base::driver::TypstDriver;
+use typstlab_proto::{Action, AppEvent, CliSpeaker, Entity};
+
+pub fn run(ctx: AppContext, inputs: Option<Vec<String>>, json: bool, verbose: bool) -> Result<()> {
+    let driver = TypstDriver::new(ctx.toolchain.typst.path());
+    let action = StatsAction::new(ctx.loaded_project, driver, inputs);
+    let presenter = StatsPresenter;
+
+    match action.run(
+        &mut |event| {
+            if !json && event.visible_in_cli(verbose) {
+                presenter.render_event(event);
+            }
+        },
+        &mut |warning| presenter.render_warning(warning),
+    ) {
+        Ok(output) => {
+            if json {
+                println!("{}", serde_json::to_string_pretty(&output)?);
+            } else {
+                presenter.render_result(&output);
+            }
+            Ok(())
+        }
+        Err(errors) => {
+            for error in &errors {
+                presenter.render_error(error);
+            }
+            Err(anyhow!("Stats failed"))
+        }
+    }
+}
+
+struct StatsPresenter;
+
+impl CliSpeaker for StatsPresenter {
+    type Event = StatsEvent;
+    type Warning = StatsWarning;
+    type Error = StatsError;
+    type Output = StatsOutput;
+
+    fn render_event(&self, event: AppEvent<Self::Event>) {
+        match event.payload {
+            StatsEvent::Collecting { paper_id } => {
+                println!("{} Collecting stats for {}", "📊".cyan(), paper_id.bold());
+            }
+        }
+    }
+
+    fn render_warning(&self, warning: Self::Warning) {
+        match warning {
+            StatsWarning::NoTargetsFound => {
+                eprintln!("{} {}", "⚠".yellow(), "no papers found".yellow());
+            }
+            StatsWarning::NotBuilt { paper_id } => {
+                eprintln!(
+                    "{} {}: {} {}",
+                    "⚠".yellow(),
+                    "page count unavailable".yellow(),
+                    paper_id.bold(),
+                    "(build it as PDF or PNG first)".dimmed()
+                );
+            }
+            StatsWarning::WordCountUnavailable { paper_id, reason } => {
+                eprintln!(
+                    "{} {}: {} {}",
+                    "⚠".yellow(),
+                    "word count unavailable".yellow(),
+                    paper_id.bold(),
+                    reason.dimmed()
+                );
+            }
+        }
+    }
+
+    fn render_error(&self, error: &Self::Error) {
+        eprintln!("{} {}", "❌ Stats failed:".red().bold(), error);
+    }
+
+    fn render_result(&self, output: &Self::Output) {
+        let width = output
+            .papers
+            .iter()
+            .map(|paper| paper.paper_id.len())
+            .max()
+            .unwrap_or(0)
+            .max("PAPER".len());
+        println!(
+            "{}",
+            format!(
+                "{:<width$}  {:>5}  {:>7}  {:>4}  {:>4}  {:>4}  {:>4}",
+                "PAPER", "PAGES", "WORDS", "FIG", "TAB", "EQ", "REFS"
+            )
+            .bright_black()
+        );
+        for paper in &output.papers {
+            println!(
+                "{}  {:>5}  {:>7}  {:>4}  {:>4}  {:>4}  {:>4}",
+                format!("{:<width$}", paper.paper_id).bold(),
+                or_dash(paper.pages),
+                or_dash(paper.words),
+                paper.figures,
+                paper.tables,
+                paper.equations,
+                paper.bibliography_entries
+            );
+        }
+
+        for paper in &output.papers {
+            if paper.outline.is_empty() {
+                continue;
+            }
+            println!();
+            println!("{} {}", paper.paper_id.bold(), paper.title.dimmed());
+            for entry in &paper.outline {
+                println!("{}{}", "  ".repeat(entry.level), entry.title);
+            }
+        }
+    }
+}
+
+fn or_dash(value: Option<usize>) -> String {
+    value.map_or_else(|| "-".to_string(), |value| value.to_string())
+}
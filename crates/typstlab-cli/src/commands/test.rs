@@ -0,0 +1,167 @@
+use anyhow::{Result, anyhow};
+use colored::Colorize;
+use typstlab_app::{
+    AppContext, PageStatus, SnapshotKind, SnapshotStatus, SnapshotTestAction, SnapshotTestError,
+    SnapshotTestEvent, SnapshotTestOutput, SnapshotTestWarning,
+};
+use typstlab_base::driver::TypstDriver;
+use typstlab_proto::{Action, AppEvent, CliSpeaker, Entity};
+
+use super::build::render_diagnostic;
+
+pub struct TestOptions {
+    /// None なら `[test].tolerance`
+    pub tolerance: Option<f64>,
+    pub update: bool,
+}
+
+pub fn run(
+    ctx: AppContext,
+    inputs: Option<Vec<String>>,
+    options: TestOptions,
+    verbose: bool,
+) -> Result<()> {
+    let driver = TypstDriver::new(ctx.toolchain.typst.path());
+    let action = SnapshotTestAction::new(ctx.loaded_project, driver, inputs)
+        .with_tolerance(options.tolerance)
+        .with_update(options.update);
+    let presenter = TestPresenter;
+
+    match action.run(
+        &mut |event| {
+            if event.visible_in_cli(verbose) {
+                presenter.render_event(event);
+            }
+        },
+        &mut |warning| presenter.render_warning(warning),
+    ) {
+        Ok(output) => {
+            presenter.render_result(&output);
+            if output.passed() {
+                Ok(())
+            } else {
+                Err(anyhow!(
+                    "{} snapshot(s) did not match",
+                    output.results.len()
+                        - output.count(SnapshotStatus::Passed)
+                        - output.count(SnapshotStatus::Updated)
+                ))
+            }
+        }
+        Err(errors) => {
+            for error in &errors {
+                presenter.render_error(error);
+            }
+            Err(anyhow!("Snapshot tests failed"))
+        }
+    }
+}
+
+struct TestPresenter;
+
+impl CliSpeaker for TestPresenter {
+    type Event = SnapshotTestEvent;
+    type Warning = SnapshotTestWarning;
+    type Error = SnapshotTestError;
+    type Output = SnapshotTestOutput;
+
+    fn render_event(&self, event: AppEvent<Self::Event>) {
+        match event.payload {
+            SnapshotTestEvent::Testing { kind, id } => {
+                println!(
+                    "{} Testing {} {}",
+                    "🧪".cyan(),
+                    kind_label(kind).dimmed(),
+                    id.bold()
+                );
+            }
+        }
+    }
+
+    fn render_warning(&self, warning: Self::Warning) {
+        match warning {
+            SnapshotTestWarning::NoTargetsFound => {
+                eprintln!(
+                    "{} {}",
+                    "⚠".yellow(),
+                    "no papers or templates to test".yellow()
+                );
+            }
+        }
+    }
+
+    fn render_error(&self, error: &Self::Error) {
+        eprintln!("{} {}", "❌ Snapshot tests failed:".red().bold(), error);
+    }
+
+    fn render_result(&self, output: &Self::Output) {
+        for result in &output.results {
+            let (mark, label) = match result.status {
+                SnapshotStatus::Passed => ("✅".green(), "passed".green()),
+                SnapshotStatus::Updated => ("📝".blue(), "updated".blue()),
+                SnapshotStatus::Missing => ("❔".yellow(), "no snapshot".yellow()),
+                SnapshotStatus::Failed => ("❌".red(), "failed".red()),
+                SnapshotStatus::Error => ("❌".red(), "render error".red()),
+            };
+            println!(
+                "{} {} {} {}",
+                mark,
+                kind_label(result.kind).dimmed(),
+                result.id.bold(),
+                label
+            );
+
+            for page in &result.pages {
+                let detail = match page.status {
+                    PageStatus::Unchanged => continue,
+                    PageStatus::Changed => format!(
+                        "{:.3}% of pixels differ",
+                        page.changed_pixels as f64 * 100.0 / page.total_pixels.max(1) as f64
+                    ),
+                    PageStatus::Added => "not in the snapshot".to_string(),
+                    PageStatus::Removed => "missing from the output".to_string(),
+                };
+                println!("   page {:<4} {}", page.page, detail.bright_black());
+            }
+            if let Some(report_dir) = &result.report_dir {
+                println!(
+                    "   {}",
+                    format!("diff images: {}", report_dir.display()).dimmed()
+                );
+            }
+            for diagnostic in &result.diagnostics {
+                render_diagnostic(diagnostic);
+            }
+        }
+
+        let failed = output.results.len()
+            - output.count(SnapshotStatus::Passed)
+            - output.count(SnapshotStatus::Updated);
+        let summary = format!(
+            "{} passed, {} updated, {} failed (tolerance {})",
+            output.count(SnapshotStatus::Passed),
+            output.count(SnapshotStatus::Updated),
+            failed,
+            output.tolerance
+        );
+        if output.passed() {
+            println!("{} {}", "✅".green(), summary);
+        } else {
+            println!("{} {}", "❌".red(), summary.bold());
+            if output.count(SnapshotStatus::Missing) > 0 || output.count(SnapshotStatus::Failed) > 0
+            {
+                println!(
+                    "   {}",
+                    "run `typstlab test --update` to accept the new output".dimmed()
+                );
+            }
+        }
+    }
+}
+
+fn kind_label(kind: SnapshotKind) -> &'static str {
+    match kind {
+        SnapshotKind::Paper => "paper",
+        SnapshotKind::Template => "template",
+    }
+}
@@ -0,0 +1,324 @@
+use anyhow::{Result, anyhow};
+use colored::Colorize;
+use std::path::{Path, PathBuf};
+use typstlab_app::{
+    Project, ProjectHandle, ProjectToolChain, ResolveEvent, ToolchainError, ToolchainInstallAction,
+    ToolchainInstallOutput, ToolchainListAction, ToolchainListOutput, ToolchainRemoveAction,
+    ToolchainResolveEvent, ToolchainResolveWarning, ToolchainUseAction, ToolchainUseOutput,
+    ToolchainWarning, TypstStore, mirrors_from_env,
+};
+use typstlab_proto::{Action, AppEvent, CliSpeaker, Loadable};
+
+pub fn list(cache_root: PathBuf, project_root: Option<PathBuf>) -> Result<()> {
+    let action = ToolchainListAction::new(typst_store(&cache_root))
+        .with_current(project_root.as_deref().and_then(current_version));
+    let presenter = ListPresenter;
+
+    match action.run(&mut |_| {}, &mut |_| {}) {
+        Ok(output) => {
+            presenter.render_result(&output);
+            Ok(())
+        }
+        Err(errors) => fail(&presenter, &errors),
+    }
+}
+
+pub fn install(
+    cache_root: PathBuf,
+    project_root: Option<PathBuf>,
+    version: String,
+    offline: bool,
+    verbose: bool,
+) -> Result<()> {
+    // 環境変数を優先し、無ければプロジェクトの `[toolchain.mirrors]` を使う。
+    // 設定が壊れている場合はミラーを黙って無視せずに止める
+    let configured = match project_root {
+        Some(root) => Project::new(root)
+            .load()
+            .map_err(|error| anyhow!("Failed to load project: {}", error))?
+            .toolchain()
+            .mirrors
+            .clone(),
+        None => Default::default(),
+    };
+    let mirrors = mirrors_from_env()?.or(configured);
+    let action = ToolchainInstallAction::new(typst_store(&cache_root), version)
+        .with_offline(offline)
+        .with_mirror(mirrors.typst);
+    let presenter = InstallPresenter;
+
+    match action.run(
+        &mut |event| {
+            if event.visible_in_cli(verbose) {
+                presenter.render_event(event);
+            }
+        },
+        &mut |warning| presenter.render_warning(warning),
+    ) {
+        Ok(output) => {
+            presenter.render_result(&output);
+            Ok(())
+        }
+        Err(errors) => fail(&presenter, &errors),
+    }
+}
+
+pub fn remove(cache_root: PathBuf, project_root: Option<PathBuf>, version: String) -> Result<()> {
+    let action = ToolchainRemoveAction::new(typst_store(&cache_root), version)
+        .with_current(project_root.as_deref().and_then(current_version));
+    let presenter = RemovePresenter;
+
+    match action.run(&mut |_| {}, &mut |warning| {
+        presenter.render_warning(warning)
+    }) {
+        Ok(output) => {
+            presenter.render_result(&output);
+            Ok(())
+        }
+        Err(errors) => fail(&presenter, &errors),
+    }
+}
+
+pub fn use_version(cache_root: PathBuf, project_root: PathBuf, version: String) -> Result<()> {
+    let loaded_project = Project::new(project_root)
+        .load()
+        .map_err(|error| anyhow!("Failed to load project: {}", error))?;
+    let action = ToolchainUseAction::new(loaded_project, typst_store(&cache_root), version);
+    let presenter = UsePresenter;
+
+    match action.run(&mut |_| {}, &mut |_| {}) {
+        Ok(output) => {
+            presenter.render_result(&output);
+            Ok(())
+        }
+        Err(errors) => fail(&presenter, &errors),
+    }
+}
+
+fn typst_store(cache_root: &Path) -> TypstStore {
+    TypstStore::new(cache_root.join("typst"))
+}
+
+/// プロジェクトが読めなければ「使用中」の印を付けないだけにする
+fn current_version(project_root: &Path) -> Option<String> {
+    project_toolchain(project_root).map(|toolchain| toolchain.typst)
+}
+
+fn project_toolchain(project_root: &Path) -> Option<ProjectToolChain> {
+    Project::new(project_root.to_path_buf())
+        .load()
+        .ok()
+        .map(|loaded| loaded.toolchain().clone())
+}
+
+fn fail<S>(presenter: &S, errors: &[ToolchainError]) -> Result<()>
+where
+    S: CliSpeaker<Error = ToolchainError>,
+{
+    for error in errors {
+        presenter.render_error(error);
+    }
+    Err(anyhow!("Toolchain command failed"))
+}
+
+/// 起動時の解決と `toolchain install` で共通の警告表示
+pub fn render_resolve_warning(warning: ToolchainResolveWarning) {
+    let message = match warning {
+        ToolchainResolveWarning::DocsSkippedOffline { version } => format!(
+            "Typst docs {} are not in the cache; continuing without them (offline)",
+            version
+        ),
+        ToolchainResolveWarning::UnverifiedDownload { url } => format!(
+            "no published SHA-256 is listed for {}; installed without verifying it",
+            url
+        ),
+    };
+    eprintln!("{} {}", "⚠".yellow(), message.yellow());
+}
+
+fn render_toolchain_error(error: &ToolchainError) {
+    eprintln!("{} {}", "❌ Toolchain command failed:".red().bold(), error);
+}
+
+struct ListPresenter;
+
+impl CliSpeaker for ListPresenter {
+    type Event = ();
+    type Warning = ();
+    type Error = ToolchainError;
+    type Output = ToolchainListOutput;
+
+    fn render_event(&self, _event: AppEvent<Self::Event>) {}
+
+    fn render_warning(&self, _warning: Self::Warning) {}
+
+    fn render_error(&self, error: &Self::Error) {
+        render_toolchain_error(error);
+    }
+
+    fn render_result(&self, output: &Self::Output) {
+        println!("{}", "Installed:".bold());
+        if output.installed.is_empty() {
+            println!("  {}", "(none)".dimmed());
+        }
+        for typst in &output.installed {
+            let in_use = output.current.as_ref() == Some(&typst.version);
+            let mark = if in_use { "*".green() } else { " ".normal() };
+            println!(
+                "{} {:<10} {}",
+                mark,
+                typst.version,
+                typst.path.display().to_string().dimmed()
+            );
+        }
+
+        let available = output
+            .available
+            .iter()
+            .filter(|version| !output.installed.iter().any(|t| &t.version == *version))
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        if !available.is_empty() {
+            println!("{} {}", "Available:".bold(), available.join(", "));
+        }
+        if let Some(current) = &output.current
+            && !output.installed.iter().any(|t| &t.version == current)
+        {
+            println!(
+                "   {}",
+                format!(
+                    "this project uses typst {} (run `typstlab toolchain install {}`)",
+                    current, current
+                )
+                .dimmed()
+            );
+        }
+    }
+}
+
+struct InstallPresenter;
+
+impl CliSpeaker for InstallPresenter {
+    type Event = ToolchainResolveEvent;
+    type Warning = ToolchainResolveWarning;
+    type Error = ToolchainError;
+    type Output = ToolchainInstallOutput;
+
+    fn render_event(&self, event: AppEvent<Self::Event>) {
+        let ToolchainResolveEvent::ResolvingTypst { version, event } = event.payload else {
+            return;
+        };
+        match event {
+            ResolveEvent::CacheMiss => {
+                println!("{} Downloading Typst {}...", "📥".yellow(), version);
+            }
+            ResolveEvent::WaitingForLock { .. } => {
+                println!(
+                    "{} Typst {} is being installed by another process, waiting...",
+                    "⏳".yellow(),
+                    version
+                );
+            }
+            _ => {}
+        }
+    }
+
+    fn render_warning(&self, warning: Self::Warning) {
+        render_resolve_warning(warning);
+    }
+
+    fn render_error(&self, error: &Self::Error) {
+        render_toolchain_error(error);
+    }
+
+    fn render_result(&self, output: &Self::Output) {
+        if output.already_installed {
+            println!(
+                "{} Typst {} is already installed",
+                "✅".green(),
+                output.typst.version.bold()
+            );
+        } else {
+            println!(
+                "{} Installed Typst {} {}",
+                "✅".green(),
+                output.typst.version.bold(),
+                output.typst.binary_path.display().to_string().dimmed()
+            );
+        }
+    }
+}
+
+struct RemovePresenter;
+
+impl CliSpeaker for RemovePresenter {
+    type Event = ();
+    type Warning = ToolchainWarning;
+    type Error = ToolchainError;
+    type Output = PathBuf;
+
+    fn render_event(&self, _event: AppEvent<Self::Event>) {}
+
+    fn render_warning(&self, warning: Self::Warning) {
+        match warning {
+            ToolchainWarning::RemovedVersionInUse { version } => {
+                eprintln!(
+                    "{} {}",
+                    "⚠".yellow(),
+                    format!(
+                        "this project uses typst {}; it will be downloaded again on the next run",
+                        version
+                    )
+                    .yellow()
+                );
+            }
+        }
+    }
+
+    fn render_error(&self, error: &Self::Error) {
+        render_toolchain_error(error);
+    }
+
+    fn render_result(&self, output: &Self::Output) {
+        println!("{} Removed {}", "🗑".cyan(), output.display());
+    }
+}
+
+struct UsePresenter;
+
+impl CliSpeaker for UsePresenter {
+    type Event = ();
+    type Warning = ();
+    type Error = ToolchainError;
+    type Output = ToolchainUseOutput;
+
+    fn render_event(&self, _event: AppEvent<Self::Event>) {}
+
+    fn render_warning(&self, _warning: Self::Warning) {}
+
+    fn render_error(&self, error: &Self::Error) {
+        render_toolchain_error(error);
+    }
+
+    fn render_result(&self, output: &Self::Output) {
+        println!(
+            "{} typst {} → {}",
+            "✅".green(),
+            output.previous,
+            output.resolved.typst.bold()
+        );
+        if let Some(docs) = &output.resolved.typst_docs {
+            println!("   {}", format!("docs {}", docs).dimmed());
+        }
+        if !output.installed {
+            println!(
+                "   {}",
+                format!(
+                    "not installed yet; it will be downloaded on the next run (or `typstlab toolchain install {}`)",
+                    output.resolved.typst
+                )
+                .dimmed()
+            );
+        }
+    }
+}
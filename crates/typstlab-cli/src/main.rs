@@ -1,13 +1,16 @@
 mod commands;
 mod utils;
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use colored::Colorize;
 use std::path::PathBuf;
 use thiserror::Error;
-use typstlab_app::{BootstrapError, BootstrapEvent, LoadEvent};
+use typstlab_app::{
+    AppContext, BootstrapError, BootstrapEvent, BootstrapWarning, LoadEvent, TestConfig,
+    offline_from_env,
+};
 use typstlab_proto::{Action, AppEvent, CliSpeaker};
-use utils::bootstrap_context;
+use utils::{bootstrap_context, cache_root, current_project_root};
 
 #[derive(Parser, Clone)]
 #[command(author, version, about, long_about = None)]
@@ -17,6 +20,11 @@ pub struct Cli {
 
     #[arg(short, long, global = true)]
     pub verbose: bool,
+
+    /// Use only Typst versions and docs already in the cache; never download
+    /// (also enabled by TYPSTLAB_OFFLINE=1)
+    #[arg(long, global = true)]
+    pub offline: bool,
 }
 
 #[derive(Subcommand, Clone)]
@@ -24,7 +32,7 @@ pub enum Commands {
     Build {
         /// Optional paper IDs or paths to build (if omitted, builds all)
         papers: Vec<String>,
-        /// Output PDF document (default if no other formats are specified)
+        /// Output PDF document (default if no formats are given here or in paper.toml)
         #[arg(long)]
         pdf: bool,
         /// Output PNG images for each page
@@ -36,9 +44,141 @@ pub enum Commands {
         /// Output HTML document
         #[arg(long)]
         html: bool,
+        /// Keep running and rebuild affected papers whenever their sources change
+        #[arg(short, long)]
+        watch: bool,
+        /// Number of parallel compile jobs (defaults to the number of CPUs)
+        #[arg(short, long, value_name = "N")]
+        jobs: Option<usize>,
+        /// Rebuild every target even if its inputs are unchanged since the last build
+        #[arg(short, long)]
+        force: bool,
+        /// PNG resolution in pixels per inch (overrides paper.toml)
+        #[arg(long)]
+        ppi: Option<f32>,
+        /// Pages to export, e.g. `1-3,5` (overrides paper.toml)
+        #[arg(long)]
+        pages: Option<String>,
+        /// Add a `sys.inputs` entry (repeatable, overrides the same key in paper.toml)
+        #[arg(long = "input", value_name = "KEY=VALUE", value_parser = parse_key_value)]
+        inputs: Vec<(String, String)>,
+        /// Additional font directory (repeatable, searched before paper.toml paths)
+        #[arg(long = "font-path", value_name = "DIR")]
+        font_paths: Vec<PathBuf>,
+        /// PDF standard to enforce, e.g. `a-2b` (overrides paper.toml)
+        #[arg(long)]
+        pdf_standard: Option<String>,
+        /// Build profile defined under [profiles] in typstlab.toml
+        #[arg(long, value_name = "NAME")]
+        profile: Option<String>,
+        /// Write a Typst timing trace next to each artifact and show the slowest phases
+        #[arg(long)]
+        timings: bool,
+    },
+    /// Remove build outputs from dist and leftover staging directories
+    Clean {
+        /// Paper IDs whose outputs to remove (if omitted, cleans all)
+        papers: Vec<String>,
+        /// Remove only PDF outputs
+        #[arg(long)]
+        pdf: bool,
+        /// Remove only PNG outputs
+        #[arg(long)]
+        png: bool,
+        /// Remove only SVG outputs
+        #[arg(long)]
+        svg: bool,
+        /// Remove only HTML outputs
+        #[arg(long)]
+        html: bool,
+        /// Remove stale `.tmp` staging directories (dist is left alone unless papers or formats are given)
+        #[arg(long)]
+        cache: bool,
+        /// List what would be removed without deleting anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Compile papers to a throwaway location and report errors and warnings without touching dist
+    #[command(args_conflicts_with_subcommands = true)]
+    Check {
+        #[command(subcommand)]
+        subcommand: Option<CheckCommands>,
+        /// Paper IDs or paths to check (if omitted, checks all)
+        papers: Vec<String>,
+        /// Check PDF output (default if no formats are given here or in paper.toml)
+        #[arg(long)]
+        pdf: bool,
+        /// Check PNG output
+        #[arg(long)]
+        png: bool,
+        /// Check SVG output
+        #[arg(long)]
+        svg: bool,
+        /// Check HTML output
+        #[arg(long)]
+        html: bool,
+        /// Fail when any paper produces compiler warnings
+        #[arg(long)]
+        deny_warnings: bool,
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Render PNG pages for the current sources and a reference, and highlight changed pixels
+    Diff {
+        /// Paper ID or path to compare
+        paper: String,
+        /// Directory of reference PNG pages or a git revision (default: HEAD)
+        #[arg(long, value_name = "DIR|REV")]
+        against: Option<String>,
+    },
+    /// Package a paper into a zip that compiles with plain `typst compile`
+    Export {
+        /// Paper ID or path to export
+        paper: String,
+        /// Destination of the zip archive
+        #[arg(short, long, value_name = "FILE")]
+        out: PathBuf,
+    },
+    /// Run a Typst query against a paper's entry point with its configured inputs
+    Query {
+        /// Paper ID or path to query
+        paper: String,
+        /// Selector, e.g. `heading`, `<label>` or `metadata`
+        selector: String,
+        /// Extract only this field from each element
+        #[arg(long)]
+        field: Option<String>,
+        /// Expect exactly one match and print it without the surrounding array
+        #[arg(long)]
+        one: bool,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = QueryFormatArg::Json)]
+        format: QueryFormatArg,
     },
     /// Show project status
     Status,
+    /// Render papers and templates to PNG and compare them with the reference images in tests/
+    Test {
+        /// Paper or template IDs (if omitted, tests all)
+        targets: Vec<String>,
+        /// Fraction of pixels per page allowed to differ (overrides [test] tolerance)
+        #[arg(long, value_name = "RATIO", value_parser = parse_tolerance)]
+        tolerance: Option<f64>,
+        /// Replace mismatching or missing reference images with the current output
+        #[arg(long)]
+        update: bool,
+    },
+    /// Report page count, word count, outline, figures and bibliography size per paper
+    Stats {
+        /// Paper IDs or paths (if omitted, reports all)
+        papers: Vec<String>,
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List the fonts Typst sees for this project
+    Fonts,
     /// Create a new project
     New {
         /// Project name (optional, defaults to current directory name)
@@ -51,6 +191,11 @@ pub enum Commands {
         #[command(subcommand)]
         subcommand: GenCommands,
     },
+    /// Manage the Typst versions in the shared cache
+    Toolchain {
+        #[command(subcommand)]
+        subcommand: ToolchainCommands,
+    },
     /// Run the MCP server
     Mcp {
         #[command(subcommand)]
@@ -75,6 +220,54 @@ pub enum GenCommands {
     },
 }
 
+#[derive(Subcommand, Clone)]
+pub enum CheckCommands {
+    /// Report missing citation keys, undefined labels and unused bibliography entries
+    Refs {
+        /// Paper IDs or paths to check (if omitted, checks all)
+        papers: Vec<String>,
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+pub enum QueryFormatArg {
+    Json,
+    Yaml,
+}
+
+impl From<QueryFormatArg> for typstlab_base::driver::QueryFormat {
+    fn from(format: QueryFormatArg) -> Self {
+        match format {
+            QueryFormatArg::Json => Self::Json,
+            QueryFormatArg::Yaml => Self::Yaml,
+        }
+    }
+}
+
+#[derive(Subcommand, Clone)]
+pub enum ToolchainCommands {
+    /// List installed and available Typst versions
+    List,
+    /// Download a Typst version into the cache
+    Install {
+        /// Typst version, e.g. 0.14.2
+        version: String,
+    },
+    /// Delete a Typst version from the cache
+    Remove {
+        /// Typst version, e.g. 0.14.2
+        version: String,
+    },
+    /// Switch this project's `[toolchain] typst` to another version
+    Use {
+        /// Typst version, e.g. 0.14.2
+        version: String,
+    },
+}
+
 #[derive(Subcommand, Clone)]
 pub enum McpCommands {
     /// Run the MCP server over stdio for a project root
@@ -84,6 +277,27 @@ pub enum McpCommands {
     },
 }
 
+/// `KEY=VALUE` 形式の引数を分解する
+fn parse_key_value(raw: &str) -> Result<(String, String), String> {
+    match raw.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got '{}'", raw)),
+    }
+}
+
+fn parse_tolerance(raw: &str) -> Result<f64, String> {
+    let value: f64 = raw
+        .parse()
+        .map_err(|_| format!("expected a number, got '{}'", raw))?;
+    if !TestConfig::is_valid_tolerance(value) {
+        return Err(format!(
+            "expected a ratio between 0.0 and 1.0, got '{}'",
+            raw
+        ));
+    }
+    Ok(value)
+}
+
 pub struct CliAction {
     pub cli: Cli,
 }
@@ -93,6 +307,11 @@ pub enum CliEvent {
     Bootstrap(BootstrapEvent),
 }
 
+#[derive(Debug, Clone)]
+pub enum CliWarning {
+    Bootstrap(BootstrapWarning),
+}
+
 #[derive(Error, Debug)]
 pub enum CliError {
     #[error("Initialization failed: {0}")]
@@ -110,16 +329,41 @@ pub enum CliError {
     System(String),
 }
 
+impl CliAction {
+    fn offline(&self) -> bool {
+        self.cli.offline || offline_from_env()
+    }
+
+    /// プロジェクトを読み込みツールチェーンを用意する。`quiet` なら進捗を表示しない
+    fn bootstrap(
+        &self,
+        quiet: bool,
+        monitor: &mut dyn FnMut(AppEvent<CliEvent>),
+        warning: &mut dyn FnMut(CliWarning),
+    ) -> Result<AppContext, Vec<CliError>> {
+        bootstrap_context(
+            self.offline(),
+            &mut |e| {
+                if !quiet {
+                    monitor(e.map_payload(CliEvent::Bootstrap));
+                }
+            },
+            &mut |w| warning(CliWarning::Bootstrap(w)),
+        )
+        .map_err(|error| vec![error])
+    }
+}
+
 impl Action for CliAction {
     type Output = ();
     type Event = CliEvent;
-    type Warning = ();
+    type Warning = CliWarning;
     type Error = CliError;
 
     fn run(
         self,
         monitor: &mut dyn FnMut(AppEvent<CliEvent>),
-        _warning: &mut dyn FnMut(()),
+        warning: &mut dyn FnMut(CliWarning),
     ) -> Result<Self::Output, Vec<Self::Error>> {
         match &self.cli.command {
             Commands::New { name, path } => {
@@ -134,11 +378,18 @@ impl Action for CliAction {
                 png,
                 svg,
                 html,
+                watch,
+                jobs,
+                force,
+                ppi,
+                pages,
+                inputs: sys_inputs,
+                font_paths,
+                pdf_standard,
+                profile,
+                timings,
             } => {
-                let ctx = bootstrap_context(&mut |e| {
-                    monitor(e.map_payload(CliEvent::Bootstrap));
-                })
-                .map_err(|error| vec![error])?;
+                let ctx = self.bootstrap(false, monitor, warning)?;
 
                 let inputs = if papers.is_empty() {
                     None
@@ -146,36 +397,183 @@ impl Action for CliAction {
                     Some(papers.clone())
                 };
 
-                let mut actual_pdf = *pdf;
-                if !actual_pdf && !png && !svg && !html {
-                    actual_pdf = true;
+                // フォーマット指定が無ければ論文ごとの paper.toml に任せる
+                let format = (*pdf || *png || *svg || *html).then_some(typstlab_app::BuildFormat {
+                    pdf: *pdf,
+                    png: *png,
+                    svg: *svg,
+                    html: *html,
+                });
+                let jobs = jobs.unwrap_or_else(typstlab_app::default_jobs);
+                let options = commands::build::BuildOptions {
+                    jobs,
+                    force: *force,
+                    timings: *timings,
+                    overrides: typstlab_app::BuildOverrides {
+                        ppi: *ppi,
+                        pages: pages.clone(),
+                        inputs: sys_inputs.iter().cloned().collect(),
+                        font_paths: font_paths.clone(),
+                        pdf_standard: pdf_standard.clone(),
+                    },
+                    profile: profile.clone(),
+                };
+                if *watch {
+                    commands::build::run_watch(ctx, inputs, format, options, self.cli.verbose)
+                } else {
+                    commands::build::run(ctx, inputs, format, options, self.cli.verbose)
                 }
+                .map_err(|e| vec![CliError::Command(e.to_string())])?;
+            }
 
-                let format = typstlab_app::BuildFormat {
-                    pdf: actual_pdf,
+            Commands::Clean {
+                papers,
+                pdf,
+                png,
+                svg,
+                html,
+                cache,
+                dry_run,
+            } => {
+                let ctx = self.bootstrap(false, monitor, warning)?;
+
+                let formats =
+                    (*pdf || *png || *svg || *html).then_some(typstlab_app::BuildFormat {
+                        pdf: *pdf,
+                        png: *png,
+                        svg: *svg,
+                        html: *html,
+                    });
+                // --cache だけなら dist には触れない
+                let dist = (!*cache || !papers.is_empty() || formats.is_some()).then(|| {
+                    typstlab_app::DistSelection {
+                        papers: (!papers.is_empty()).then(|| papers.clone()),
+                        formats,
+                    }
+                });
+                commands::clean::run(ctx, dist, *cache, *dry_run, self.cli.verbose)
+                    .map_err(|e| vec![CliError::Command(e.to_string())])?;
+            }
+
+            Commands::Check {
+                subcommand: Some(CheckCommands::Refs { papers, json }),
+                ..
+            } => {
+                // JSON を標準出力に出すときは起動時の表示で汚さない
+                let ctx = self.bootstrap(*json, monitor, warning)?;
+
+                let inputs = (!papers.is_empty()).then(|| papers.clone());
+                commands::check::run_refs(ctx, inputs, *json, self.cli.verbose)
+                    .map_err(|e| vec![CliError::Command(e.to_string())])?;
+            }
+
+            Commands::Check {
+                subcommand: None,
+                papers,
+                pdf,
+                png,
+                svg,
+                html,
+                deny_warnings,
+                json,
+            } => {
+                let ctx = self.bootstrap(*json, monitor, warning)?;
+
+                let inputs = (!papers.is_empty()).then(|| papers.clone());
+                let format = (*pdf || *png || *svg || *html).then_some(typstlab_app::BuildFormat {
+                    pdf: *pdf,
                     png: *png,
                     svg: *svg,
                     html: *html,
+                });
+                let options = commands::check::CheckOptions {
+                    format,
+                    deny_warnings: *deny_warnings,
+                    json: *json,
                 };
-                commands::build::run(ctx, inputs, format, self.cli.verbose)
+                commands::check::run(ctx, inputs, options, self.cli.verbose)
+                    .map_err(|e| vec![CliError::Command(e.to_string())])?;
+            }
+
+            Commands::Diff { paper, against } => {
+                let ctx = self.bootstrap(false, monitor, warning)?;
+
+                commands::diff::run(ctx, paper.clone(), against.clone(), self.cli.verbose)
+                    .map_err(|e| vec![CliError::Command(e.to_string())])?;
+            }
+
+            Commands::Export { paper, out } => {
+                let ctx = self.bootstrap(false, monitor, warning)?;
+
+                commands::export::run(ctx, paper.clone(), out.clone(), self.cli.verbose)
                     .map_err(|e| vec![CliError::Command(e.to_string())])?;
             }
 
+            Commands::Query {
+                paper,
+                selector,
+                field,
+                one,
+                format,
+            } => {
+                // 標準出力はクエリ結果だけにする
+                let ctx = self.bootstrap(true, monitor, warning)?;
+
+                let options = commands::query::QueryOptions {
+                    field: field.clone(),
+                    one: *one,
+                    format: (*format).into(),
+                };
+                commands::query::run(
+                    ctx,
+                    paper.clone(),
+                    selector.clone(),
+                    options,
+                    self.cli.verbose,
+                )
+                .map_err(|e| vec![CliError::Command(e.to_string())])?;
+            }
+
             Commands::Status => {
-                let ctx = bootstrap_context(&mut |e| {
-                    monitor(e.map_payload(CliEvent::Bootstrap));
-                })
-                .map_err(|error| vec![error])?;
+                let ctx = self.bootstrap(false, monitor, warning)?;
 
                 commands::status::run(ctx, self.cli.verbose)
                     .map_err(|e| vec![CliError::Command(e.to_string())])?;
             }
 
+            Commands::Stats { papers, json } => {
+                let ctx = self.bootstrap(*json, monitor, warning)?;
+
+                let inputs = (!papers.is_empty()).then(|| papers.clone());
+                commands::stats::run(ctx, inputs, *json, self.cli.verbose)
+                    .map_err(|e| vec![CliError::Command(e.to_string())])?;
+            }
+
+            Commands::Test {
+                targets,
+                tolerance,
+                update,
+            } => {
+                let ctx = self.bootstrap(false, monitor, warning)?;
+
+                let inputs = (!targets.is_empty()).then(|| targets.clone());
+                let options = commands::test::TestOptions {
+                    tolerance: *tolerance,
+                    update: *update,
+                };
+                commands::test::run(ctx, inputs, options, self.cli.verbose)
+                    .map_err(|e| vec![CliError::Command(e.to_string())])?;
+            }
+
+            Commands::Fonts => {
+                let ctx = self.bootstrap(false, monitor, warning)?;
+
+                commands::fonts::run(ctx, self.cli.verbose)
+                    .map_err(|e| vec![CliError::Command(e.to_string())])?;
+            }
+
             Commands::Gen { subcommand } => {
-                let ctx = bootstrap_context(&mut |e| {
-                    monitor(e.map_payload(CliEvent::Bootstrap));
-                })
-                .map_err(|error| vec![error])?;
+                let ctx = self.bootstrap(false, monitor, warning)?;
 
                 match subcommand {
                     GenCommands::Paper { id, template } => {
@@ -194,6 +592,33 @@ impl Action for CliAction {
                 }
             }
 
+            Commands::Toolchain { subcommand } => {
+                let cache_root = cache_root().map_err(|error| vec![error])?;
+                let project_root = current_project_root();
+
+                match subcommand {
+                    ToolchainCommands::List => {
+                        commands::toolchain::list(cache_root, project_root.ok())
+                    }
+                    ToolchainCommands::Install { version } => commands::toolchain::install(
+                        cache_root,
+                        project_root.ok(),
+                        version.clone(),
+                        self.offline(),
+                        self.cli.verbose,
+                    ),
+                    ToolchainCommands::Remove { version } => {
+                        commands::toolchain::remove(cache_root, project_root.ok(), version.clone())
+                    }
+                    ToolchainCommands::Use { version } => commands::toolchain::use_version(
+                        cache_root,
+                        project_root.map_err(|error| vec![error])?,
+                        version.clone(),
+                    ),
+                }
+                .map_err(|e| vec![CliError::Command(e.to_string())])?;
+            }
+
             Commands::Mcp { subcommand } => match subcommand {
                 McpCommands::Stdio { root } => {
                     commands::mcp::run_stdio(root.clone())
@@ -210,7 +635,7 @@ struct RootPresenter;
 
 impl CliSpeaker for RootPresenter {
     type Event = CliEvent;
-    type Warning = ();
+    type Warning = CliWarning;
     type Error = CliError;
     type Output = ();
 
@@ -235,6 +660,26 @@ impl CliSpeaker for RootPresenter {
                             version
                         );
                     }
+                    BootstrapEvent::ResolvingToolchain(ToolchainResolveEvent::ResolvingTypst {
+                        version,
+                        event: ResolveEvent::WaitingForLock { .. },
+                    }) => {
+                        println!(
+                            "{} Typst {} is being installed by another process, waiting...",
+                            "⏳".yellow(),
+                            version
+                        );
+                    }
+                    BootstrapEvent::ResolvingToolchain(ToolchainResolveEvent::ResolvingDocs {
+                        version,
+                        event: ResolveEvent::WaitingForLock { .. },
+                    }) => {
+                        println!(
+                            "{} Typst docs {} are being installed by another process, waiting...",
+                            "⏳".yellow(),
+                            version
+                        );
+                    }
                     BootstrapEvent::Ready => {
                         println!("{} Environment ready.", "✅".green());
                     }
@@ -244,7 +689,13 @@ impl CliSpeaker for RootPresenter {
         }
     }
 
-    fn render_warning(&self, _warning: ()) {}
+    fn render_warning(&self, warning: CliWarning) {
+        match warning {
+            CliWarning::Bootstrap(BootstrapWarning::Toolchain(warning)) => {
+                commands::toolchain::render_resolve_warning(warning);
+            }
+        }
+    }
 
     fn render_error(&self, error: &CliError) {
         eprintln!("\n{} {}", "💥 ERROR:".red().bold(), error);
@@ -265,7 +716,7 @@ fn main() {
                 presenter.render_event(event);
             }
         },
-        &mut |_| {},
+        &mut |warning| presenter.render_warning(warning),
     ) {
         Ok(out) => presenter.render_result(&out),
         Err(errors) => {
@@ -1,6 +1,8 @@
 use crate::CliError;
 use std::path::{Path, PathBuf};
-use typstlab_app::{AppContext, BootstrapAction, BootstrapError};
+use typstlab_app::{
+    AppContext, BootstrapAction, BootstrapError, BootstrapWarning, mirrors_from_env,
+};
 use typstlab_proto::{Action, AppEvent, PROJECT_SETTING_FILE};
 
 pub fn find_project_root(start: &Path) -> Result<PathBuf, CliError> {
@@ -30,25 +32,35 @@ pub fn find_project_root(start: &Path) -> Result<PathBuf, CliError> {
     }
 }
 
-pub fn bootstrap_context(
-    monitor: &mut dyn FnMut(AppEvent<typstlab_app::BootstrapEvent>),
-) -> Result<AppContext, CliError> {
+/// カレントディレクトリから見つかるプロジェクトのルート
+pub fn current_project_root() -> Result<PathBuf, CliError> {
     let current_dir = std::env::current_dir().map_err(|error| {
         CliError::System(format!("Could not identify current directory: {}", error))
     })?;
-    let project_root = find_project_root(&current_dir)?;
+    find_project_root(&current_dir)
+}
 
-    let cache_root = dirs::cache_dir()
+/// Typst や docs を置く共有キャッシュ
+pub fn cache_root() -> Result<PathBuf, CliError> {
+    Ok(dirs::cache_dir()
         .ok_or_else(|| CliError::System("Could not find cache directory".to_string()))?
-        .join("typstlab");
+        .join("typstlab"))
+}
 
+pub fn bootstrap_context(
+    offline: bool,
+    monitor: &mut dyn FnMut(AppEvent<typstlab_app::BootstrapEvent>),
+    warning: &mut dyn FnMut(BootstrapWarning),
+) -> Result<AppContext, CliError> {
     let bootstrap = BootstrapAction {
-        project_root,
-        cache_root,
+        project_root: current_project_root()?,
+        cache_root: cache_root()?,
+        offline,
+        mirrors: mirrors_from_env().map_err(CliError::Bootstrap)?,
     };
 
     bootstrap
-        .run(monitor, &mut |_| {})
+        .run(monitor, warning)
         .map_err(collapse_bootstrap_errors)
 }
 
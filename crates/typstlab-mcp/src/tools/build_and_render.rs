@@ -1,7 +1,9 @@
 use base64::{Engine as _, engine::general_purpose::STANDARD};
 use rmcp::model::{CallToolResult, Content};
-use typstlab_app::{AppContext, BuildAction, BuildFormat};
-use typstlab_proto::{Action, Entity};
+use serde::Serialize;
+use std::path::PathBuf;
+use typstlab_app::{AppContext, BuildAction, BuildDiagnostic, BuildError, BuildFormat};
+use typstlab_proto::{Action, Artifact, Entity};
 
 pub fn execute(ctx: AppContext, paper_id: String) -> Result<CallToolResult, String> {
     use typstlab_base::driver::TypstDriver;
@@ -16,12 +18,19 @@ pub fn execute(ctx: AppContext, paper_id: String) -> Result<CallToolResult, Stri
         ctx.loaded_project,
         driver,
         Some(vec![paper_id.clone()]),
-        format,
+        Some(format),
     );
-    let results = action.run(&mut |_| {}, &mut |_| {}).map_err(|errors| {
-        let err_msgs: Vec<_> = errors.into_iter().map(|e| e.to_string()).collect();
-        format!("Build failed:\n{}", err_msgs.join("\n"))
-    })?;
+    let results = match action.run(&mut |_| {}, &mut |_| {}) {
+        Ok(results) => results,
+        // コンパイルエラーは診断を構造化したままツールエラーとして返す
+        Err(errors) if errors.iter().any(is_compile_failure) => {
+            return Ok(format_build_failure(&paper_id, &errors));
+        }
+        Err(errors) => {
+            let err_msgs: Vec<_> = errors.into_iter().map(|e| e.to_string()).collect();
+            return Err(format!("Build failed:\n{}", err_msgs.join("\n")));
+        }
+    };
 
     let target_dist = results
         .into_iter()
@@ -32,6 +41,56 @@ pub fn execute(ctx: AppContext, paper_id: String) -> Result<CallToolResult, Stri
     format_png_response(&paper_id, &png_paths)
 }
 
+#[derive(Debug, Serialize)]
+struct McpBuildFailure<'a> {
+    paper_id: &'a str,
+    artifacts: Vec<McpFailedArtifact<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct McpFailedArtifact<'a> {
+    root: PathBuf,
+    diagnostics: &'a [BuildDiagnostic],
+    /// 診断として解釈できなかった場合の生の出力
+    #[serde(skip_serializing_if = "Option::is_none")]
+    raw_error: Option<&'a str>,
+}
+
+fn is_compile_failure(error: &BuildError) -> bool {
+    matches!(error, BuildError::PaperBuildError(_))
+}
+
+/// コンパイル失敗を診断付きの構造化エラーに整形する純粋な関数
+pub(crate) fn format_build_failure(paper_id: &str, errors: &[BuildError]) -> CallToolResult {
+    let artifacts = errors
+        .iter()
+        .filter_map(|error| match error {
+            BuildError::PaperBuildError(artifact) => Some(McpFailedArtifact {
+                root: artifact.root(),
+                diagnostics: &artifact.diagnostics,
+                raw_error: artifact
+                    .diagnostics
+                    .is_empty()
+                    .then_some(artifact.error_message.as_deref())
+                    .flatten(),
+            }),
+            _ => None,
+        })
+        .collect();
+
+    let failure = McpBuildFailure {
+        paper_id,
+        artifacts,
+    };
+    match serde_json::to_value(&failure) {
+        Ok(value) => CallToolResult::structured_error(value),
+        Err(error) => CallToolResult::error(vec![Content::text(format!(
+            "failed to serialize build failure: {}",
+            error
+        ))]),
+    }
+}
+
 /// png_paths と paper_id からレスポンスを整形する純粋な関数
 pub(crate) fn format_png_response(
     paper_id: &str,
@@ -64,6 +123,50 @@ mod tests {
     use serde_json::json;
     use tempfile::TempDir;
 
+    // --- format_build_failure のユニットテスト ---
+
+    #[test]
+    fn test_format_build_failure_exposes_diagnostics() {
+        use typstlab_app::{BuildArtifact, DiagnosticSeverity};
+
+        let artifact = BuildArtifact {
+            root_name: PathBuf::from("p01").join("png"),
+            absolute_path: PathBuf::from("/project/dist/p01/png"),
+            success: false,
+            error_message: Some("main.typ:3:2: error: unknown variable: foo".to_string()),
+            diagnostics: vec![BuildDiagnostic {
+                severity: DiagnosticSeverity::Error,
+                file: Some(PathBuf::from("main.typ")),
+                line: Some(3),
+                column: Some(2),
+                message: "unknown variable: foo".to_string(),
+                hints: vec![],
+            }],
+        };
+
+        let result = format_build_failure("p01", &[BuildError::PaperBuildError(artifact)]);
+        let actual = serde_json::to_value(&result).unwrap();
+
+        assert_eq!(actual["isError"], json!(true));
+        assert_eq!(
+            actual["structuredContent"],
+            json!({
+                "paper_id": "p01",
+                "artifacts": [{
+                    "root": "p01/png",
+                    "diagnostics": [{
+                        "severity": "error",
+                        "file": "main.typ",
+                        "line": 3,
+                        "column": 2,
+                        "message": "unknown variable: foo",
+                        "hints": []
+                    }]
+                }]
+            })
+        );
+    }
+
     // --- format_png_response のユニットテスト ---
 
     #[test]
@@ -1,7 +1,7 @@
 use rmcp::ErrorData as McpError;
 use std::path::PathBuf;
-use typstlab_app::AppContext;
 use typstlab_app::actions::bootstrap::BootstrapAction;
+use typstlab_app::{AppContext, mirrors_from_env, offline_from_env};
 use typstlab_proto::Action;
 
 pub fn bootstrap_context(project_root: PathBuf) -> Result<AppContext, String> {
@@ -12,6 +12,8 @@ pub fn bootstrap_context(project_root: PathBuf) -> Result<AppContext, String> {
     BootstrapAction {
         project_root,
         cache_root,
+        offline: offline_from_env(),
+        mirrors: mirrors_from_env().map_err(|error| error.to_string())?,
     }
     .run(&mut |_| {}, &mut |_| {})
     .map_err(format_bootstrap_errors)
@@ -1,2 +1,3 @@
 pub mod check_docs_schema;
 pub mod json_check;
+pub mod update_checksums;
@@ -0,0 +1,158 @@
+use anyhow::{Context, Result, anyhow};
+use serde::Serialize;
+use serde_json::ser::PrettyFormatter;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use typstlab_base::digest::Sha256Reader;
+use typstlab_base::install::{HttpProvider, InstallProvider};
+use typstlab_base::link_resolver::{
+    DOCS_ASSET, DOCS_URL_TEMPLATE, TYPST_URL_TEMPLATE, typst_release_assets,
+};
+use typstlab_base::{known_docs_versions, known_typst_versions};
+
+type Checksums = BTreeMap<String, BTreeMap<String, String>>;
+
+/// 互換表に載っている全バージョンの配布物を取得し、`sha256` 表を書き換える
+pub fn run() -> Result<()> {
+    let base_dir = Path::new("crates/typstlab-base/src/version_resolver_jsons");
+    let provider = HttpProvider::try_new()?;
+    let mut skipped = 0;
+
+    let mut typst = Checksums::new();
+    for version in known_typst_versions()? {
+        for asset in typst_release_assets() {
+            let url = release_url(TYPST_URL_TEMPLATE, &version, &asset);
+            match fetch_sha256(&provider, &url) {
+                Ok(digest) => {
+                    typst
+                        .entry(version.clone())
+                        .or_default()
+                        .insert(asset, digest);
+                }
+                Err(error) => {
+                    eprintln!("⚠ skipped {}: {:#}", url, error);
+                    skipped += 1;
+                }
+            }
+        }
+    }
+    write_checksums(&base_dir.join("typst.json"), &typst)?;
+
+    let mut docs = Checksums::new();
+    for version in known_docs_versions()? {
+        let url = release_url(DOCS_URL_TEMPLATE, &version, DOCS_ASSET);
+        match fetch_sha256(&provider, &url) {
+            Ok(digest) => {
+                docs.entry(version)
+                    .or_default()
+                    .insert(DOCS_ASSET.to_string(), digest);
+            }
+            Err(error) => {
+                eprintln!("⚠ skipped {}: {:#}", url, error);
+                skipped += 1;
+            }
+        }
+    }
+    write_checksums(&base_dir.join("type_docs.json"), &docs)?;
+
+    if skipped > 0 {
+        return Err(anyhow!("{} asset(s) could not be downloaded", skipped));
+    }
+    Ok(())
+}
+
+fn release_url(template: &str, version: &str, asset: &str) -> String {
+    template
+        .replace("{version}", version)
+        .replace("{asset}", asset)
+}
+
+fn fetch_sha256(provider: &HttpProvider, url: &str) -> Result<String> {
+    let (reader, _) = provider.fetch(url)?;
+    let digest = Sha256Reader::new(reader)
+        .finish()
+        .with_context(|| format!("failed to read {}", url))?;
+    println!("✅ {} {}", digest, url);
+    Ok(digest)
+}
+
+fn write_checksums(path: &Path, checksums: &Checksums) -> Result<()> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let updated = replace_checksums(&content, checksums)
+        .with_context(|| format!("failed to update {}", path.display()))?;
+    fs::write(path, updated).with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// 他のキーの並びや書式を保ったまま、`"sha256": { ... }` の部分だけを差し替える
+fn replace_checksums(content: &str, checksums: &Checksums) -> Result<String> {
+    let key = content
+        .find("\"sha256\"")
+        .ok_or_else(|| anyhow!("no \"sha256\" table"))?;
+    let open = key
+        + content[key..]
+            .find('{')
+            .ok_or_else(|| anyhow!("\"sha256\" is not an object"))?;
+
+    let mut depth = 0;
+    let mut close = None;
+    for (offset, ch) in content[open..].char_indices() {
+        match ch {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(open + offset);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let close = close.ok_or_else(|| anyhow!("unterminated \"sha256\" table"))?;
+
+    let mut rendered = Vec::new();
+    let mut serializer = serde_json::Serializer::with_formatter(
+        &mut rendered,
+        PrettyFormatter::with_indent(b"    "),
+    );
+    checksums.serialize(&mut serializer)?;
+    // 表はトップレベルのオブジェクトの 1 段内側に置かれている
+    let rendered = String::from_utf8(rendered)?.replace('\n', "\n    ");
+
+    Ok(format!(
+        "{}{}{}",
+        &content[..open],
+        rendered,
+        &content[close + 1..]
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replace_checksums_keeps_surrounding_json() {
+        let content = "{\n    \"0.14.2\": [\n        \"0.14.2\"\n    ],\n    \"sha256\": {}\n}\n";
+        let mut checksums = Checksums::new();
+        checksums
+            .entry("0.14.2".to_string())
+            .or_default()
+            .insert("docs.json".to_string(), "ab".repeat(32));
+
+        let updated = replace_checksums(content, &checksums).unwrap();
+
+        assert_eq!(
+            updated,
+            format!(
+                "{{\n    \"0.14.2\": [\n        \"0.14.2\"\n    ],\n    \"sha256\": {{\n        \"0.14.2\": {{\n            \"docs.json\": \"{}\"\n        }}\n    }}\n}}\n",
+                "ab".repeat(32)
+            )
+        );
+        let value: serde_json::Value = serde_json::from_str(&updated).unwrap();
+        assert!(value["sha256"]["0.14.2"]["docs.json"].is_string());
+    }
+}
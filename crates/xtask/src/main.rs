@@ -18,6 +18,8 @@ enum Commands {
     JsonCheck,
     /// Check Typst docs.json files against docs_parser schema.rs
     CheckDocsSchema { files: Vec<PathBuf> },
+    /// Download every listed Typst release and docs.json and refresh the sha256 tables
+    UpdateChecksums,
 }
 
 fn main() -> Result<()> {
@@ -26,5 +28,6 @@ fn main() -> Result<()> {
     match cli.command {
         Commands::JsonCheck => commands::json_check::run(),
         Commands::CheckDocsSchema { files } => commands::check_docs_schema::run(&files),
+        Commands::UpdateChecksums => commands::update_checksums::run(),
     }
 }